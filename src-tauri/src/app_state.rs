@@ -5,6 +5,10 @@ use anyhow::Result;
 
 use crate::database::{Database, repository::RepositoryService, workspace::WorkspaceService};
 use crate::services::{ScriptExecutor, TerminalService};
+use crate::services::command_history::CommandHistoryStore;
+use crate::services::execution_journal::ExecutionJournal;
+use crate::services::maintenance::MaintenanceService;
+use crate::services::shutdown::ShutdownCoordinator;
 
 pub struct AppState {
     pub database: Arc<Database>,
@@ -12,6 +16,9 @@ pub struct AppState {
     pub workspace_service: Arc<WorkspaceService>,
     pub script_executor: Arc<ScriptExecutor>,
     pub terminal_service: Arc<TerminalService>,
+    pub shutdown: Arc<ShutdownCoordinator>,
+    pub journal: Arc<ExecutionJournal>,
+    pub maintenance: Arc<MaintenanceService>,
     pub data_dir: Arc<RwLock<PathBuf>>,
 }
 
@@ -27,8 +34,18 @@ impl AppState {
         // 创建服务
         let repository_service = Arc::new(RepositoryService::new(database.pool().clone()));
         let workspace_service = Arc::new(WorkspaceService::new(database.pool().clone()));
-        let script_executor = Arc::new(ScriptExecutor::new());
-        let terminal_service = Arc::new(TerminalService::new());
+        let journal = Arc::new(ExecutionJournal::new(data_dir.join("journal")));
+        let script_executor = Arc::new(ScriptExecutor::new().with_journal(journal.clone()));
+        let command_history = Arc::new(CommandHistoryStore::new(data_dir.join("command_history")));
+        let terminal_service = Arc::new(
+            TerminalService::with_command_history(command_history).with_journal(journal.clone()),
+        );
+
+        // 恢复上次异常退出时仍在运行的脚本和终端
+        let interrupted = journal.interrupted();
+        script_executor.restore_interrupted(&interrupted);
+        terminal_service.restore_interrupted(&interrupted);
+        let shutdown = Arc::new(ShutdownCoordinator::new(terminal_service.clone(), script_executor.clone()));
         
         Ok(Self {
             database,
@@ -36,6 +53,9 @@ impl AppState {
             workspace_service,
             script_executor,
             terminal_service,
+            shutdown,
+            journal,
+            maintenance: Arc::new(MaintenanceService::with_settings_file(data_dir.join("maintenance.json"))),
             data_dir: Arc::new(RwLock::new(data_dir)),
         })
     }
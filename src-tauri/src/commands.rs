@@ -1,4 +1,5 @@
 use tauri::State;
+use tauri::ipc::Channel;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -6,11 +7,21 @@ use std::path::PathBuf;
 use crate::app_state::AppState;
 use crate::database::models::{Repository, Workspace, CreateRepositoryRequest, CreateWorkspaceRequest, UpdateRepositoryRequest};
 use crate::services::{GitService, RepositoryManagerService, WorkspaceManagerService, ScriptExecutor, TerminalService};
-use crate::services::git_service::{GitStatus, GitBranch, WorktreeInfo};
+use crate::services::git_service::{GitStatus, GitBranch, WorktreeInfo, WorktreeOptions, GitHunk, CommitOptions, CommitLogQuery, CommitLogPage, GitCommitDetails, GitOperationState, GitConflict, ConflictResolution, GitStash, StashOptions};
+use crate::services::git_diff::{DiffTarget, GitDiff, GitDiffOptions};
+use crate::services::git_remote::{FetchResult, PullResult, PullStrategy, PushOptions, PushResult, RemoteProgress, RemoteProgressCallback};
 use crate::services::repository_service::{RepositoryConfig, RepositoryValidationResult, AddRepositoryRequest, RepositoryScript};
 use crate::services::workspace_service::{WorkspaceMetadata, WorkspaceInfo, CreateWorkspaceRequest as CreateManagedWorkspaceRequest, ArchiveWorkspaceRequest, WorkspaceStatus};
 use crate::services::script_executor::{ScriptExecution, ScriptExecutionResult, ExecutionStatus};
-use crate::services::terminal_service::{TerminalSession, TerminalOutput, CommandExecution, TerminalStatus, OutputType};
+use crate::services::terminal_service::{TerminalSession, TerminalOutput, CommandExecution, CommandExecutionResult, TerminalStatus, OutputType, ShellConfig, ShellOptions, TerminalSignal, ControlKey, SearchScope, TerminalSearchResult, TerminalWorkspace, TerminalLimits, WorkspacePort};
+use crate::services::process_inspector::ProcessTree;
+use crate::services::command_history::{CommandHistoryEntry, HistoryMatchMode};
+use crate::services::terminal_history::{HistoryChunk, HistorySearchQuery};
+use crate::services::terminal_recording::{self, RecordingEvent, RecordingInfo, ReplayOptions};
+use crate::services::shell_integration::TerminalCommand;
+use crate::services::shutdown::{ShutdownStatus, DEFAULT_SHUTDOWN_TIMEOUT};
+use crate::services::execution_journal::{InterruptedExecution, JournalRecord};
+use crate::services::maintenance::{MaintenanceRun, MaintenanceSettings, RepositoryMaintenance};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -126,15 +137,60 @@ pub async fn get_workspaces_by_repository(
     }
 }
 
+/// 数据库工作区对应的工作区元数据ID，终端按元数据ID关联工作区
+async fn managed_workspace_id(state: &AppState, workspace_id: &str) -> Result<Option<String>, String> {
+    let workspace = match state.workspace_service.get_by_id(workspace_id).await.map_err(|e| e.to_string())? {
+        Some(workspace) => workspace,
+        None => return Ok(None),
+    };
+    let repository = match state.repository_service.get_by_id(&workspace.repository_id).await.map_err(|e| e.to_string())? {
+        Some(repository) => repository,
+        None => return Ok(None),
+    };
+
+    let workspace_path = PathBuf::from(&workspace.path);
+    let canonical_path = workspace_path.canonicalize().ok();
+    // 仓库目录已不存在时没有可关联的终端
+    let metadata = match WorkspaceManagerService::list_workspaces(std::path::Path::new(&repository.path)) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(None),
+    };
+    Ok(metadata
+        .into_iter()
+        .find(|m| {
+            m.workspace_path == workspace_path
+                || (canonical_path.is_some() && m.workspace_path.canonicalize().ok() == canonical_path)
+        })
+        .map(|m| m.id))
+}
+
 #[tauri::command]
 pub async fn archive_workspace(
     state: State<'_, AppState>,
     id: String,
+    close_terminals: Option<bool>,
 ) -> Result<ApiResponse<Option<Workspace>>, String> {
-    match state.workspace_service.archive(&id).await {
-        Ok(workspace) => Ok(ApiResponse::success(workspace)),
-        Err(e) => Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e))),
+    let terminal_workspace_id = match managed_workspace_id(&state, &id).await {
+        Ok(workspace_id) => workspace_id,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e))),
+    };
+    if let Some(ref workspace_id) = terminal_workspace_id {
+        if let Err(e) = state.terminal_service.check_workspace_terminals(workspace_id, close_terminals.unwrap_or(true)) {
+            return Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e)));
+        }
+    }
+
+    let workspace = match state.workspace_service.archive(&id).await {
+        Ok(workspace) => workspace,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e))),
+    };
+
+    if let Some(ref workspace_id) = terminal_workspace_id {
+        if let Err(e) = state.terminal_service.close_workspace_terminals(workspace_id).await {
+            eprintln!("警告: 关闭工作区终端失败: {}", e);
+        }
     }
+    Ok(ApiResponse::success(workspace))
 }
 
 #[tauri::command]
@@ -152,11 +208,29 @@ pub async fn restore_workspace(
 pub async fn delete_workspace(
     state: State<'_, AppState>,
     id: String,
+    close_terminals: Option<bool>,
 ) -> Result<ApiResponse<bool>, String> {
-    match state.workspace_service.delete(&id).await {
-        Ok(deleted) => Ok(ApiResponse::success(deleted)),
-        Err(e) => Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e))),
+    let terminal_workspace_id = match managed_workspace_id(&state, &id).await {
+        Ok(workspace_id) => workspace_id,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e))),
+    };
+    if let Some(ref workspace_id) = terminal_workspace_id {
+        if let Err(e) = state.terminal_service.check_workspace_terminals(workspace_id, close_terminals.unwrap_or(true)) {
+            return Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e)));
+        }
+    }
+
+    let deleted = match state.workspace_service.delete(&id).await {
+        Ok(deleted) => deleted,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e))),
+    };
+
+    if let Some(ref workspace_id) = terminal_workspace_id {
+        if let Err(e) = state.terminal_service.close_workspace_terminals(workspace_id).await {
+            eprintln!("警告: 关闭工作区终端失败: {}", e);
+        }
     }
+    Ok(ApiResponse::success(deleted))
 }
 
 // Git Operations Commands
@@ -183,8 +257,15 @@ pub async fn create_git_worktree(
     worktree_name: String,
     worktree_path: String,
     branch_name: Option<String>,
+    start_point: Option<String>,
+    detach: Option<bool>,
 ) -> Result<ApiResponse<bool>, String> {
-    match GitService::create_worktree(&repo_path, &worktree_name, &worktree_path, branch_name.as_deref()) {
+    let options = WorktreeOptions {
+        branch: branch_name,
+        start_point,
+        detach: detach.unwrap_or(false),
+    };
+    match GitService::create_worktree_with_options(&repo_path, &worktree_name, &worktree_path, &options) {
         Ok(_) => Ok(ApiResponse::success(true)),
         Err(e) => Ok(ApiResponse::error(format!("Failed to create worktree: {}", e))),
     }
@@ -232,6 +313,75 @@ pub async fn create_git_branch(
     }
 }
 
+#[tauri::command]
+pub async fn get_git_branches_with_merge_status(
+    repo_path: String,
+    base_branch: Option<String>,
+) -> Result<ApiResponse<Vec<GitBranch>>, String> {
+    let base = match base_branch {
+        Some(base) => Ok(base),
+        None => RepositoryManagerService::load_repository_config(&repo_path).and_then(|config| {
+            config
+                .default_branch
+                .ok_or_else(|| anyhow::anyhow!("Repository has no default branch configured"))
+        }),
+    };
+
+    match base.and_then(|base| GitService::get_branches_with_merge_status(&repo_path, &base)) {
+        Ok(branches) => Ok(ApiResponse::success(branches)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get branches: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn delete_git_branch(
+    repo_path: String,
+    branch_name: String,
+    force: Option<bool>,
+    delete_remote: Option<bool>,
+) -> Result<ApiResponse<()>, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        GitService::delete_branch(&repo_path, &branch_name, force.unwrap_or(false), delete_remote.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to delete branch: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn delete_git_remote_branch(
+    repo_path: String,
+    remote: String,
+    branch_name: String,
+) -> Result<ApiResponse<()>, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        GitService::delete_remote_branch(&repo_path, &remote, &branch_name)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to delete remote branch: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn rename_git_branch(
+    repo_path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<ApiResponse<Vec<WorkspaceMetadata>>, String> {
+    match WorkspaceManagerService::rename_branch(std::path::Path::new(&repo_path), &old_name, &new_name) {
+        Ok(workspaces) => Ok(ApiResponse::success(workspaces)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to rename branch: {}", e))),
+    }
+}
+
 #[tauri::command]
 pub async fn is_git_repository(path: String) -> Result<ApiResponse<bool>, String> {
     let is_repo = GitService::is_git_repository(&path);
@@ -273,6 +423,396 @@ pub async fn get_global_gitignore() -> Result<ApiResponse<Option<String>>, Strin
     }
 }
 
+#[tauri::command]
+pub async fn stage_git_paths(repo_path: String, paths: Vec<String>) -> Result<ApiResponse<bool>, String> {
+    match GitService::stage_paths(&repo_path, &paths) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to stage paths: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn unstage_git_paths(repo_path: String, paths: Vec<String>) -> Result<ApiResponse<bool>, String> {
+    match GitService::unstage_paths(&repo_path, &paths) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to unstage paths: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn discard_git_changes(repo_path: String, paths: Vec<String>) -> Result<ApiResponse<bool>, String> {
+    match GitService::discard_changes(&repo_path, &paths) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to discard changes: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_git_file_hunks(
+    repo_path: String,
+    file_path: String,
+    staged: bool,
+) -> Result<ApiResponse<Vec<GitHunk>>, String> {
+    match GitService::get_file_hunks(&repo_path, &file_path, staged) {
+        Ok(hunks) => Ok(ApiResponse::success(hunks)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get file hunks: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn stage_git_hunk(
+    repo_path: String,
+    file_path: String,
+    hunk_header: String,
+) -> Result<ApiResponse<bool>, String> {
+    match GitService::stage_hunk(&repo_path, &file_path, &hunk_header) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to stage hunk: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn unstage_git_hunk(
+    repo_path: String,
+    file_path: String,
+    hunk_header: String,
+) -> Result<ApiResponse<bool>, String> {
+    match GitService::unstage_hunk(&repo_path, &file_path, &hunk_header) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to unstage hunk: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn commit_git_changes(
+    repo_path: String,
+    options: CommitOptions,
+) -> Result<ApiResponse<String>, String> {
+    match GitService::commit(&repo_path, &options) {
+        Ok(commit_id) => Ok(ApiResponse::success(commit_id)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to commit changes: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_git_diff(
+    repo_path: String,
+    target: DiffTarget,
+    options: Option<GitDiffOptions>,
+) -> Result<ApiResponse<GitDiff>, String> {
+    match GitService::get_diff(&repo_path, &target, &options.unwrap_or_default()) {
+        Ok(diff) => Ok(ApiResponse::success(diff)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get diff: {}", e))),
+    }
+}
+
+/// 比较工作区分支与源分支，未指定源分支时使用仓库配置的默认分支
+#[tauri::command]
+pub async fn get_workspace_branch_diff(
+    repo_path: String,
+    workspace_id: String,
+    base_branch: Option<String>,
+    options: Option<GitDiffOptions>,
+) -> Result<ApiResponse<GitDiff>, String> {
+    let result = WorkspaceManagerService::load_workspace_metadata(std::path::Path::new(&repo_path), &workspace_id)
+        .and_then(|metadata| {
+            let base = match base_branch {
+                Some(base) => base,
+                None => RepositoryManagerService::load_repository_config(&repo_path)?
+                    .default_branch
+                    .ok_or_else(|| anyhow::anyhow!("Repository has no default branch configured"))?,
+            };
+            GitService::get_diff(
+                &metadata.workspace_path,
+                &DiffTarget::Branch { base },
+                &options.unwrap_or_default(),
+            )
+        });
+
+    match result {
+        Ok(diff) => Ok(ApiResponse::success(diff)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get workspace diff: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_commit_log(
+    repo_path: String,
+    query: Option<CommitLogQuery>,
+) -> Result<ApiResponse<CommitLogPage>, String> {
+    match GitService::get_commit_log(&repo_path, &query.unwrap_or_default()) {
+        Ok(page) => Ok(ApiResponse::success(page)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get commit log: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_commit_details(
+    repo_path: String,
+    commit_id: String,
+) -> Result<ApiResponse<GitCommitDetails>, String> {
+    match GitService::get_commit_details(&repo_path, &commit_id) {
+        Ok(details) => Ok(ApiResponse::success(details)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get commit details: {}", e))),
+    }
+}
+
+/// 将远程操作的进度转发到前端
+fn remote_progress(on_progress: Channel<RemoteProgress>) -> RemoteProgressCallback {
+    Box::new(move |progress| {
+        let _ = on_progress.send(progress);
+    })
+}
+
+#[tauri::command]
+pub async fn fetch_git_remote(
+    repo_path: String,
+    remote: Option<String>,
+    prune: Option<bool>,
+    on_progress: Channel<RemoteProgress>,
+) -> Result<ApiResponse<FetchResult>, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        GitService::fetch(&repo_path, remote.as_deref(), prune.unwrap_or(false), Some(remote_progress(on_progress)))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(result) => Ok(ApiResponse::success(result)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to fetch: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn pull_git_branch(
+    repo_path: String,
+    strategy: Option<PullStrategy>,
+    on_progress: Channel<RemoteProgress>,
+) -> Result<ApiResponse<PullResult>, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        GitService::pull(&repo_path, strategy.unwrap_or_default(), Some(remote_progress(on_progress)))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(result) => Ok(ApiResponse::success(result)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to pull: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn push_git_branch(
+    repo_path: String,
+    options: Option<PushOptions>,
+    on_progress: Channel<RemoteProgress>,
+) -> Result<ApiResponse<PushResult>, String> {
+    let options = options.unwrap_or_default();
+    let result = tokio::task::spawn_blocking(move || {
+        GitService::push(&repo_path, &options, Some(remote_progress(on_progress)))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(result) => Ok(ApiResponse::success(result)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to push: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn update_workspace_from_source(
+    state: State<'_, AppState>,
+    repo_path: String,
+    workspace_id: String,
+    source_branch: Option<String>,
+    strategy: Option<PullStrategy>,
+) -> Result<ApiResponse<PullResult>, String> {
+    // 未指定时优先使用仓库记录的源分支，其次是仓库配置的默认分支
+    let source_branch = match source_branch {
+        Some(branch) => Some(branch),
+        None => match state.repository_service.get_by_path(&repo_path).await {
+            Ok(repository) => repository.and_then(|r| r.source_branch),
+            Err(e) => return Ok(ApiResponse::error(format!("Failed to get repository: {}", e))),
+        },
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        WorkspaceManagerService::update_from_source(
+            std::path::Path::new(&repo_path),
+            &workspace_id,
+            source_branch.as_deref(),
+            strategy.unwrap_or(PullStrategy::Merge),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(result) => Ok(ApiResponse::success(result)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to update workspace: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_git_operation_state(repo_path: String) -> Result<ApiResponse<GitOperationState>, String> {
+    match GitService::get_operation_state(&repo_path) {
+        Ok(state) => Ok(ApiResponse::success(state)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get operation state: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn abort_git_operation(repo_path: String) -> Result<ApiResponse<()>, String> {
+    match GitService::abort_operation(&repo_path) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to abort operation: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn continue_git_operation(repo_path: String) -> Result<ApiResponse<PullResult>, String> {
+    match GitService::continue_operation(&repo_path) {
+        Ok(result) => Ok(ApiResponse::success(result)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to continue operation: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_git_conflicts(repo_path: String) -> Result<ApiResponse<Vec<GitConflict>>, String> {
+    match GitService::get_conflicts(&repo_path) {
+        Ok(conflicts) => Ok(ApiResponse::success(conflicts)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get conflicts: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn resolve_git_conflict(
+    repo_path: String,
+    file_path: String,
+    resolution: ConflictResolution,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    match GitService::resolve_conflict(&repo_path, &file_path, &resolution) {
+        Ok(remaining) => Ok(ApiResponse::success(remaining)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to resolve conflict: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn mark_git_conflicts_resolved(
+    repo_path: String,
+    paths: Vec<String>,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    match GitService::mark_resolved(&repo_path, &paths) {
+        Ok(remaining) => Ok(ApiResponse::success(remaining)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to mark conflicts resolved: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_git_stashes(repo_path: String) -> Result<ApiResponse<Vec<GitStash>>, String> {
+    match GitService::list_stashes(&repo_path) {
+        Ok(stashes) => Ok(ApiResponse::success(stashes)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to list stashes: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn save_git_stash(
+    repo_path: String,
+    options: Option<StashOptions>,
+) -> Result<ApiResponse<GitStash>, String> {
+    match GitService::save_stash(&repo_path, &options.unwrap_or_default()) {
+        Ok(stash) => Ok(ApiResponse::success(stash)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to save stash: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn apply_git_stash(
+    repo_path: String,
+    stash_id: String,
+    reinstate_index: Option<bool>,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    match GitService::apply_stash(&repo_path, &stash_id, reinstate_index.unwrap_or(false)) {
+        Ok(conflicts) => Ok(ApiResponse::success(conflicts)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to apply stash: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn pop_git_stash(
+    repo_path: String,
+    stash_id: String,
+    reinstate_index: Option<bool>,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    match GitService::pop_stash(&repo_path, &stash_id, reinstate_index.unwrap_or(false)) {
+        Ok(conflicts) => Ok(ApiResponse::success(conflicts)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to pop stash: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn drop_git_stash(repo_path: String, stash_id: String) -> Result<ApiResponse<()>, String> {
+    match GitService::drop_stash(&repo_path, &stash_id) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to drop stash: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_git_stash_diff(
+    repo_path: String,
+    stash_id: String,
+    options: Option<GitDiffOptions>,
+) -> Result<ApiResponse<GitDiff>, String> {
+    match GitService::get_stash_diff(&repo_path, &stash_id, &options.unwrap_or_default()) {
+        Ok(diff) => Ok(ApiResponse::success(diff)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get stash diff: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_maintenance_settings(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<MaintenanceSettings>, String> {
+    Ok(ApiResponse::success(state.maintenance.get_settings()))
+}
+
+#[tauri::command]
+pub async fn set_maintenance_settings(
+    state: State<'_, AppState>,
+    settings: MaintenanceSettings,
+) -> Result<ApiResponse<()>, String> {
+    match state.maintenance.set_settings(settings) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to set maintenance settings: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_maintenance_status(
+    state: State<'_, AppState>,
+    repo_path: Option<String>,
+) -> Result<ApiResponse<Vec<RepositoryMaintenance>>, String> {
+    let statuses = match repo_path {
+        Some(path) => state.maintenance.get_status(&path).into_iter().collect(),
+        None => state.maintenance.get_statuses(),
+    };
+    Ok(ApiResponse::success(statuses))
+}
+
+#[tauri::command]
+pub async fn run_repository_maintenance(
+    state: State<'_, AppState>,
+    repo_path: String,
+) -> Result<ApiResponse<MaintenanceRun>, String> {
+    match state.maintenance.run_now(&repo_path).await {
+        Ok(run) => Ok(ApiResponse::success(run)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to run repository maintenance: {}", e))),
+    }
+}
+
 // Repository Management Commands
 
 #[tauri::command]
@@ -409,13 +949,25 @@ pub async fn get_managed_workspace_info(
 
 #[tauri::command]
 pub async fn archive_managed_workspace(
+    state: State<'_, AppState>,
     repo_path: String,
     request: ArchiveWorkspaceRequest,
+    close_terminals: Option<bool>,
 ) -> Result<ApiResponse<WorkspaceMetadata>, String> {
-    match WorkspaceManagerService::archive_workspace(&std::path::Path::new(&repo_path), request) {
-        Ok(metadata) => Ok(ApiResponse::success(metadata)),
-        Err(e) => Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e))),
+    if let Err(e) = state.terminal_service.check_workspace_terminals(&request.workspace_id, close_terminals.unwrap_or(true)) {
+        return Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e)));
     }
+
+    let workspace_id = request.workspace_id.clone();
+    let metadata = match WorkspaceManagerService::archive_workspace(&std::path::Path::new(&repo_path), request) {
+        Ok(metadata) => metadata,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to archive workspace: {}", e))),
+    };
+
+    if let Err(e) = state.terminal_service.close_workspace_terminals(&workspace_id).await {
+        eprintln!("警告: 关闭工作区终端失败: {}", e);
+    }
+    Ok(ApiResponse::success(metadata))
 }
 
 #[tauri::command]
@@ -431,13 +983,23 @@ pub async fn restore_managed_workspace(
 
 #[tauri::command]
 pub async fn delete_managed_workspace(
+    state: State<'_, AppState>,
     repo_path: String,
     workspace_id: String,
+    close_terminals: Option<bool>,
 ) -> Result<ApiResponse<bool>, String> {
-    match WorkspaceManagerService::delete_workspace(&std::path::Path::new(&repo_path), &workspace_id) {
-        Ok(_) => Ok(ApiResponse::success(true)),
-        Err(e) => Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e))),
+    if let Err(e) = state.terminal_service.check_workspace_terminals(&workspace_id, close_terminals.unwrap_or(true)) {
+        return Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e)));
+    }
+
+    if let Err(e) = WorkspaceManagerService::delete_workspace(&std::path::Path::new(&repo_path), &workspace_id) {
+        return Ok(ApiResponse::error(format!("Failed to delete workspace: {}", e)));
     }
+
+    if let Err(e) = state.terminal_service.close_workspace_terminals(&workspace_id).await {
+        eprintln!("警告: 关闭工作区终端失败: {}", e);
+    }
+    Ok(ApiResponse::success(true))
 }
 
 #[tauri::command]
@@ -626,10 +1188,11 @@ pub async fn create_terminal(
     name: Option<String>,
     working_directory: String,
     environment: Option<HashMap<String, String>>,
+    shell: Option<ShellOptions>,
 ) -> Result<ApiResponse<String>, String> {
     let working_dir = PathBuf::from(working_directory);
     
-    match state.terminal_service.create_terminal(name, working_dir, environment).await {
+    match state.terminal_service.create_terminal(name, working_dir, environment, shell).await {
         Ok(terminal_id) => Ok(ApiResponse::success(terminal_id)),
         Err(e) => Ok(ApiResponse::error(format!("Failed to create terminal: {}", e))),
     }
@@ -665,7 +1228,11 @@ pub async fn execute_single_command(
     args: Vec<String>,
     working_directory: String,
     environment: Option<HashMap<String, String>>,
-) -> Result<ApiResponse<TerminalOutput>, String> {
+    timeout_ms: Option<u64>,
+    stdin: Option<String>,
+    use_shell: Option<bool>,
+    workspace_id: Option<String>,
+) -> Result<ApiResponse<CommandExecutionResult>, String> {
     let working_dir = PathBuf::from(working_directory);
     let env = environment.unwrap_or_default();
     
@@ -674,6 +1241,10 @@ pub async fn execute_single_command(
         args,
         working_directory: working_dir,
         environment: env,
+        timeout_ms,
+        stdin,
+        use_shell: use_shell.unwrap_or(false),
+        workspace_id,
     };
     
     match state.terminal_service.execute_command(execution).await {
@@ -750,4 +1321,493 @@ pub async fn set_terminal_name(
         Ok(_) => Ok(ApiResponse::success(())),
         Err(e) => Ok(ApiResponse::error(format!("Failed to set terminal name: {}", e))),
     }
+}
+
+#[tauri::command]
+pub async fn set_default_terminal_shell(
+    state: State<'_, AppState>,
+    program: Option<String>,
+    args: Option<Vec<String>>,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.set_default_shell(program, args.unwrap_or_default()) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to set default shell: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_default_terminal_shell(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<ShellConfig>, String> {
+    let shell = state.terminal_service.get_default_shell();
+    Ok(ApiResponse::success(shell))
+}
+
+#[tauri::command]
+pub async fn set_terminal_auto_restart(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    auto_restart: bool,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.set_auto_restart(&terminal_id, auto_restart) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to set terminal auto restart: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn send_terminal_signal(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    signal: TerminalSignal,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.send_signal(&terminal_id, signal).await {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to send signal to terminal: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn send_terminal_input(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    data: String,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.send_input(&terminal_id, &data).await {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to send input to terminal: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn send_terminal_control_key(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    key: ControlKey,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.send_control_key(&terminal_id, key).await {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to send control key to terminal: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_terminal_history_since(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    seq: u64,
+    limit: Option<usize>,
+) -> Result<ApiResponse<HistoryChunk>, String> {
+    match state.terminal_service.get_terminal_history_since(&terminal_id, seq, limit) {
+        Ok(chunk) => Ok(ApiResponse::success(chunk)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get terminal history: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_terminal_history_limit(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    max_bytes: usize,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.set_history_limit(&terminal_id, max_bytes) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to set terminal history limit: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn search_terminal_history(
+    state: State<'_, AppState>,
+    scope: SearchScope,
+    query: HistorySearchQuery,
+) -> Result<ApiResponse<Vec<TerminalSearchResult>>, String> {
+    match state.terminal_service.search_terminal_history(scope, query) {
+        Ok(results) => Ok(ApiResponse::success(results)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to search terminal history: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn start_terminal_recording(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    repo_path: String,
+) -> Result<ApiResponse<String>, String> {
+    let logs_dir = RepositoryManagerService::get_logs_dir(&repo_path);
+
+    match state.terminal_service.start_recording(&terminal_id, &logs_dir) {
+        Ok(path) => Ok(ApiResponse::success(path.to_string_lossy().to_string())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to start terminal recording: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_terminal_recording(
+    state: State<'_, AppState>,
+    terminal_id: String,
+) -> Result<ApiResponse<Option<String>>, String> {
+    match state.terminal_service.stop_recording(&terminal_id) {
+        Ok(path) => Ok(ApiResponse::success(path.map(|p| p.to_string_lossy().to_string()))),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to stop terminal recording: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_terminal_recordings(
+    repo_path: String,
+    workspace_path: Option<String>,
+) -> Result<ApiResponse<Vec<RecordingInfo>>, String> {
+    let logs_dir = RepositoryManagerService::get_logs_dir(&repo_path);
+    let workspace_path = workspace_path.map(PathBuf::from);
+
+    match terminal_recording::list_recordings(&logs_dir, workspace_path.as_deref()) {
+        Ok(recordings) => Ok(ApiResponse::success(recordings)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to list terminal recordings: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn replay_terminal_recording(
+    repo_path: String,
+    recording_name: String,
+    options: Option<ReplayOptions>,
+    on_event: Channel<RecordingEvent>,
+) -> Result<ApiResponse<()>, String> {
+    let logs_dir = RepositoryManagerService::get_logs_dir(&repo_path);
+    let path = match terminal_recording::resolve_recording(&logs_dir, &recording_name) {
+        Ok(path) => path,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to replay terminal recording: {}", e))),
+    };
+
+    let mut events = match terminal_recording::replay_recording(&path, options.unwrap_or_default()) {
+        Ok(events) => events,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to replay terminal recording: {}", e))),
+    };
+
+    while let Some(event) = events.recv().await {
+        if on_event.send(event).is_err() {
+            break;
+        }
+    }
+
+    Ok(ApiResponse::success(()))
+}
+
+#[tauri::command]
+pub async fn get_terminal_commands(
+    state: State<'_, AppState>,
+    terminal_id: String,
+) -> Result<ApiResponse<Vec<TerminalCommand>>, String> {
+    match state.terminal_service.get_terminal_commands(&terminal_id) {
+        Ok(commands) => Ok(ApiResponse::success(commands)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get terminal commands: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn create_workspace_terminal(
+    state: State<'_, AppState>,
+    repo_path: String,
+    workspace_id: String,
+    name: Option<String>,
+    environment: Option<HashMap<String, String>>,
+    shell: Option<ShellOptions>,
+) -> Result<ApiResponse<String>, String> {
+    let metadata = match WorkspaceManagerService::load_workspace_metadata(&std::path::Path::new(&repo_path), &workspace_id) {
+        Ok(metadata) => metadata,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to create terminal: {}", e))),
+    };
+
+    // 仓库已注册时关联其ID
+    let repository_id = match state.repository_service.get_by_path(&metadata.repository_path.to_string_lossy()).await {
+        Ok(repository) => repository.map(|r| r.id),
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to create terminal: {}", e))),
+    };
+
+    let workspace = TerminalWorkspace {
+        workspace_id: metadata.id,
+        workspace_path: metadata.workspace_path,
+        repository_id,
+        repository_path: Some(metadata.repository_path),
+        branch: metadata.branch,
+    };
+
+    match state.terminal_service.create_workspace_terminal(name, workspace, environment, shell).await {
+        Ok(terminal_id) => Ok(ApiResponse::success(terminal_id)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to create terminal: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_workspace_terminals(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<ApiResponse<Vec<TerminalSession>>, String> {
+    let sessions = state.terminal_service.get_workspace_terminals(&workspace_id);
+    Ok(ApiResponse::success(sessions))
+}
+
+#[tauri::command]
+pub async fn close_workspace_terminals(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    match state.terminal_service.close_workspace_terminals(&workspace_id).await {
+        Ok(closed) => Ok(ApiResponse::success(closed)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to close workspace terminals: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_terminal_limits(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<TerminalLimits>, String> {
+    let limits = state.terminal_service.get_limits();
+    Ok(ApiResponse::success(limits))
+}
+
+#[tauri::command]
+pub async fn set_terminal_limits(
+    state: State<'_, AppState>,
+    limits: TerminalLimits,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.set_limits(limits) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to set terminal limits: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_terminal_process_tree(
+    state: State<'_, AppState>,
+    terminal_id: String,
+) -> Result<ApiResponse<Option<ProcessTree>>, String> {
+    match state.terminal_service.get_process_tree(&terminal_id) {
+        Ok(tree) => Ok(ApiResponse::success(tree)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get terminal process tree: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_script_process_tree(
+    state: State<'_, AppState>,
+    execution_id: String,
+) -> Result<ApiResponse<Option<ProcessTree>>, String> {
+    match state.script_executor.get_process_tree(&execution_id) {
+        Ok(tree) => Ok(ApiResponse::success(tree)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to get script process tree: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_workspace_ports(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<ApiResponse<Vec<WorkspacePort>>, String> {
+    let ports = state.terminal_service.get_workspace_ports(&workspace_id);
+    Ok(ApiResponse::success(ports))
+}
+
+#[tauri::command]
+pub async fn attach_terminal(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    from_seq: Option<u64>,
+    on_output: Channel<TerminalOutput>,
+) -> Result<ApiResponse<String>, String> {
+    let attachment = match state.terminal_service.attach(&terminal_id, from_seq) {
+        Ok(attachment) => attachment,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to attach terminal: {}", e))),
+    };
+
+    let subscriber_id = attachment.subscriber_id.clone();
+    let terminal_service = state.terminal_service.clone();
+
+    // 先发送历史，再持续转发实时输出，直到客户端分离或窗口关闭
+    tokio::spawn(async move {
+        let mut last_seq = attachment.backlog.next_seq.saturating_sub(1);
+        for output in attachment.backlog.entries {
+            if on_output.send(output).is_err() {
+                let _ = terminal_service.detach(&terminal_id, &attachment.subscriber_id);
+                return;
+            }
+        }
+
+        let mut receiver = attachment.receiver;
+        let mut detached = attachment.detached;
+        loop {
+            let outputs = tokio::select! {
+                _ = &mut detached => return,
+                received = receiver.recv() => match received {
+                    Ok(output) if output.seq > last_seq => vec![output],
+                    Ok(_) => continue,
+                    // 落后太多时从历史中补齐
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        match terminal_service.get_terminal_history_since(&terminal_id, last_seq, None) {
+                            Ok(chunk) => chunk.entries,
+                            Err(_) => return,
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                },
+            };
+
+            for output in outputs {
+                last_seq = output.seq;
+                if on_output.send(output).is_err() {
+                    let _ = terminal_service.detach(&terminal_id, &attachment.subscriber_id);
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(ApiResponse::success(subscriber_id))
+}
+
+#[tauri::command]
+pub async fn detach_terminal(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    subscriber_id: String,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.detach(&terminal_id, &subscriber_id) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to detach terminal: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn poll_terminal_subscriber(
+    state: State<'_, AppState>,
+    terminal_id: String,
+    subscriber_id: String,
+) -> Result<ApiResponse<HistoryChunk>, String> {
+    match state.terminal_service.poll_subscriber(&terminal_id, &subscriber_id) {
+        Ok(chunk) => Ok(ApiResponse::success(chunk)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to poll terminal output: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn search_command_history(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    query: String,
+    mode: Option<HistoryMatchMode>,
+    limit: Option<usize>,
+) -> Result<ApiResponse<Vec<CommandHistoryEntry>>, String> {
+    let entries = state.terminal_service.command_history().search(
+        &workspace_id,
+        &query,
+        mode.unwrap_or(HistoryMatchMode::Prefix),
+        limit,
+    );
+    Ok(ApiResponse::success(entries))
+}
+
+#[tauri::command]
+pub async fn remove_command_history_entry(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    command: String,
+) -> Result<ApiResponse<bool>, String> {
+    match state.terminal_service.command_history().remove(&workspace_id, &command) {
+        Ok(removed) => Ok(ApiResponse::success(removed)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to remove command history entry: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn clear_command_history(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<ApiResponse<()>, String> {
+    match state.terminal_service.command_history().clear(&workspace_id) {
+        Ok(_) => Ok(ApiResponse::success(())),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to clear command history: {}", e))),
+    }
+}
+
+// 应用退出相关命令
+
+#[tauri::command]
+pub async fn get_shutdown_status(state: State<'_, AppState>) -> Result<ApiResponse<ShutdownStatus>, String> {
+    Ok(ApiResponse::success(state.shutdown.status()))
+}
+
+#[tauri::command]
+pub async fn set_confirm_on_exit(
+    state: State<'_, AppState>,
+    confirm: bool,
+) -> Result<ApiResponse<()>, String> {
+    state.shutdown.set_confirm_on_exit(confirm);
+    Ok(ApiResponse::success(()))
+}
+
+/// 用户确认后结束所有进程并退出应用
+#[tauri::command]
+pub async fn confirm_app_exit(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<()>, String> {
+    state.shutdown.confirm_exit();
+    state.shutdown.shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await;
+    app.exit(0);
+    Ok(ApiResponse::success(()))
+}
+
+// 崩溃恢复相关命令
+
+#[tauri::command]
+pub async fn get_interrupted_executions(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<Vec<InterruptedExecution>>, String> {
+    Ok(ApiResponse::success(state.journal.interrupted()))
+}
+
+#[tauri::command]
+pub async fn kill_interrupted_orphans(
+    state: State<'_, AppState>,
+    execution_id: String,
+) -> Result<ApiResponse<usize>, String> {
+    match state.journal.kill_orphans(&execution_id) {
+        Ok(killed) => Ok(ApiResponse::success(killed)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to kill orphaned processes: {}", e))),
+    }
+}
+
+/// 重新运行中断的执行：先结束遗留进程，脚本以新的执行运行并返回其ID，终端原地重启并返回终端ID
+#[tauri::command]
+pub async fn rerun_interrupted_execution(
+    state: State<'_, AppState>,
+    execution_id: String,
+) -> Result<ApiResponse<String>, String> {
+    let interrupted = match state.journal.get_interrupted(&execution_id) {
+        Some(interrupted) => interrupted,
+        None => return Ok(ApiResponse::error("Interrupted execution not found".to_string())),
+    };
+
+    if let Err(e) = state.journal.clear_orphans(&execution_id).await {
+        return Ok(ApiResponse::error(format!("Failed to rerun interrupted execution: {}", e)));
+    }
+
+    let result = match interrupted.record {
+        JournalRecord::Script(_) => state.script_executor.clone().rerun_interrupted(&interrupted).await,
+        JournalRecord::Terminal(_) => state.terminal_service.restart_interrupted(&interrupted).await,
+    };
+
+    match result {
+        Ok(id) => Ok(ApiResponse::success(id)),
+        Err(e) => Ok(ApiResponse::error(format!("Failed to rerun interrupted execution: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn dismiss_interrupted_execution(
+    state: State<'_, AppState>,
+    execution_id: String,
+) -> Result<ApiResponse<bool>, String> {
+    Ok(ApiResponse::success(state.journal.dismiss(&execution_id)))
 }
\ No newline at end of file
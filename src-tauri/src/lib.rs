@@ -3,7 +3,12 @@ pub mod app_state;
 pub mod commands;
 pub mod services;
 
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, RunEvent, WindowEvent};
+
 use app_state::AppState;
+use services::shutdown::{ShutdownCoordinator, DEFAULT_SHUTDOWN_TIMEOUT};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -11,10 +16,30 @@ pub fn run() {
     
     rt.block_on(async {
         let app_state = AppState::new().await.expect("Failed to initialize app state");
+
+        // 后台回收空闲和已关闭的终端
+        tokio::spawn(app_state.terminal_service.clone().run_reaper());
+
+        // 后台定期获取远程更新并清理过期引用
+        tokio::spawn(app_state.maintenance.clone().run_scheduler(app_state.repository_service.clone()));
+
+        let shutdown = app_state.shutdown.clone();
+        let close_shutdown = shutdown.clone();
+        let runtime = tokio::runtime::Handle::current();
         
         tauri::Builder::default()
             .plugin(tauri_plugin_opener::init())
             .manage(app_state)
+            .on_window_event(move |window, event| {
+                if let WindowEvent::CloseRequested { api, .. } = event {
+                    // 有命令或脚本在运行时交给前端确认，确认后调用 confirm_app_exit
+                    let status = close_shutdown.status();
+                    if status.requires_confirmation {
+                        api.prevent_close();
+                        let _ = window.emit("app-exit-requested", status);
+                    }
+                }
+            })
             .invoke_handler(tauri::generate_handler![
                 commands::greet,
                 commands::database_health_check,
@@ -36,11 +61,46 @@ pub fn run() {
                 commands::remove_git_worktree,
                 commands::checkout_git_branch,
                 commands::create_git_branch,
+                commands::get_git_branches_with_merge_status,
+                commands::delete_git_branch,
+                commands::delete_git_remote_branch,
+                commands::rename_git_branch,
                 commands::is_git_repository,
                 commands::init_git_repository,
                 commands::clone_git_repository,
                 commands::set_global_gitignore,
                 commands::get_global_gitignore,
+                commands::stage_git_paths,
+                commands::unstage_git_paths,
+                commands::discard_git_changes,
+                commands::get_git_file_hunks,
+                commands::stage_git_hunk,
+                commands::unstage_git_hunk,
+                commands::commit_git_changes,
+                commands::get_git_diff,
+                commands::get_workspace_branch_diff,
+                commands::get_commit_log,
+                commands::get_commit_details,
+                commands::fetch_git_remote,
+                commands::pull_git_branch,
+                commands::push_git_branch,
+                commands::update_workspace_from_source,
+                commands::get_git_operation_state,
+                commands::abort_git_operation,
+                commands::continue_git_operation,
+                commands::get_git_conflicts,
+                commands::resolve_git_conflict,
+                commands::mark_git_conflicts_resolved,
+                commands::list_git_stashes,
+                commands::save_git_stash,
+                commands::apply_git_stash,
+                commands::pop_git_stash,
+                commands::drop_git_stash,
+                commands::get_git_stash_diff,
+                commands::get_maintenance_settings,
+                commands::set_maintenance_settings,
+                commands::get_maintenance_status,
+                commands::run_repository_maintenance,
                 // Repository management
                 commands::validate_repository,
                 commands::add_repository_management,
@@ -89,8 +149,60 @@ pub fn run() {
                 commands::get_all_terminals,
                 commands::cleanup_closed_terminals,
                 commands::set_terminal_name,
+                commands::set_default_terminal_shell,
+                commands::get_default_terminal_shell,
+                commands::set_terminal_auto_restart,
+                commands::send_terminal_signal,
+                commands::send_terminal_input,
+                commands::send_terminal_control_key,
+                commands::get_terminal_history_since,
+                commands::set_terminal_history_limit,
+                commands::search_terminal_history,
+                commands::start_terminal_recording,
+                commands::stop_terminal_recording,
+                commands::list_terminal_recordings,
+                commands::replay_terminal_recording,
+                commands::get_terminal_commands,
+                commands::create_workspace_terminal,
+                commands::get_workspace_terminals,
+                commands::close_workspace_terminals,
+                commands::get_terminal_limits,
+                commands::set_terminal_limits,
+                commands::get_terminal_process_tree,
+                commands::get_script_process_tree,
+                commands::get_workspace_ports,
+                commands::attach_terminal,
+                commands::detach_terminal,
+                commands::poll_terminal_subscriber,
+                commands::search_command_history,
+                commands::remove_command_history_entry,
+                commands::clear_command_history,
+                commands::get_shutdown_status,
+                commands::set_confirm_on_exit,
+                commands::confirm_app_exit,
+                commands::get_interrupted_executions,
+                commands::kill_interrupted_orphans,
+                commands::rerun_interrupted_execution,
+                commands::dismiss_interrupted_execution,
             ])
-            .run(tauri::generate_context!())
-            .expect("error while running tauri application");
+            .build(tauri::generate_context!())
+            .expect("error while building tauri application")
+            .run(move |_app, event| {
+                if let RunEvent::Exit = event {
+                    shutdown_blocking(&runtime, shutdown.clone());
+                }
+            });
     });
 }
+
+/// 在事件循环线程上等待后台运行时完成进程清理
+fn shutdown_blocking(runtime: &tokio::runtime::Handle, shutdown: Arc<ShutdownCoordinator>) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    runtime.spawn(async move {
+        let _ = tx.send(shutdown.shutdown(DEFAULT_SHUTDOWN_TIMEOUT).await);
+    });
+
+    if rx.recv_timeout(DEFAULT_SHUTDOWN_TIMEOUT + Duration::from_secs(2)).is_err() {
+        eprintln!("警告: 等待子进程退出超时");
+    }
+}
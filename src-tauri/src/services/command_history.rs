@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+/// 每个工作区保留的历史命令数量上限
+const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// 一条历史命令（相同命令只保留一条）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandHistoryEntry {
+    pub command: String,
+    pub first_used: u64,
+    pub last_used: u64,
+    pub use_count: u32,
+    /// 最近一次执行的退出码，未知时为None
+    pub exit_code: Option<i32>,
+    pub cwd: Option<PathBuf>,
+}
+
+/// 历史命令的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum HistoryMatchMode {
+    /// 以查询开头，用于上方向键补全
+    Prefix,
+    /// 包含查询，用于 Ctrl-R 搜索
+    Substring,
+}
+
+/// 修改后延迟写入磁盘的时间，连续的修改合并为一次写入
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// 发送给写入线程的消息
+enum SaveRequest {
+    Save(String),
+    /// 立即写出所有待保存的历史，完成后通知调用方
+    Flush(mpsc::Sender<()>),
+}
+
+#[derive(Debug)]
+struct HistoryState {
+    /// 为空时只保存在内存中
+    storage_dir: Option<PathBuf>,
+    /// 已加载的工作区历史，按最后使用时间从旧到新排列
+    workspaces: Mutex<HashMap<String, Vec<CommandHistoryEntry>>>,
+}
+
+/// 按工作区保存的命令历史，持久化为目录中的JSON文件
+///
+/// 修改只更新内存，文件由后台线程延迟写入，调用方（例如持有终端锁的输出处理）不会被磁盘I/O阻塞。
+#[derive(Debug)]
+pub struct CommandHistoryStore {
+    state: Arc<HistoryState>,
+    /// 没有存储目录时为None
+    save_tx: Option<mpsc::Sender<SaveRequest>>,
+}
+
+impl Default for CommandHistoryStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl CommandHistoryStore {
+    pub fn new(storage_dir: PathBuf) -> Self {
+        let state = Arc::new(HistoryState {
+            storage_dir: Some(storage_dir),
+            workspaces: Mutex::new(HashMap::new()),
+        });
+
+        let (save_tx, save_rx) = mpsc::channel();
+        let writer_state = Arc::clone(&state);
+        std::thread::spawn(move || Self::run_writer(writer_state, save_rx));
+
+        Self {
+            state,
+            save_tx: Some(save_tx),
+        }
+    }
+
+    /// 创建不写入磁盘的历史存储
+    pub fn in_memory() -> Self {
+        Self {
+            state: Arc::new(HistoryState {
+                storage_dir: None,
+                workspaces: Mutex::new(HashMap::new()),
+            }),
+            save_tx: None,
+        }
+    }
+
+    fn current_timestamp() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    /// 工作区历史文件路径，文件名为ID的百分号编码，不同的ID不会映射到同一文件
+    fn history_file(storage_dir: &Path, workspace_id: &str) -> PathBuf {
+        let mut file_name = String::with_capacity(workspace_id.len());
+        for byte in workspace_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                file_name.push(byte as char);
+            } else {
+                file_name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        storage_dir.join(format!("{}.json", file_name))
+    }
+
+    fn load(&self, workspace_id: &str) -> Vec<CommandHistoryEntry> {
+        self.state
+            .storage_dir
+            .as_ref()
+            .map(|dir| Self::history_file(dir, workspace_id))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(state: &HistoryState, workspace_id: &str) -> Result<(), String> {
+        let path = match state.storage_dir {
+            Some(ref dir) => Self::history_file(dir, workspace_id),
+            None => return Ok(()),
+        };
+
+        // 只在锁内序列化，写文件时不持有锁
+        let content = {
+            let workspaces = state.workspaces.lock().unwrap();
+            match workspaces.get(workspace_id) {
+                Some(entries) => serde_json::to_string(entries)
+                    .map_err(|e| format!("Failed to serialize command history: {}", e))?,
+                None => return Ok(()),
+            }
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create command history directory: {}", e))?;
+        }
+        fs::write(&path, content).map_err(|e| format!("Failed to write command history: {}", e))
+    }
+
+    /// 后台写入线程：收到保存请求后等待 SAVE_DELAY 合并后续修改，再写出所有待保存的工作区
+    fn run_writer(state: Arc<HistoryState>, save_rx: mpsc::Receiver<SaveRequest>) {
+        let mut pending: HashSet<String> = HashSet::new();
+        loop {
+            let request = if pending.is_empty() {
+                save_rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+            } else {
+                save_rx.recv_timeout(SAVE_DELAY)
+            };
+
+            let mut flushed = None;
+            let disconnected = match request {
+                Ok(SaveRequest::Save(workspace_id)) => {
+                    pending.insert(workspace_id);
+                    continue;
+                }
+                Ok(SaveRequest::Flush(done)) => {
+                    flushed = Some(done);
+                    false
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => false,
+                Err(mpsc::RecvTimeoutError::Disconnected) => true,
+            };
+
+            for workspace_id in pending.drain() {
+                if let Err(e) = Self::save(&state, &workspace_id) {
+                    eprintln!("警告: 写入命令历史失败: {}", e);
+                }
+            }
+            if let Some(done) = flushed {
+                let _ = done.send(());
+            }
+            if disconnected {
+                break;
+            }
+        }
+    }
+
+    /// 立即写出所有待保存的历史，应用退出前调用
+    pub fn flush(&self) {
+        if let Some(ref save_tx) = self.save_tx {
+            let (done_tx, done_rx) = mpsc::channel();
+            if save_tx.send(SaveRequest::Flush(done_tx)).is_ok() {
+                let _ = done_rx.recv();
+            }
+        }
+    }
+
+    /// 在工作区历史上执行修改，文件由写入线程延迟保存
+    fn update<T>(
+        &self,
+        workspace_id: &str,
+        f: impl FnOnce(&mut Vec<CommandHistoryEntry>) -> T,
+    ) -> Result<T, String> {
+        let result = {
+            let mut workspaces = self.state.workspaces.lock().unwrap();
+            let entries = workspaces
+                .entry(workspace_id.to_string())
+                .or_insert_with(|| self.load(workspace_id));
+            f(entries)
+        };
+
+        if let Some(ref save_tx) = self.save_tx {
+            save_tx
+                .send(SaveRequest::Save(workspace_id.to_string()))
+                .map_err(|_| "Command history writer has stopped".to_string())?;
+        }
+        Ok(result)
+    }
+
+    fn read<T>(&self, workspace_id: &str, f: impl FnOnce(&[CommandHistoryEntry]) -> T) -> T {
+        let mut workspaces = self.state.workspaces.lock().unwrap();
+        let entries = workspaces
+            .entry(workspace_id.to_string())
+            .or_insert_with(|| self.load(workspace_id));
+        f(entries)
+    }
+
+    /// 记录一条命令，重复的命令移动到最新位置
+    pub fn record(
+        &self,
+        workspace_id: &str,
+        command: &str,
+        exit_code: Option<i32>,
+        cwd: Option<PathBuf>,
+    ) -> Result<(), String> {
+        let command = command.trim();
+        if command.is_empty() {
+            return Ok(());
+        }
+
+        let timestamp = Self::current_timestamp();
+        self.update(workspace_id, |entries| {
+            let entry = match entries.iter().position(|e| e.command == command) {
+                Some(index) => {
+                    let mut entry = entries.remove(index);
+                    entry.last_used = timestamp;
+                    entry.use_count += 1;
+                    entry.exit_code = exit_code;
+                    if cwd.is_some() {
+                        entry.cwd = cwd;
+                    }
+                    entry
+                }
+                None => CommandHistoryEntry {
+                    command: command.to_string(),
+                    first_used: timestamp,
+                    last_used: timestamp,
+                    use_count: 1,
+                    exit_code,
+                    cwd,
+                },
+            };
+
+            entries.push(entry);
+            if entries.len() > MAX_HISTORY_ENTRIES {
+                let excess = entries.len() - MAX_HISTORY_ENTRIES;
+                entries.drain(..excess);
+            }
+        })
+    }
+
+    /// 命令结束后补充退出码
+    pub fn set_exit_code(&self, workspace_id: &str, command: &str, exit_code: Option<i32>) -> Result<(), String> {
+        let command = command.trim();
+        self.update(workspace_id, |entries| {
+            if let Some(entry) = entries.iter_mut().rev().find(|e| e.command == command) {
+                entry.exit_code = exit_code;
+            }
+        })
+    }
+
+    /// 搜索历史命令，最近使用的排在前面
+    pub fn search(
+        &self,
+        workspace_id: &str,
+        query: &str,
+        mode: HistoryMatchMode,
+        limit: Option<usize>,
+    ) -> Vec<CommandHistoryEntry> {
+        let query = query.to_lowercase();
+        self.read(workspace_id, |entries| {
+            entries
+                .iter()
+                .rev()
+                .filter(|entry| {
+                    let command = entry.command.to_lowercase();
+                    match mode {
+                        HistoryMatchMode::Prefix => command.starts_with(&query),
+                        HistoryMatchMode::Substring => command.contains(&query),
+                    }
+                })
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// 删除一条历史命令
+    pub fn remove(&self, workspace_id: &str, command: &str) -> Result<bool, String> {
+        self.update(workspace_id, |entries| {
+            let before = entries.len();
+            entries.retain(|e| e.command != command);
+            entries.len() != before
+        })
+    }
+
+    /// 清空工作区的历史命令
+    pub fn clear(&self, workspace_id: &str) -> Result<(), String> {
+        self.update(workspace_id, |entries| entries.clear())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_search() {
+        let store = CommandHistoryStore::in_memory();
+        store.record("ws", "npm run dev", None, None).unwrap();
+        store.record("ws", "git status", Some(0), None).unwrap();
+        store.record("ws", "npm test", Some(1), None).unwrap();
+        store.record("ws", "npm run dev", None, None).unwrap();
+        store.record("other", "cargo build", Some(0), None).unwrap();
+
+        // 去重后最近使用的排在前面
+        let all = store.search("ws", "", HistoryMatchMode::Prefix, None);
+        let commands: Vec<&str> = all.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, vec!["npm run dev", "npm test", "git status"]);
+        assert_eq!(all[0].use_count, 2);
+
+        let prefix = store.search("ws", "npm", HistoryMatchMode::Prefix, Some(1));
+        assert_eq!(prefix.len(), 1);
+        assert_eq!(prefix[0].command, "npm run dev");
+
+        let substring = store.search("ws", "STATUS", HistoryMatchMode::Substring, None);
+        assert_eq!(substring[0].command, "git status");
+        assert!(store.search("ws", "status", HistoryMatchMode::Prefix, None).is_empty());
+
+        store.set_exit_code("ws", "npm run dev", Some(130)).unwrap();
+        assert_eq!(store.search("ws", "npm run", HistoryMatchMode::Prefix, None)[0].exit_code, Some(130));
+
+        assert!(store.remove("ws", "npm test").unwrap());
+        assert_eq!(store.search("ws", "", HistoryMatchMode::Prefix, None).len(), 2);
+    }
+
+    #[test]
+    fn test_history_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CommandHistoryStore::new(temp_dir.path().to_path_buf());
+        store.record("ws/1", "make build", Some(0), Some(PathBuf::from("/repo"))).unwrap();
+        store.record("ws_1", "make test", None, None).unwrap();
+        store.flush();
+
+        // 新的实例从磁盘加载，相似的ID不共用历史文件
+        let reloaded = CommandHistoryStore::new(temp_dir.path().to_path_buf());
+        let entries = reloaded.search("ws/1", "make", HistoryMatchMode::Prefix, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].exit_code, Some(0));
+        assert_eq!(entries[0].cwd, Some(PathBuf::from("/repo")));
+        assert!(reloaded.search("ws/1", "make test", HistoryMatchMode::Prefix, None).is_empty());
+        assert_eq!(reloaded.search("ws_1", "make", HistoryMatchMode::Prefix, None).len(), 1);
+
+        reloaded.clear("ws/1").unwrap();
+        reloaded.flush();
+        let store = CommandHistoryStore::new(temp_dir.path().to_path_buf());
+        assert!(store.search("ws/1", "", HistoryMatchMode::Prefix, None).is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,673 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::services::process_inspector::{self, ProcessInfo};
+use crate::services::script_executor::{ExecutionStatus, ScriptExecution};
+use crate::services::terminal_service::{TerminalOutput, TerminalSession, TerminalStatus};
+
+/// 写入子进程环境的执行ID，用于在应用重启后识别遗留进程
+pub const EXECUTION_ID_ENV: &str = "WORKHORSE_EXECUTION_ID";
+
+/// 单个输出日志文件的大小上限，超过后轮转为 `.1` 文件
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// 结束遗留进程后等待其退出的检查次数和间隔
+const ORPHAN_EXIT_POLLS: usize = 40;
+const ORPHAN_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 日志中记录的执行对象
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data")]
+pub enum JournalRecord {
+    Script(ScriptExecution),
+    Terminal(TerminalSession),
+}
+
+impl JournalRecord {
+    pub fn id(&self) -> &str {
+        match self {
+            JournalRecord::Script(execution) => &execution.id,
+            JournalRecord::Terminal(session) => &session.id,
+        }
+    }
+}
+
+/// 正在运行的脚本或终端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub record: JournalRecord,
+    pub pid: Option<u32>,
+    pub started_at: u64,
+}
+
+/// 上次运行时未正常结束的执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptedExecution {
+    pub id: String,
+    /// 状态已标记为 Interrupted，脚本的部分输出保存在 stdout/stderr 中
+    pub record: JournalRecord,
+    pub pid: Option<u32>,
+    pub started_at: u64,
+    pub detected_at: u64,
+    /// 终端中断前的输出
+    #[serde(default)]
+    pub output: Vec<TerminalOutput>,
+    /// 仍然存活的遗留进程，每次查询时重新检测
+    #[serde(default)]
+    pub orphans: Vec<ProcessInfo>,
+    /// 无法检测遗留进程时的原因（例如当前平台不支持），此时 `orphans` 为空不代表没有遗留进程
+    #[serde(default)]
+    pub orphans_error: Option<String>,
+}
+
+/// 输出日志的类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// 终端输出，每行一条JSON格式的 `TerminalOutput`
+    Terminal,
+}
+
+impl LogStream {
+    fn extension(&self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+            LogStream::Terminal => "jsonl",
+        }
+    }
+}
+
+/// 追加写入的输出日志，超过大小上限时轮转，最多保留两个文件
+#[derive(Debug)]
+pub struct OutputLog {
+    path: PathBuf,
+    file: BufWriter<File>,
+    written: u64,
+}
+
+impl OutputLog {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            file: BufWriter::new(file),
+            written,
+        })
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if self.written > 0 && self.written + data.len() as u64 > MAX_LOG_BYTES {
+            self.file.flush()?;
+            fs::rename(&self.path, rotated_path(&self.path))?;
+            self.file = BufWriter::new(File::create(&self.path)?);
+            self.written = 0;
+        }
+        self.file.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    /// 写入一条终端输出
+    pub fn write_output(&mut self, output: &TerminalOutput) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(output)?;
+        line.push(b'\n');
+        self.write(&line)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// 记录正在运行的脚本和终端，应用异常退出后据此恢复
+///
+/// 运行中的执行保存在 `journal.json`，启动时其中剩余的条目即为上次被中断的执行，
+/// 移入 `interrupted.json` 直到用户处理。
+#[derive(Debug)]
+pub struct ExecutionJournal {
+    /// 为空时只保存在内存中
+    journal_dir: Option<PathBuf>,
+    active: Mutex<HashMap<String, JournalEntry>>,
+    interrupted: Mutex<HashMap<String, InterruptedExecution>>,
+}
+
+impl Default for ExecutionJournal {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl ExecutionJournal {
+    /// 打开日志目录，并将上次运行遗留的条目标记为中断
+    pub fn new(journal_dir: PathBuf) -> Self {
+        let journal = Self {
+            journal_dir: Some(journal_dir),
+            active: Mutex::new(HashMap::new()),
+            interrupted: Mutex::new(HashMap::new()),
+        };
+        journal.recover();
+        journal
+    }
+
+    /// 创建不写入磁盘的日志
+    pub fn in_memory() -> Self {
+        Self {
+            journal_dir: None,
+            active: Mutex::new(HashMap::new()),
+            interrupted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn current_timestamp() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    fn journal_file(&self) -> Option<PathBuf> {
+        self.journal_dir.as_ref().map(|dir| dir.join("journal.json"))
+    }
+
+    fn interrupted_file(&self) -> Option<PathBuf> {
+        self.journal_dir.as_ref().map(|dir| dir.join("interrupted.json"))
+    }
+
+    fn log_path(&self, id: &str, stream: LogStream) -> Option<PathBuf> {
+        let file_name: String = id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.journal_dir
+            .as_ref()
+            .map(|dir| dir.join("output").join(format!("{}.{}", file_name, stream.extension())))
+    }
+
+    /// 读取日志内容，包括轮转前的部分
+    fn read_log(&self, id: &str, stream: LogStream) -> Vec<u8> {
+        let path = match self.log_path(id, stream) {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+        let mut content = fs::read(rotated_path(&path)).unwrap_or_default();
+        content.extend(fs::read(&path).unwrap_or_default());
+        content
+    }
+
+    fn remove_logs(&self, id: &str) {
+        for stream in [LogStream::Stdout, LogStream::Stderr, LogStream::Terminal] {
+            if let Some(path) = self.log_path(id, stream) {
+                let _ = fs::remove_file(rotated_path(&path));
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    /// 先写入同目录的临时文件再替换，写入中途崩溃不会留下不完整的日志
+    fn write_json<T: Serialize>(path: Option<PathBuf>, value: &T) -> Result<(), String> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create journal directory: {}", e))?;
+        }
+        let content = serde_json::to_vec(value)
+            .map_err(|e| format!("Failed to serialize journal: {}", e))?;
+
+        let temp_path = path.with_extension("json.tmp");
+        let write_temp = || -> std::io::Result<()> {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(&content)?;
+            file.sync_all()
+        };
+        write_temp()
+            .and_then(|_| fs::rename(&temp_path, &path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp_path);
+                format!("Failed to write journal: {}", e)
+            })
+    }
+
+    /// 读取日志文件，文件无法解析时改名为 `.corrupt` 保留下来，不会被之后的写入覆盖
+    fn read_json<T: DeserializeOwned>(path: Option<PathBuf>) -> Vec<T> {
+        let path = match path {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(_) => return Vec::new(),
+        };
+
+        match serde_json::from_slice(&content) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let corrupt_path = path.with_extension("json.corrupt");
+                eprintln!(
+                    "警告: 执行日志 {} 无法解析，已保留为 {}: {}",
+                    path.display(),
+                    corrupt_path.display(),
+                    e
+                );
+                if let Err(e) = fs::rename(&path, &corrupt_path) {
+                    eprintln!("警告: 保留无法解析的执行日志失败: {}", e);
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// 写入磁盘前移除环境变量，其中可能包含令牌等敏感信息
+    fn redact(record: &mut JournalRecord) {
+        match record {
+            JournalRecord::Script(execution) => execution.environment.clear(),
+            JournalRecord::Terminal(session) => session.environment.clear(),
+        }
+    }
+
+    fn save_active(&self, active: &HashMap<String, JournalEntry>) {
+        let entries: Vec<JournalEntry> = active
+            .values()
+            .cloned()
+            .map(|mut entry| {
+                Self::redact(&mut entry.record);
+                entry
+            })
+            .collect();
+        if let Err(e) = Self::write_json(self.journal_file(), &entries) {
+            eprintln!("警告: 写入执行日志失败: {}", e);
+        }
+    }
+
+    fn save_interrupted(&self, interrupted: &HashMap<String, InterruptedExecution>) {
+        // 输出保存在日志文件中，不重复写入
+        let entries: Vec<InterruptedExecution> = interrupted
+            .values()
+            .cloned()
+            .map(|mut entry| {
+                entry.output.clear();
+                entry.orphans.clear();
+                entry.orphans_error = None;
+                Self::redact(&mut entry.record);
+                if let JournalRecord::Script(ref mut execution) = entry.record {
+                    execution.stdout.clear();
+                    execution.stderr.clear();
+                }
+                entry
+            })
+            .collect();
+        if let Err(e) = Self::write_json(self.interrupted_file(), &entries) {
+            eprintln!("警告: 写入中断记录失败: {}", e);
+        }
+    }
+
+    /// 加载上次运行的日志，未结束的执行标记为中断并读回部分输出
+    fn recover(&self) {
+        let mut interrupted: Vec<InterruptedExecution> = Self::read_json(self.interrupted_file());
+        let leftover: Vec<JournalEntry> = Self::read_json(self.journal_file());
+        let detected_at = Self::current_timestamp();
+        for entry in leftover {
+            let mut record = entry.record;
+            match record {
+                JournalRecord::Script(ref mut execution) => {
+                    execution.status = ExecutionStatus::Interrupted;
+                    execution.end_time = Some(detected_at);
+                    execution.pid = None;
+                }
+                JournalRecord::Terminal(ref mut session) => {
+                    session.status = TerminalStatus::Interrupted;
+                    session.attached_clients = 0;
+                    session.recording_path = None;
+                }
+            }
+            interrupted.push(InterruptedExecution {
+                id: record.id().to_string(),
+                record,
+                pid: entry.pid,
+                started_at: entry.started_at,
+                detected_at,
+                output: Vec::new(),
+                orphans: Vec::new(),
+                orphans_error: None,
+            });
+        }
+
+        for entry in interrupted.iter_mut() {
+            match entry.record {
+                JournalRecord::Script(ref mut execution) => {
+                    execution.stdout =
+                        String::from_utf8_lossy(&self.read_log(&entry.id, LogStream::Stdout)).to_string();
+                    execution.stderr =
+                        String::from_utf8_lossy(&self.read_log(&entry.id, LogStream::Stderr)).to_string();
+                }
+                JournalRecord::Terminal(_) => {
+                    let content = self.read_log(&entry.id, LogStream::Terminal);
+                    entry.output = String::from_utf8_lossy(&content)
+                        .lines()
+                        .filter_map(|line| serde_json::from_str(line).ok())
+                        .collect();
+                }
+            }
+        }
+
+        let interrupted: HashMap<String, InterruptedExecution> =
+            interrupted.into_iter().map(|entry| (entry.id.clone(), entry)).collect();
+        self.save_interrupted(&interrupted);
+        self.save_active(&HashMap::new());
+        *self.interrupted.lock().unwrap() = interrupted;
+    }
+
+    /// 记录开始运行的执行，同一ID之前的中断记录随之失效
+    pub fn begin(&self, record: JournalRecord, pid: Option<u32>) {
+        let id = record.id().to_string();
+        {
+            let mut interrupted = self.interrupted.lock().unwrap();
+            if interrupted.remove(&id).is_some() {
+                self.save_interrupted(&interrupted);
+                self.remove_logs(&id);
+            }
+        }
+
+        let mut active = self.active.lock().unwrap();
+        active.insert(
+            id,
+            JournalEntry {
+                record,
+                pid,
+                started_at: Self::current_timestamp(),
+            },
+        );
+        self.save_active(&active);
+    }
+
+    /// 更新执行的进程PID（如终端自动重启后）
+    pub fn set_pid(&self, id: &str, pid: Option<u32>) {
+        let mut active = self.active.lock().unwrap();
+        if let Some(entry) = active.get_mut(id) {
+            entry.pid = pid;
+            self.save_active(&active);
+        }
+    }
+
+    /// 打开执行的输出日志，内存模式或创建失败时返回None
+    pub fn output_log(&self, id: &str, stream: LogStream) -> Option<OutputLog> {
+        let path = self.log_path(id, stream)?;
+        let result = path
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| OutputLog::open(path));
+        match result {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("警告: 创建输出日志失败: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 执行正常结束，删除日志条目和输出
+    pub fn finish(&self, id: &str) {
+        let mut active = self.active.lock().unwrap();
+        if active.remove(id).is_some() {
+            self.save_active(&active);
+            self.remove_logs(id);
+        }
+    }
+
+    /// 获取所有中断的执行，并检测其遗留进程
+    pub fn interrupted(&self) -> Vec<InterruptedExecution> {
+        let interrupted = self.interrupted.lock().unwrap();
+        let mut entries: Vec<InterruptedExecution> = interrupted
+            .values()
+            .cloned()
+            .map(Self::detect_orphans)
+            .collect();
+        entries.sort_by_key(|entry| entry.started_at);
+        entries
+    }
+
+    /// 获取指定的中断执行
+    pub fn get_interrupted(&self, id: &str) -> Option<InterruptedExecution> {
+        let interrupted = self.interrupted.lock().unwrap();
+        interrupted.get(id).cloned().map(Self::detect_orphans)
+    }
+
+    fn detect_orphans(mut entry: InterruptedExecution) -> InterruptedExecution {
+        match process_inspector::processes_with_env(EXECUTION_ID_ENV, &entry.id) {
+            Ok(orphans) => {
+                entry.orphans = orphans;
+                entry.orphans_error = None;
+            }
+            Err(e) => {
+                entry.orphans.clear();
+                entry.orphans_error = Some(e);
+            }
+        }
+        entry
+    }
+
+    /// 结束中断执行的所有遗留进程及其子进程，返回收到信号的进程数
+    pub fn kill_orphans(&self, id: &str) -> Result<usize, String> {
+        if !self.interrupted.lock().unwrap().contains_key(id) {
+            return Err("Interrupted execution not found".to_string());
+        }
+
+        #[cfg(unix)]
+        let signal = libc::SIGKILL;
+        #[cfg(not(unix))]
+        let signal = 9;
+
+        Ok(process_inspector::processes_with_env(EXECUTION_ID_ENV, id)?
+            .iter()
+            .map(|process| process_inspector::signal_tree(process.pid, signal))
+            .sum())
+    }
+
+    /// 重新运行前结束中断执行的遗留进程并等待其退出，避免新旧进程同时运行。
+    /// 当前平台无法检测遗留进程时只输出警告
+    pub async fn clear_orphans(&self, id: &str) -> Result<(), String> {
+        let killed = match self.kill_orphans(id) {
+            Ok(killed) => killed,
+            Err(e) if self.interrupted.lock().unwrap().contains_key(id) => {
+                eprintln!("警告: 无法检测中断执行 {} 的遗留进程: {}", id, e);
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if killed == 0 {
+            return Ok(());
+        }
+
+        for _ in 0..ORPHAN_EXIT_POLLS {
+            tokio::time::sleep(ORPHAN_EXIT_POLL_INTERVAL).await;
+            if process_inspector::processes_with_env(EXECUTION_ID_ENV, id)?.is_empty() {
+                return Ok(());
+            }
+        }
+        Err("Orphaned processes are still running".to_string())
+    }
+
+    /// 移除中断记录及其输出
+    pub fn dismiss(&self, id: &str) -> bool {
+        let mut interrupted = self.interrupted.lock().unwrap();
+        let removed = interrupted.remove(id).is_some();
+        if removed {
+            self.save_interrupted(&interrupted);
+            self.remove_logs(id);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn script_execution(id: &str) -> ScriptExecution {
+        ScriptExecution {
+            id: id.to_string(),
+            script_content: "npm run build".to_string(),
+            working_directory: PathBuf::from("/repo"),
+            environment: HashMap::new(),
+            status: ExecutionStatus::Running,
+            start_time: Some(1),
+            end_time: None,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            pid: Some(12345),
+        }
+    }
+
+    #[test]
+    fn test_recover_interrupted_execution() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        journal.begin(JournalRecord::Script(script_execution("exec_1")), Some(12345));
+        journal.begin(JournalRecord::Script(script_execution("exec_2")), Some(12346));
+
+        let mut stdout = journal.output_log("exec_1", LogStream::Stdout).unwrap();
+        stdout.write(b"compiling...\n").unwrap();
+        stdout.flush().unwrap();
+
+        // 正常结束的执行不会被恢复
+        journal.finish("exec_2");
+        drop(journal);
+
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        let interrupted = journal.interrupted();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].id, "exec_1");
+        assert_eq!(interrupted[0].pid, Some(12345));
+        match &interrupted[0].record {
+            JournalRecord::Script(execution) => {
+                assert!(matches!(execution.status, ExecutionStatus::Interrupted));
+                assert_eq!(execution.stdout, "compiling...\n");
+                assert_eq!(execution.pid, None);
+            }
+            _ => panic!("expected script record"),
+        }
+
+        // 中断记录在用户处理前一直保留
+        drop(journal);
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        assert!(journal.get_interrupted("exec_1").is_some());
+        assert!(journal.dismiss("exec_1"));
+        assert!(journal.interrupted().is_empty());
+        assert!(!temp_dir.path().join("output").join("exec_1.stdout").exists());
+    }
+
+    #[test]
+    fn test_corrupt_journal_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        // 模拟写入中途崩溃留下的不完整文件
+        fs::write(temp_dir.path().join("interrupted.json"), "[{\"id\":\"exec_1\",").unwrap();
+
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        assert!(journal.interrupted().is_empty());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("interrupted.json.corrupt")).unwrap(),
+            "[{\"id\":\"exec_1\","
+        );
+        assert!(!temp_dir.path().join("journal.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_environment_not_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        let mut execution = script_execution("exec_env");
+        execution.environment.insert("API_TOKEN".to_string(), "secret-value".to_string());
+        journal.begin(JournalRecord::Script(execution), None);
+
+        let content = fs::read_to_string(temp_dir.path().join("journal.json")).unwrap();
+        assert!(content.contains("exec_env"));
+        assert!(!content.contains("secret-value"));
+    }
+
+    #[test]
+    fn test_output_log_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        let mut log = journal.output_log("exec_1", LogStream::Stdout).unwrap();
+
+        let chunk = vec![b'a'; MAX_LOG_BYTES as usize / 2 + 1];
+        log.write(&chunk).unwrap();
+        log.write(&chunk).unwrap();
+        log.write(b"tail").unwrap();
+        log.flush().unwrap();
+
+        let content = journal.read_log("exec_1", LogStream::Stdout);
+        assert_eq!(content.len(), chunk.len() * 2 + 4);
+        assert!(content.ends_with(b"tail"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_detect_and_kill_orphans() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        journal.begin(JournalRecord::Script(script_execution("exec_orphan")), None);
+        drop(journal);
+
+        // 模拟上次运行遗留的子进程
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .env(EXECUTION_ID_ENV, "exec_orphan")
+            .spawn()
+            .unwrap();
+
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        let interrupted = journal.get_interrupted("exec_orphan").unwrap();
+        assert!(interrupted.orphans_error.is_none());
+        assert_eq!(interrupted.orphans.len(), 1);
+        assert_eq!(interrupted.orphans[0].pid, child.id());
+
+        assert_eq!(journal.kill_orphans("exec_orphan").unwrap(), 1);
+        child.wait().unwrap();
+        assert!(journal.get_interrupted("exec_orphan").unwrap().orphans.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_clear_orphans_waits_for_exit() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        journal.begin(JournalRecord::Script(script_execution("exec_rerun")), None);
+        drop(journal);
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .env(EXECUTION_ID_ENV, "exec_rerun")
+            .spawn()
+            .unwrap();
+        // 回收僵尸进程，否则进程表中一直存在
+        let reaper = std::thread::spawn(move || child.wait());
+
+        let journal = ExecutionJournal::new(temp_dir.path().to_path_buf());
+        journal.clear_orphans("exec_rerun").await.unwrap();
+        assert!(journal.get_interrupted("exec_rerun").unwrap().orphans.is_empty());
+        reaper.join().unwrap().unwrap();
+        assert!(journal.clear_orphans("missing").await.is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,442 @@
+use anyhow::Result;
+use git2::{Delta, DiffFindOptions, DiffLineType, Patch};
+use serde::{Deserialize, Serialize};
+
+/// 单个文件默认的大小上限，超过时不生成差异块
+pub const DEFAULT_MAX_DIFF_FILE_BYTES: u64 = 1024 * 1024;
+/// 单个文件默认最多返回的差异行数
+pub const DEFAULT_MAX_DIFF_LINES: usize = 5000;
+
+/// 比较的对象
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DiffTarget {
+    /// 工作区与索引（未暂存的更改）
+    WorkingTree,
+    /// 索引与HEAD（已暂存的更改）
+    Staged,
+    /// 当前分支与基准分支的合并基础之间的提交（`git diff base...HEAD`）
+    Branch { base: String },
+}
+
+/// 差异选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffOptions {
+    #[serde(default = "default_context_lines")]
+    pub context_lines: u32,
+    /// 忽略所有空白差异
+    #[serde(default)]
+    pub ignore_whitespace: bool,
+    /// 忽略空白数量的变化
+    #[serde(default)]
+    pub ignore_whitespace_change: bool,
+    /// 忽略行尾空白
+    #[serde(default)]
+    pub ignore_whitespace_eol: bool,
+    /// 检测重命名和复制
+    #[serde(default = "default_detect_renames")]
+    pub detect_renames: bool,
+    /// 只比较这些路径，为空时比较全部
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// 超过该大小的文件只返回文件信息
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// 每个文件最多返回的差异行数，超出部分截断，为0时只返回文件和统计信息
+    #[serde(default = "default_max_lines")]
+    pub max_lines_per_file: usize,
+}
+
+fn default_context_lines() -> u32 {
+    3
+}
+
+fn default_detect_renames() -> bool {
+    true
+}
+
+fn default_max_file_bytes() -> u64 {
+    DEFAULT_MAX_DIFF_FILE_BYTES
+}
+
+fn default_max_lines() -> usize {
+    DEFAULT_MAX_DIFF_LINES
+}
+
+impl Default for GitDiffOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: default_context_lines(),
+            ignore_whitespace: false,
+            ignore_whitespace_change: false,
+            ignore_whitespace_eol: false,
+            detect_renames: default_detect_renames(),
+            paths: Vec::new(),
+            max_file_bytes: default_max_file_bytes(),
+            max_lines_per_file: default_max_lines(),
+        }
+    }
+}
+
+impl GitDiffOptions {
+    /// 转换为 git2 的差异选项
+    pub fn to_git2(&self) -> git2::DiffOptions {
+        let mut opts = git2::DiffOptions::new();
+        opts.context_lines(self.context_lines)
+            .ignore_whitespace(self.ignore_whitespace)
+            .ignore_whitespace_change(self.ignore_whitespace_change)
+            .ignore_whitespace_eol(self.ignore_whitespace_eol)
+            .max_size(self.max_file_bytes.min(i64::MAX as u64) as i64);
+        for path in &self.paths {
+            opts.pathspec(path);
+        }
+        opts
+    }
+}
+
+/// 差异行的类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum GitDiffLineKind {
+    Context,
+    Addition,
+    Deletion,
+    /// 文件末尾没有换行符的提示
+    NoNewlineAtEof,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffLine {
+    pub kind: GitDiffLineKind,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffFile {
+    /// 新增的文件为None
+    pub old_path: Option<String>,
+    /// 删除的文件为None
+    pub new_path: Option<String>,
+    /// added / deleted / modified / renamed / copied / typechange / untracked
+    pub status: String,
+    pub is_binary: bool,
+    /// 文件超过大小上限，没有生成差异块
+    pub too_large: bool,
+    /// 差异行超过上限被截断
+    pub truncated: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitDiffStats {
+    pub files_changed: usize,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiff {
+    pub files: Vec<GitDiffFile>,
+    pub stats: GitDiffStats,
+    /// 分支比较时的合并基础提交
+    pub merge_base: Option<String>,
+}
+
+fn delta_status(delta: Delta) -> &'static str {
+    match delta {
+        Delta::Added => "added",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        Delta::Untracked => "untracked",
+        _ => "modified",
+    }
+}
+
+fn line_kind(origin: DiffLineType) -> Option<GitDiffLineKind> {
+    match origin {
+        DiffLineType::Context => Some(GitDiffLineKind::Context),
+        DiffLineType::Addition => Some(GitDiffLineKind::Addition),
+        DiffLineType::Deletion => Some(GitDiffLineKind::Deletion),
+        DiffLineType::ContextEOFNL | DiffLineType::AddEOFNL | DiffLineType::DeleteEOFNL => {
+            Some(GitDiffLineKind::NoNewlineAtEof)
+        }
+        _ => None,
+    }
+}
+
+/// 检测重命名和复制（包括工作区中未跟踪的文件）
+pub fn find_renames(diff: &mut git2::Diff, options: &GitDiffOptions) -> Result<()> {
+    if options.detect_renames {
+        let mut find = DiffFindOptions::new();
+        find.renames(true).copies(true).for_untracked(true);
+        diff.find_similar(Some(&mut find))?;
+    }
+    Ok(())
+}
+
+/// 将 git2 的差异转换为结构化结果
+pub fn build_diff(diff: &git2::Diff, options: &GitDiffOptions, merge_base: Option<String>) -> Result<GitDiff> {
+    let mut files = Vec::new();
+    let mut stats = GitDiffStats::default();
+
+    for index in 0..diff.deltas().len() {
+        let delta = diff.get_delta(index).expect("delta index in range");
+        let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+        let old_path = match delta.status() {
+            Delta::Added | Delta::Untracked => None,
+            _ => path(delta.old_file()),
+        };
+        let new_path = match delta.status() {
+            Delta::Deleted => None,
+            _ => path(delta.new_file()),
+        };
+        let too_large = delta.old_file().size() > options.max_file_bytes
+            || delta.new_file().size() > options.max_file_bytes;
+
+        let mut file = GitDiffFile {
+            old_path,
+            new_path,
+            status: delta_status(delta.status()).to_string(),
+            is_binary: false,
+            too_large,
+            truncated: false,
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        };
+
+        if !too_large {
+            if let Some(patch) = Patch::from_diff(diff, index)? {
+                file.is_binary = patch.delta().flags().is_binary();
+                if !file.is_binary {
+                    let (_, additions, deletions) = patch.line_stats()?;
+                    file.additions = additions;
+                    file.deletions = deletions;
+                    if options.max_lines_per_file > 0 {
+                        file.truncated = collect_hunks(&patch, options.max_lines_per_file, &mut file.hunks)?;
+                    }
+                }
+            } else {
+                file.is_binary = delta.flags().is_binary();
+            }
+        }
+
+        stats.files_changed += 1;
+        stats.additions += file.additions;
+        stats.deletions += file.deletions;
+        files.push(file);
+    }
+
+    Ok(GitDiff {
+        files,
+        stats,
+        merge_base,
+    })
+}
+
+/// 收集补丁中的差异块，返回是否因超过行数上限而截断
+fn collect_hunks(patch: &Patch, max_lines: usize, hunks: &mut Vec<GitDiffHunk>) -> Result<bool> {
+    let mut remaining = max_lines;
+
+    for hunk_index in 0..patch.num_hunks() {
+        if remaining == 0 {
+            return Ok(true);
+        }
+
+        let (hunk, line_count) = patch.hunk(hunk_index)?;
+        let mut diff_hunk = GitDiffHunk {
+            header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines: Vec::new(),
+        };
+
+        for line_index in 0..line_count {
+            if remaining == 0 {
+                hunks.push(diff_hunk);
+                return Ok(true);
+            }
+
+            let line = patch.line_in_hunk(hunk_index, line_index)?;
+            let kind = match line_kind(line.origin_value()) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            diff_hunk.lines.push(GitDiffLine {
+                kind,
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            });
+            remaining -= 1;
+        }
+
+        hunks.push(diff_hunk);
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::git_service::GitService;
+    use crate::services::test_support::{commit_all, init_repo};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn find_file<'a>(diff: &'a GitDiff, path: &str) -> &'a GitDiffFile {
+        diff.files
+            .iter()
+            .find(|f| f.new_path.as_deref() == Some(path) || f.old_path.as_deref() == Some(path))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_working_tree_and_staged_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo(repo_path);
+        let long_text: String = (1..=30).map(|i| format!("shared line {}\n", i)).collect();
+        fs::write(repo_path.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(repo_path.join("old_name.txt"), &long_text).unwrap();
+        fs::write(repo_path.join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+        commit_all(repo_path, "Initial");
+
+        fs::write(repo_path.join("a.txt"), "one\n2\nthree\nfour").unwrap();
+        fs::write(repo_path.join("image.bin"), [0u8, 9, 9, 9]).unwrap();
+        fs::write(repo_path.join("new.txt"), "fresh\n").unwrap();
+
+        let diff = GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &GitDiffOptions::default()).unwrap();
+        assert_eq!(diff.stats.files_changed, 3);
+
+        let modified = find_file(&diff, "a.txt");
+        assert_eq!(modified.status, "modified");
+        assert_eq!((modified.additions, modified.deletions), (2, 1));
+        let lines = &modified.hunks[0].lines;
+        let added = lines.iter().find(|l| l.content == "2").unwrap();
+        assert_eq!(added.kind, GitDiffLineKind::Addition);
+        assert_eq!((added.old_lineno, added.new_lineno), (None, Some(2)));
+        let removed = lines.iter().find(|l| l.content == "two").unwrap();
+        assert_eq!((removed.old_lineno, removed.new_lineno), (Some(2), None));
+        assert!(lines.iter().any(|l| l.kind == GitDiffLineKind::NoNewlineAtEof));
+
+        assert!(find_file(&diff, "image.bin").is_binary);
+        let untracked = find_file(&diff, "new.txt");
+        assert_eq!(untracked.status, "untracked");
+        assert_eq!(untracked.old_path, None);
+        assert_eq!(untracked.additions, 1);
+
+        // 暂存的重命名被识别
+        fs::rename(repo_path.join("old_name.txt"), repo_path.join("new_name.txt")).unwrap();
+        GitService::stage_paths(repo_path, &["old_name.txt".to_string(), "new_name.txt".to_string()]).unwrap();
+        let staged = GitService::get_diff(repo_path, &DiffTarget::Staged, &GitDiffOptions::default()).unwrap();
+        assert_eq!(staged.files.len(), 1);
+        assert_eq!(staged.files[0].status, "renamed");
+        assert_eq!(staged.files[0].old_path.as_deref(), Some("old_name.txt"));
+        assert_eq!(staged.files[0].new_path.as_deref(), Some("new_name.txt"));
+    }
+
+    #[test]
+    fn test_diff_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo(repo_path);
+        let content: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        fs::write(repo_path.join("file.txt"), &content).unwrap();
+        commit_all(repo_path, "Initial");
+
+        // 只有空白变化
+        fs::write(repo_path.join("file.txt"), content.replace("line 50\n", "line   50  \n")).unwrap();
+        let options = GitDiffOptions {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        let diff = GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &options).unwrap();
+        assert!(diff.files.iter().all(|f| f.hunks.is_empty()));
+
+        let options = GitDiffOptions {
+            context_lines: 0,
+            ..Default::default()
+        };
+        let diff = GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &options).unwrap();
+        assert_eq!(diff.files[0].hunks[0].lines.len(), 2);
+
+        // 行数和文件大小上限
+        let rewritten: String = (1..=100).map(|i| format!("changed {}\n", i)).collect();
+        fs::write(repo_path.join("file.txt"), &rewritten).unwrap();
+        let options = GitDiffOptions {
+            max_lines_per_file: 10,
+            ..Default::default()
+        };
+        let diff = GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &options).unwrap();
+        let file = &diff.files[0];
+        assert!(file.truncated);
+        assert_eq!(file.hunks.iter().map(|h| h.lines.len()).sum::<usize>(), 10);
+        assert_eq!(file.additions, 100);
+
+        let options = GitDiffOptions {
+            max_file_bytes: 100,
+            ..Default::default()
+        };
+        let diff = GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &options).unwrap();
+        assert!(diff.files[0].too_large);
+        assert!(diff.files[0].hunks.is_empty());
+
+        let options = GitDiffOptions {
+            paths: vec!["other.txt".to_string()],
+            ..Default::default()
+        };
+        assert!(GitService::get_diff(repo_path, &DiffTarget::WorkingTree, &options).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_branch_diff_uses_merge_base() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo(repo_path);
+        fs::write(repo_path.join("base.txt"), "base\n").unwrap();
+        commit_all(repo_path, "Initial");
+        let repo = GitService::open_repository(repo_path).unwrap();
+        let source = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        GitService::create_branch(repo_path, "feature", None).unwrap();
+        GitService::checkout_branch(repo_path, "feature").unwrap();
+        fs::write(repo_path.join("feature.txt"), "feature\n").unwrap();
+        commit_all(repo_path, "Feature work");
+
+        // 源分支上之后的提交不出现在比较结果中
+        GitService::checkout_branch(repo_path, &source).unwrap();
+        fs::write(repo_path.join("main.txt"), "main\n").unwrap();
+        commit_all(repo_path, "Main work");
+        GitService::checkout_branch(repo_path, "feature").unwrap();
+
+        let target = DiffTarget::Branch { base: source.clone() };
+        let diff = GitService::get_diff(repo_path, &target, &GitDiffOptions::default()).unwrap();
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].new_path.as_deref(), Some("feature.txt"));
+        assert_eq!(diff.files[0].status, "added");
+        assert!(diff.merge_base.is_some());
+
+        let target = DiffTarget::Branch { base: "missing".to_string() };
+        assert!(GitService::get_diff(repo_path, &target, &GitDiffOptions::default()).is_err());
+    }
+}
\ No newline at end of file
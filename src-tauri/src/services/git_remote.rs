@@ -0,0 +1,191 @@
+use git2::{Cred, CredentialType, RemoteCallbacks};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// 默认的远程仓库名称
+pub const DEFAULT_REMOTE: &str = "origin";
+
+/// 认证失败后重试的最大次数，避免凭据无效时无限循环
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// 远程操作所处的阶段
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RemoteStage {
+    /// 接收对象
+    Receiving,
+    /// 解析增量
+    Resolving,
+    /// 推送对象
+    Pushing,
+    /// 远程仓库返回的消息
+    Message,
+}
+
+/// 远程操作的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteProgress {
+    pub stage: RemoteStage,
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+    pub message: Option<String>,
+}
+
+pub type RemoteProgressCallback = Box<dyn FnMut(RemoteProgress)>;
+
+/// 拉取时整合远程更改的方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum PullStrategy {
+    /// 只允许快进
+    #[default]
+    FastForwardOnly,
+    Merge,
+    Rebase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PullStatus {
+    UpToDate,
+    FastForwarded,
+    Merged,
+    Rebased,
+    /// 合并或变基产生冲突，仓库停留在进行中的状态，等待解决
+    Conflicts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchResult {
+    pub remote: String,
+    pub received_objects: usize,
+    pub received_bytes: usize,
+    /// 被更新的引用
+    pub updated_refs: Vec<String>,
+    /// 因远程分支已删除而被移除的远程跟踪分支
+    pub pruned_refs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullResult {
+    pub status: PullStatus,
+    /// 拉取后HEAD指向的提交
+    pub head: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+/// 推送选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushOptions {
+    /// 默认为当前分支的上游远程或 origin
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// 本地分支，默认为当前分支
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// 远程分支名称，默认与本地分支相同
+    #[serde(default)]
+    pub remote_branch: Option<String>,
+    /// 推送后将远程分支设置为上游
+    #[serde(default)]
+    pub set_upstream: bool,
+    /// 仅当远程分支仍与本地记录的远程跟踪分支一致时强制推送
+    #[serde(default)]
+    pub force_with_lease: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    pub remote_branch: String,
+    pub upstream_set: bool,
+}
+
+/// 依次尝试 ssh-agent、git 凭据助手和默认凭据
+fn credentials(
+    config: &git2::Config,
+    url: &str,
+    username: Option<&str>,
+    allowed: CredentialType,
+) -> Result<Cred, git2::Error> {
+    if allowed.contains(CredentialType::SSH_KEY) {
+        return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+    }
+    if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+        return Cred::credential_helper(config, url, username);
+    }
+    if allowed.contains(CredentialType::USERNAME) {
+        return Cred::username(username.unwrap_or("git"));
+    }
+    if allowed.contains(CredentialType::DEFAULT) {
+        return Cred::default();
+    }
+    Err(git2::Error::from_str("No supported authentication method"))
+}
+
+/// 创建带有认证和进度回调的远程回调
+pub fn remote_callbacks<'a>(
+    config: git2::Config,
+    progress: Option<RemoteProgressCallback>,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        attempts += 1;
+        if attempts > MAX_CREDENTIAL_ATTEMPTS {
+            return Err(git2::Error::from_str("Authentication failed"));
+        }
+        credentials(&config, url, username, allowed)
+    });
+
+    if let Some(progress) = progress {
+        // 多个回调共用同一个进度函数
+        let progress = Rc::new(RefCell::new(progress));
+
+        let transfer = Rc::clone(&progress);
+        callbacks.transfer_progress(move |stats| {
+            let resolving = stats.received_objects() == stats.total_objects() && stats.total_deltas() > 0;
+            let (stage, current, total) = if resolving {
+                (RemoteStage::Resolving, stats.indexed_deltas(), stats.total_deltas())
+            } else {
+                (RemoteStage::Receiving, stats.received_objects(), stats.total_objects())
+            };
+            (transfer.borrow_mut())(RemoteProgress {
+                stage,
+                current,
+                total,
+                bytes: stats.received_bytes(),
+                message: None,
+            });
+            true
+        });
+
+        let sideband = Rc::clone(&progress);
+        callbacks.sideband_progress(move |data| {
+            let message = String::from_utf8_lossy(data).trim().to_string();
+            if !message.is_empty() {
+                (sideband.borrow_mut())(RemoteProgress {
+                    stage: RemoteStage::Message,
+                    current: 0,
+                    total: 0,
+                    bytes: 0,
+                    message: Some(message),
+                });
+            }
+            true
+        });
+
+        callbacks.push_transfer_progress(move |current, total, bytes| {
+            (progress.borrow_mut())(RemoteProgress {
+                stage: RemoteStage::Pushing,
+                current,
+                total,
+                bytes,
+                message: None,
+            });
+        });
+    }
+
+    callbacks
+}
\ No newline at end of file
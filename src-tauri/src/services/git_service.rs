@@ -1,11 +1,20 @@
 use anyhow::{anyhow, Result};
 use git2::{
-    Branch, BranchType, Repository, Status, StatusOptions, 
+    AnnotatedCommit, ApplyLocation, ApplyOptions, Branch, BranchType, Commit, Diff, DiffOptions,
+    Direction, FetchPrune, Index, IndexAddOption, Oid, Remote, Repository, RepositoryState, Signature,
+    Sort, Status, StatusOptions, Time, Tree,
     WorktreeAddOptions, WorktreePruneOptions
 };
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::services::git_diff::{self, DiffTarget, GitDiff, GitDiffFile, GitDiffOptions, GitDiffStats};
+use crate::services::git_remote::{
+    self, FetchResult, PullResult, PullStatus, PullStrategy, PushOptions, PushResult, RemoteProgressCallback,
+    DEFAULT_REMOTE,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitStatus {
     pub current_branch: Option<String>,
@@ -32,6 +41,10 @@ pub struct GitBranch {
     pub is_head: bool,
     pub is_remote: bool,
     pub upstream: Option<String>,
+    /// 分支最新提交的时间
+    pub last_commit_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// 是否已合并到基准分支，仅在指定基准分支时设置
+    pub is_merged: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +56,191 @@ pub struct WorktreeInfo {
     pub is_prunable: bool,
 }
 
+/// 创建worktree的选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorktreeOptions {
+    /// 要检出的分支，不存在时从 start_point 创建；为空时使用worktree名称
+    pub branch: Option<String>,
+    /// 新分支或分离HEAD的起点，可以是分支、远程分支、标签或提交，默认为HEAD
+    pub start_point: Option<String>,
+    /// 以分离HEAD检出 start_point，不创建分支
+    pub detach: bool,
+}
+
+/// 建议的提交说明摘要行最大长度，可作为 `CommitOptions::max_summary_length` 使用
+pub const MAX_COMMIT_SUMMARY_LENGTH: usize = 72;
+
+/// 文件差异中的一个块，`header` 用于暂存或取消暂存该块
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+/// 提交选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitOptions {
+    pub message: String,
+    /// 修改最近一次提交
+    #[serde(default)]
+    pub amend: bool,
+    /// 允许没有更改的提交
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// 覆盖仓库或用户配置中的作者
+    #[serde(default)]
+    pub author_name: Option<String>,
+    #[serde(default)]
+    pub author_email: Option<String>,
+    /// 去掉以 `#` 开头的注释行，用于来自编辑器模板或 MERGE_MSG 的说明
+    #[serde(default)]
+    pub strip_comments: bool,
+    /// 摘要行的长度限制，为空时不限制
+    #[serde(default)]
+    pub max_summary_length: Option<usize>,
+}
+
+/// 提交日志默认每页的数量
+pub const DEFAULT_LOG_PAGE_SIZE: usize = 50;
+/// 提交日志每页的最大数量
+pub const MAX_LOG_PAGE_SIZE: usize = 500;
+
+/// 提交日志的查询条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitLogQuery {
+    /// 起始的分支、标签或提交，默认为HEAD
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// 跳过的匹配提交数量
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 只包含修改了该文件或目录的提交
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 作者名称或邮箱包含该文本（不区分大小写）
+    #[serde(default)]
+    pub author: Option<String>,
+    /// 提交说明包含该文本（不区分大小写）
+    #[serde(default)]
+    pub message: Option<String>,
+    /// 提交时间范围（含边界）
+    #[serde(default)]
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub authored_at: chrono::DateTime<chrono::Utc>,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub committed_at: chrono::DateTime<chrono::Utc>,
+    pub parent_ids: Vec<String>,
+}
+
+/// 一页提交日志
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitLogPage {
+    pub commits: Vec<GitCommitInfo>,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// 提交详情，`files` 中只包含文件和增删行数，不含差异块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommitDetails {
+    pub commit: GitCommitInfo,
+    pub message: String,
+    pub files: Vec<GitDiffFile>,
+    pub stats: GitDiffStats,
+}
+
+/// 仓库中正在进行的多步操作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum GitOperation {
+    None,
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Other,
+}
+
+/// 进行中操作的状态及尚未解决冲突的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitOperationState {
+    pub operation: GitOperation,
+    pub conflicts: Vec<String>,
+    /// 变基时当前提交的序号和总提交数
+    pub rebase_progress: Option<(usize, usize)>,
+}
+
+/// 冲突中一方的文件版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictSide {
+    pub path: String,
+    pub id: String,
+    pub mode: u32,
+    pub is_binary: bool,
+    /// 二进制文件不返回内容
+    pub content: Option<String>,
+}
+
+/// 冲突文件的共同祖先、我方和对方版本，某一方删除了文件时对应版本为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitConflict {
+    pub path: String,
+    pub ancestor: Option<ConflictSide>,
+    pub ours: Option<ConflictSide>,
+    pub theirs: Option<ConflictSide>,
+}
+
+/// 解决冲突的方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// 使用手动合并后的内容
+    Merged(String),
+    /// 删除文件
+    Delete,
+}
+
+/// 储藏记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStash {
+    pub index: usize,
+    pub id: String,
+    pub message: String,
+    /// 储藏时所在的分支
+    pub branch: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub has_untracked: bool,
+}
+
+/// 储藏选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StashOptions {
+    pub message: Option<String>,
+    /// 同时储藏未跟踪的文件
+    pub include_untracked: bool,
+    /// 储藏后保留已暂存的更改
+    pub keep_index: bool,
+}
+
 pub struct GitService;
 
 impl GitService {
@@ -202,6 +400,8 @@ impl GitService {
                     is_head,
                     is_remote: false,
                     upstream,
+                    last_commit_at: Self::branch_commit_time(&branch),
+                    is_merged: None,
                 });
             }
         }
@@ -216,6 +416,8 @@ impl GitService {
                     is_head: false,
                     is_remote: true,
                     upstream: None,
+                    last_commit_at: Self::branch_commit_time(&branch),
+                    is_merged: None,
                 });
             }
         }
@@ -223,33 +425,319 @@ impl GitService {
         Ok(branches)
     }
 
-    /// 创建新的worktree
+    fn branch_commit_time(branch: &Branch) -> Option<chrono::DateTime<chrono::Utc>> {
+        branch
+            .get()
+            .peel_to_commit()
+            .ok()
+            .map(|commit| Self::to_datetime(commit.time()))
+    }
+
+    /// `ancestor` 是否已包含在 `commit` 的历史中
+    fn is_merged_into(repo: &Repository, ancestor: Oid, commit: Oid) -> Result<bool> {
+        Ok(ancestor == commit || repo.graph_descendant_of(commit, ancestor)?)
+    }
+
+    /// 获取分支列表，并标记各分支是否已合并到基准分支
+    pub fn get_branches_with_merge_status<P: AsRef<Path>>(repo_path: P, base_branch: &str) -> Result<Vec<GitBranch>> {
+        let repo_path = repo_path.as_ref();
+        let repo = Self::open_repository(repo_path)?;
+        let base = repo
+            .revparse_single(base_branch)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| anyhow!("Base branch not found: {}", base_branch))?
+            .id();
+
+        let mut branches = Self::get_branches(repo_path)?;
+        for branch in &mut branches {
+            let branch_type = if branch.is_remote { BranchType::Remote } else { BranchType::Local };
+            let tip = repo
+                .find_branch(&branch.name, branch_type)
+                .ok()
+                .and_then(|b| b.get().peel_to_commit().ok())
+                .map(|commit| commit.id());
+            if let Some(tip) = tip {
+                branch.is_merged = Some(Self::is_merged_into(&repo, tip, base)?);
+            }
+        }
+
+        Ok(branches)
+    }
+
+    /// 删除本地分支。未强制删除时要求分支已合并到上游（没有上游时为HEAD），
+    /// `delete_remote` 为 true 时同时删除远程分支
+    pub fn delete_branch<P: AsRef<Path>>(
+        repo_path: P,
+        branch_name: &str,
+        force: bool,
+        delete_remote: bool,
+    ) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        let mut branch = repo
+            .find_branch(branch_name, BranchType::Local)
+            .map_err(|_| anyhow!("Branch not found: {}", branch_name))?;
+        let branch_ref = format!("refs/heads/{}", branch_name);
+
+        if let Some(path) = Self::branch_checked_out_at(&repo, &branch_ref)? {
+            return Err(anyhow!(
+                "Cannot delete branch '{}' checked out at {}",
+                branch_name,
+                path.display()
+            ));
+        }
+
+        let upstream = branch.upstream().ok();
+        if !force {
+            let tip = branch.get().peel_to_commit()?.id();
+            let target = match &upstream {
+                Some(upstream) => upstream.get().peel_to_commit()?.id(),
+                None => repo.head()?.peel_to_commit()?.id(),
+            };
+            if !Self::is_merged_into(&repo, tip, target)? {
+                return Err(anyhow!("Branch '{}' is not fully merged", branch_name));
+            }
+        }
+
+        if delete_remote {
+            // 有上游时删除上游分支，否则删除 origin 上的同名分支
+            let (remote_name, remote_ref) = match &upstream {
+                Some(_) => {
+                    let remote = repo.branch_upstream_remote(&branch_ref)?;
+                    let merge = repo.config()?.get_string(&format!("branch.{}.merge", branch_name))?;
+                    (
+                        remote.as_str().ok_or_else(|| anyhow!("Invalid remote name"))?.to_string(),
+                        merge,
+                    )
+                }
+                None => (DEFAULT_REMOTE.to_string(), branch_ref.clone()),
+            };
+            Self::delete_remote_ref(&repo, &remote_name, &remote_ref)?;
+        }
+
+        branch.delete()?;
+        Ok(())
+    }
+
+    /// 删除远程仓库中的分支
+    pub fn delete_remote_branch<P: AsRef<Path>>(repo_path: P, remote_name: &str, branch_name: &str) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        Self::delete_remote_ref(&repo, remote_name, &format!("refs/heads/{}", branch_name))
+    }
+
+    fn delete_remote_ref(repo: &Repository, remote_name: &str, remote_ref: &str) -> Result<()> {
+        let mut remote = repo
+            .find_remote(remote_name)
+            .map_err(|e| anyhow!("Remote not found: {}: {}", remote_name, e))?;
+        Self::push_refspec(repo, &mut remote, &format!(":{}", remote_ref), None, None)?;
+
+        // 删除对应的远程跟踪分支
+        let tracking_ref = format!(
+            "refs/remotes/{}/{}",
+            remote_name,
+            remote_ref.trim_start_matches("refs/heads/")
+        );
+        if let Ok(mut reference) = repo.find_reference(&tracking_ref) {
+            reference.delete()?;
+        }
+        Ok(())
+    }
+
+    /// 重命名本地分支，并更新检出该分支的worktree
+    pub fn rename_branch<P: AsRef<Path>>(repo_path: P, old_name: &str, new_name: &str, force: bool) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        let mut branch = repo
+            .find_branch(old_name, BranchType::Local)
+            .map_err(|_| anyhow!("Branch not found: {}", old_name))?;
+        if !force && repo.find_branch(new_name, BranchType::Local).is_ok() {
+            return Err(anyhow!("Branch already exists: {}", new_name));
+        }
+
+        let old_ref = format!("refs/heads/{}", old_name);
+        let new_ref = format!("refs/heads/{}", new_name);
+        let checked_out_at = Self::branch_checked_out_at(&repo, &old_ref)?;
+        branch
+            .rename(new_name, force)
+            .map_err(|e| anyhow!("Failed to rename branch: {}", e))?;
+
+        // 确保检出旧分支的worktree指向新分支
+        if let Some(path) = checked_out_at {
+            let checked_out = Repository::open(&path)?;
+            if checked_out.find_reference("HEAD")?.symbolic_target() == Some(old_ref.as_str()) {
+                checked_out.set_head(&new_ref)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 创建新的worktree并检出指定分支，分支不存在时从HEAD创建
     pub fn create_worktree<P: AsRef<Path>>(
         repo_path: P,
         worktree_name: &str,
         worktree_path: P,
         branch_name: Option<&str>,
     ) -> Result<()> {
+        let options = WorktreeOptions {
+            branch: branch_name.map(str::to_string),
+            ..Default::default()
+        };
+        Self::create_worktree_with_options(repo_path, worktree_name, worktree_path, &options)?;
+        Ok(())
+    }
+
+    /// 分支在主仓库或某个worktree中被检出时，返回其工作目录
+    fn branch_checked_out_at(repo: &Repository, branch_ref: &str) -> Result<Option<PathBuf>> {
+        let is_checked_out = |repo: &Repository| {
+            repo.find_reference("HEAD")
+                .ok()
+                .and_then(|head| head.symbolic_target().map(|target| target == branch_ref))
+                .unwrap_or(false)
+        };
+
+        // 从worktree打开时也要检查主仓库
+        let main_repo = if repo.is_worktree() {
+            let commondir = fs::read_to_string(repo.path().join("commondir"))?;
+            Repository::open(repo.path().join(commondir.trim()))?
+        } else {
+            Repository::open(repo.path())?
+        };
+        if is_checked_out(&main_repo) {
+            return Ok(Some(main_repo.workdir().unwrap_or(main_repo.path()).to_path_buf()));
+        }
+
+        for name in main_repo.worktrees()?.iter().flatten() {
+            let worktree = main_repo.find_worktree(name)?;
+            if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+                if is_checked_out(&worktree_repo) {
+                    return Ok(Some(worktree.path().to_path_buf()));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 按选项创建worktree：检出已有分支，从分支、远程分支、标签或提交创建新分支，
+    /// 或以分离HEAD检出。从远程分支创建时设置上游，分支已在其他位置检出时失败
+    pub fn create_worktree_with_options<P: AsRef<Path>>(
+        repo_path: P,
+        worktree_name: &str,
+        worktree_path: P,
+        options: &WorktreeOptions,
+    ) -> Result<WorktreeInfo> {
         let repo = Self::open_repository(repo_path)?;
-        
-        let opts = WorktreeAddOptions::new();
-        
-        // 如果指定了分支名，创建对应的引用
-        if let Some(branch) = branch_name {
-            let branch_ref = format!("refs/heads/{}", branch);
-            
-            // 检查分支是否存在，如果不存在则创建
-            if repo.find_reference(&branch_ref).is_err() {
-                let head = repo.head()?;
-                let target = head.target().ok_or_else(|| anyhow!("No target for HEAD"))?;
-                let commit = repo.find_commit(target)?;
-                repo.branch(branch, &commit, false)?;
+        let worktree_path = worktree_path.as_ref();
+
+        let start = match options.start_point.as_deref() {
+            Some(start) => Some(
+                repo.revparse_single(start)
+                    .and_then(|object| object.peel_to_commit())
+                    .map_err(|_| anyhow!("Start point not found: {}", start))?,
+            ),
+            None => None,
+        };
+        let head_commit = || -> Result<Commit> {
+            Self::head_commit(&repo)?.ok_or_else(|| anyhow!("Repository has no commits"))
+        };
+
+        if options.detach {
+            if options.branch.is_some() {
+                return Err(anyhow!("Cannot check out a branch in a detached worktree"));
+            }
+            let commit = match start {
+                Some(commit) => commit,
+                None => head_commit()?,
+            };
+
+            // libgit2 只能通过分支创建worktree，先检出临时分支再分离HEAD。
+            // 临时分支名带随机后缀且不覆盖已有分支，避免影响用户的同名分支
+            let temp_branch = format!(
+                "workhorse-worktree-{}-{}",
+                worktree_name,
+                uuid::Uuid::new_v4().simple()
+            );
+            let mut branch = repo.branch(&temp_branch, &commit, false)?;
+            let mut opts = WorktreeAddOptions::new();
+            opts.reference(Some(branch.get()));
+            let result = repo
+                .worktree(worktree_name, worktree_path, Some(&opts))
+                .and_then(|worktree| Repository::open_from_worktree(&worktree))
+                .and_then(|worktree_repo| worktree_repo.set_head_detached(commit.id()));
+
+            // 清理失败不覆盖创建worktree本身的错误
+            let cleanup = branch.delete();
+            result.map_err(|e| anyhow!("Failed to create worktree: {}", e))?;
+            if let Err(e) = cleanup {
+                eprintln!("警告: 删除临时分支 {} 失败: {}", temp_branch, e);
+            }
+        } else {
+            let branch_name = options.branch.as_deref().unwrap_or(worktree_name);
+            let branch_ref = format!("refs/heads/{}", branch_name);
+
+            let (branch, created) = match repo.find_branch(branch_name, BranchType::Local) {
+                Ok(branch) => {
+                    if options.start_point.is_some() {
+                        return Err(anyhow!("Branch already exists: {}", branch_name));
+                    }
+                    if let Some(path) = Self::branch_checked_out_at(&repo, &branch_ref)? {
+                        return Err(anyhow!(
+                            "Branch '{}' is already checked out at {}",
+                            branch_name,
+                            path.display()
+                        ));
+                    }
+                    (branch, false)
+                }
+                Err(_) => {
+                    // 未指定起点时，存在同名远程分支则从远程分支创建
+                    let upstream = match options.start_point.as_deref() {
+                        Some(start) => repo
+                            .find_branch(start, BranchType::Remote)
+                            .ok()
+                            .map(|_| start.to_string()),
+                        None => {
+                            let remote_branch = format!("{}/{}", DEFAULT_REMOTE, branch_name);
+                            repo.find_branch(&remote_branch, BranchType::Remote)
+                                .ok()
+                                .map(|_| remote_branch)
+                        }
+                    };
+                    let commit = match (start, upstream.as_deref()) {
+                        (Some(commit), _) => commit,
+                        (None, Some(upstream)) => repo
+                            .find_branch(upstream, BranchType::Remote)?
+                            .get()
+                            .peel_to_commit()?,
+                        (None, None) => head_commit()?,
+                    };
+
+                    let mut branch = repo.branch(branch_name, &commit, false)?;
+                    if let Some(upstream) = upstream.as_deref() {
+                        branch.set_upstream(Some(upstream))?;
+                    }
+                    (branch, true)
+                }
+            };
+
+            let mut opts = WorktreeAddOptions::new();
+            opts.reference(Some(branch.get()));
+            if let Err(e) = repo.worktree(worktree_name, worktree_path, Some(&opts)) {
+                // 创建失败时删除新建的分支
+                if created {
+                    let mut branch = branch;
+                    let _ = branch.delete();
+                }
+                return Err(anyhow!("Failed to create worktree: {}", e));
             }
         }
 
-        repo.worktree(worktree_name, worktree_path.as_ref(), Some(&opts))?;
-        
-        Ok(())
+        Ok(WorktreeInfo {
+            name: worktree_name.to_string(),
+            path: worktree_path.to_path_buf(),
+            branch: Self::get_worktree_branch(&repo, worktree_name).ok(),
+            is_locked: false,
+            is_prunable: false,
+        })
     }
 
     /// 获取所有worktree列表
@@ -290,6 +778,9 @@ impl GitService {
         let worktree_repo = Repository::open(worktree_path)?;
         
         let head = worktree_repo.head()?;
+        if !head.is_branch() {
+            return Err(anyhow!("Worktree HEAD is detached"));
+        }
         if let Some(name) = head.shorthand() {
             Ok(name.to_string())
         } else {
@@ -313,6 +804,22 @@ impl GitService {
         Ok(())
     }
 
+    /// 清理工作目录已不存在的worktree，返回被清理的worktree名称
+    pub fn prune_worktrees<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        let mut pruned = Vec::new();
+
+        for name in repo.worktrees()?.iter().flatten() {
+            let worktree = repo.find_worktree(name)?;
+            if worktree.is_prunable(None).unwrap_or(false) {
+                worktree.prune(None)?;
+                pruned.push(name.to_string());
+            }
+        }
+
+        Ok(pruned)
+    }
+
     /// 切换分支
     pub fn checkout_branch<P: AsRef<Path>>(
         repo_path: P,
@@ -354,107 +861,2336 @@ impl GitService {
         Ok(())
     }
 
-    /// 设置Git全局忽略配置
-    pub fn set_global_gitignore<P: AsRef<Path>>(gitignore_path: P) -> Result<()> {
-        let mut config = git2::Config::open_default()?;
-        let path_str = gitignore_path.as_ref().to_string_lossy();
-        config.set_str("core.excludesfile", &path_str)?;
+    /// 暂存指定路径（文件或目录）的更改，包括删除
+    pub fn stage_paths<P: AsRef<Path>>(repo_path: P, paths: &[String]) -> Result<()> {
+        if paths.is_empty() {
+            return Err(anyhow!("No paths specified"));
+        }
+
+        let repo = Self::open_repository(repo_path)?;
+        let mut index = repo.index()?;
+        index.add_all(paths.iter(), IndexAddOption::DEFAULT, None)?;
+        // add_all 不处理已删除的文件
+        index.update_all(paths.iter(), None)?;
+        index.write()?;
+
         Ok(())
     }
 
-    /// 获取Git全局忽略配置
-    pub fn get_global_gitignore() -> Result<Option<String>> {
-        let config = git2::Config::open_default()?;
-        match config.get_string("core.excludesfile") {
-            Ok(path) => Ok(Some(path)),
-            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
-            Err(e) => Err(anyhow!("Failed to get global gitignore: {}", e)),
+    /// 取消暂存指定路径，恢复为HEAD中的状态
+    pub fn unstage_paths<P: AsRef<Path>>(repo_path: P, paths: &[String]) -> Result<()> {
+        if paths.is_empty() {
+            return Err(anyhow!("No paths specified"));
         }
-    }
 
-    /// 验证路径是否为Git仓库
-    pub fn is_git_repository<P: AsRef<Path>>(path: P) -> bool {
-        Repository::open(path).is_ok()
+        let repo = Self::open_repository(repo_path)?;
+        match Self::head_commit(&repo)? {
+            Some(head) => repo.reset_default(Some(head.as_object()), paths.iter())?,
+            None => {
+                // 还没有提交时直接从索引中移除
+                let mut index = repo.index()?;
+                index.remove_all(paths.iter(), None)?;
+                index.write()?;
+            }
+        }
+
+        Ok(())
     }
 
-    /// 初始化新的Git仓库
-    pub fn init_repository<P: AsRef<Path>>(path: P, bare: bool) -> Result<Repository> {
-        if bare {
-            Repository::init_bare(path)
-        } else {
-            Repository::init(path)
+    /// 丢弃工作区中指定路径未暂存的更改，未跟踪的文件会被删除
+    pub fn discard_changes<P: AsRef<Path>>(repo_path: P, paths: &[String]) -> Result<()> {
+        if paths.is_empty() {
+            return Err(anyhow!("No paths specified"));
         }
-        .map_err(|e| anyhow!("Failed to initialize repository: {}", e))
-    }
 
-    /// 克隆远程仓库
-    pub fn clone_repository<P: AsRef<Path>>(
-        url: &str,
-        path: P,
-        progress_callback: Option<Box<dyn FnMut(git2::Progress) -> bool>>,
-    ) -> Result<Repository> {
-        let mut builder = git2::build::RepoBuilder::new();
-        
-        if let Some(mut callback) = progress_callback {
-            let mut remote_callbacks = git2::RemoteCallbacks::new();
-            remote_callbacks.transfer_progress(move |progress| {
-                callback(progress)
-            });
-            
-            let mut fetch_options = git2::FetchOptions::new();
-            fetch_options.remote_callbacks(remote_callbacks);
-            builder.fetch_options(fetch_options);
+        let repo = Self::open_repository(repo_path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("Repository has no working directory"))?
+            .to_path_buf();
+
+        // 删除未跟踪的文件
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+        for path in paths {
+            opts.pathspec(path);
+        }
+        let untracked: Vec<PathBuf> = repo
+            .statuses(Some(&mut opts))?
+            .iter()
+            .filter(|entry| entry.status().contains(Status::WT_NEW))
+            .filter_map(|entry| entry.path().map(|path| workdir.join(path)))
+            .collect();
+        for path in untracked {
+            std::fs::remove_file(&path)
+                .map_err(|e| anyhow!("Failed to remove untracked file {:?}: {}", path, e))?;
         }
 
-        builder.clone(url, path.as_ref())
-            .map_err(|e| anyhow!("Failed to clone repository: {}", e))
+        // 已跟踪的文件恢复为索引中的内容
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force();
+        for path in paths {
+            checkout.path(path);
+        }
+        repo.checkout_index(None, Some(&mut checkout))?;
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
-    use std::fs;
+    /// 获取文件的差异块，`staged` 为 true 时比较HEAD与索引，否则比较索引与工作区
+    pub fn get_file_hunks<P: AsRef<Path>>(repo_path: P, file_path: &str, staged: bool) -> Result<Vec<GitHunk>> {
+        let repo = Self::open_repository(repo_path)?;
+        let diff = Self::file_diff(&repo, file_path, staged, false)?;
+        Self::collect_hunks(&diff)
+    }
 
-    #[test]
-    fn test_init_repository() {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
+    /// 暂存文件中的一个差异块
+    pub fn stage_hunk<P: AsRef<Path>>(repo_path: P, file_path: &str, hunk_header: &str) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        let diff = Self::file_diff(&repo, file_path, false, false)?;
+        let hunk_index = Self::find_hunk(&diff, hunk_header)?;
+        Self::apply_hunk(&repo, &diff, hunk_index)
+    }
 
-        let repo = GitService::init_repository(repo_path, false).unwrap();
-        assert!(repo_path.join(".git").exists());
+    /// 取消暂存文件中的一个差异块，`hunk_header` 取自已暂存的差异
+    pub fn unstage_hunk<P: AsRef<Path>>(repo_path: P, file_path: &str, hunk_header: &str) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        let diff = Self::file_diff(&repo, file_path, true, false)?;
+        let hunk_index = Self::find_hunk(&diff, hunk_header)?;
+
+        // 反向差异中块的顺序不变，应用到索引即撤销该块
+        let reverse_diff = Self::file_diff(&repo, file_path, true, true)?;
+        Self::apply_hunk(&repo, &reverse_diff, hunk_index)
     }
 
-    #[test]
-    fn test_is_git_repository() {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
+    fn head_commit(repo: &Repository) -> Result<Option<Commit<'_>>> {
+        match repo.head() {
+            Ok(head) => Ok(Some(head.peel_to_commit()?)),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+            Err(e) => Err(anyhow!("Failed to resolve HEAD: {}", e)),
+        }
+    }
 
-        // 未初始化时应该返回false
-        assert!(!GitService::is_git_repository(repo_path));
+    fn file_diff<'a>(repo: &'a Repository, file_path: &str, staged: bool, reverse: bool) -> Result<Diff<'a>> {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(file_path)
+            .disable_pathspec_match(true)
+            .reverse(reverse);
 
-        // 初始化后应该返回true
-        GitService::init_repository(repo_path, false).unwrap();
-        assert!(GitService::is_git_repository(repo_path));
+        let diff = if staged {
+            let head_tree = Self::head_commit(repo)?.map(|commit| commit.tree()).transpose()?;
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?
+        } else {
+            opts.include_untracked(true).show_untracked_content(true);
+            repo.diff_index_to_workdir(None, Some(&mut opts))?
+        };
+        Ok(diff)
     }
 
-    #[test]
-    fn test_get_repository_status() {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
+    fn hunk_header(hunk: &git2::DiffHunk) -> String {
+        String::from_utf8_lossy(hunk.header()).trim_end().to_string()
+    }
 
-        // 初始化仓库
-        GitService::init_repository(repo_path, false).unwrap();
+    fn collect_hunks(diff: &Diff) -> Result<Vec<GitHunk>> {
+        let mut hunks = Vec::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            Some(&mut |_, hunk| {
+                hunks.push(GitHunk {
+                    header: Self::hunk_header(&hunk),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                });
+                true
+            }),
+            None,
+        )?;
+        Ok(hunks)
+    }
 
-        // 创建一个文件
-        let file_path = repo_path.join("test.txt");
-        fs::write(&file_path, "test content").unwrap();
+    fn find_hunk(diff: &Diff, hunk_header: &str) -> Result<usize> {
+        Self::collect_hunks(diff)?
+            .iter()
+            .position(|hunk| hunk.header == hunk_header.trim_end())
+            .ok_or_else(|| anyhow!("Hunk not found: {}", hunk_header))
+    }
 
-        // 获取状态
-        let status = GitService::get_repository_status(repo_path).unwrap();
-        assert!(status.is_dirty);
-        assert!(!status.files.is_empty());
-        assert_eq!(status.files[0].path, "test.txt");
+    /// 只将差异中的第 `hunk_index` 个块应用到索引
+    fn apply_hunk(repo: &Repository, diff: &Diff, hunk_index: usize) -> Result<()> {
+        let mut current = 0;
+        let mut opts = ApplyOptions::new();
+        opts.hunk_callback(|hunk| {
+            if hunk.is_none() {
+                return false;
+            }
+            let selected = current == hunk_index;
+            current += 1;
+            selected
+        });
+        repo.apply(diff, ApplyLocation::Index, Some(&mut opts))
+            .map_err(|e| anyhow!("Failed to apply hunk: {}", e))
+    }
+
+    /// 获取结构化的差异，包括文件、差异块和行号
+    pub fn get_diff<P: AsRef<Path>>(repo_path: P, target: &DiffTarget, options: &GitDiffOptions) -> Result<GitDiff> {
+        let repo = Self::open_repository(repo_path)?;
+        let mut opts = options.to_git2();
+        let mut merge_base = None;
+
+        let mut diff = match target {
+            DiffTarget::WorkingTree => {
+                opts.include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .show_untracked_content(true);
+                repo.diff_index_to_workdir(None, Some(&mut opts))?
+            }
+            DiffTarget::Staged => {
+                let head_tree = Self::head_commit(&repo)?.map(|commit| commit.tree()).transpose()?;
+                repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?
+            }
+            DiffTarget::Branch { base } => {
+                let head = Self::head_commit(&repo)?.ok_or_else(|| anyhow!("Repository has no commits"))?;
+                let base_commit = repo
+                    .revparse_single(base)
+                    .and_then(|object| object.peel_to_commit())
+                    .map_err(|e| anyhow!("Failed to resolve base branch {}: {}", base, e))?;
+                let base_oid = repo.merge_base(base_commit.id(), head.id())?;
+                merge_base = Some(base_oid.to_string());
+
+                let base_tree = repo.find_commit(base_oid)?.tree()?;
+                repo.diff_tree_to_tree(Some(&base_tree), Some(&head.tree()?), Some(&mut opts))?
+            }
+        };
+
+        git_diff::find_renames(&mut diff, options)?;
+        git_diff::build_diff(&diff, options, merge_base)
+    }
+
+    fn to_datetime(time: Time) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(time.seconds(), 0).unwrap_or_default()
+    }
+
+    fn commit_info(commit: &Commit) -> Result<GitCommitInfo> {
+        let id = commit.id().to_string();
+        let short_id = commit
+            .as_object()
+            .short_id()
+            .ok()
+            .and_then(|buf| buf.as_str().map(str::to_string))
+            .unwrap_or_else(|| id[..7].to_string());
+        let author = commit.author();
+        let committer = commit.committer();
+
+        Ok(GitCommitInfo {
+            id,
+            short_id,
+            summary: commit.summary().unwrap_or("").to_string(),
+            author_name: author.name().unwrap_or("").to_string(),
+            author_email: author.email().unwrap_or("").to_string(),
+            authored_at: Self::to_datetime(author.when()),
+            committer_name: committer.name().unwrap_or("").to_string(),
+            committer_email: committer.email().unwrap_or("").to_string(),
+            committed_at: Self::to_datetime(committer.when()),
+            parent_ids: commit.parent_ids().map(|id| id.to_string()).collect(),
+        })
+    }
+
+    /// 提交是否修改了指定路径（与第一个父提交比较）
+    fn commit_touches_path(commit: &Commit, path: &Path) -> Result<bool> {
+        let entry_id = |tree: &Tree| tree.get_path(path).ok().map(|entry| entry.id());
+        let current = entry_id(&commit.tree()?);
+        let previous = match commit.parent(0) {
+            Ok(parent) => entry_id(&parent.tree()?),
+            Err(_) => None,
+        };
+        Ok(current != previous)
+    }
+
+    fn matches_log_query(commit: &Commit, query: &CommitLogQuery) -> Result<bool> {
+        let committed_at = Self::to_datetime(commit.committer().when());
+        if query.since.is_some_and(|since| committed_at < since)
+            || query.until.is_some_and(|until| committed_at > until)
+        {
+            return Ok(false);
+        }
+
+        if let Some(author) = query.author.as_deref() {
+            let author = author.to_lowercase();
+            let signature = commit.author();
+            let name = signature.name().unwrap_or("").to_lowercase();
+            let email = signature.email().unwrap_or("").to_lowercase();
+            if !name.contains(&author) && !email.contains(&author) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(message) = query.message.as_deref() {
+            let commit_message = commit.message().unwrap_or("").to_lowercase();
+            if !commit_message.contains(&message.to_lowercase()) {
+                return Ok(false);
+            }
+        }
+
+        match query.path.as_deref() {
+            Some(path) => Self::commit_touches_path(commit, Path::new(path.trim_end_matches('/'))),
+            None => Ok(true),
+        }
+    }
+
+    /// 分页获取提交日志，按提交时间从新到旧排列
+    pub fn get_commit_log<P: AsRef<Path>>(repo_path: P, query: &CommitLogQuery) -> Result<CommitLogPage> {
+        let repo = Self::open_repository(repo_path)?;
+        let limit = query.limit.unwrap_or(DEFAULT_LOG_PAGE_SIZE).clamp(1, MAX_LOG_PAGE_SIZE);
+        let mut page = CommitLogPage {
+            commits: Vec::new(),
+            offset: query.offset,
+            has_more: false,
+        };
+
+        let start = match query.reference.as_deref() {
+            Some(reference) => repo
+                .revparse_single(reference)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|e| anyhow!("Failed to resolve {}: {}", reference, e))?,
+            None => match Self::head_commit(&repo)? {
+                Some(head) => head,
+                // 还没有提交
+                None => return Ok(page),
+            },
+        };
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
+        revwalk.push(start.id())?;
+
+        let mut skipped = 0;
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            if !Self::matches_log_query(&commit, query)? {
+                continue;
+            }
+            if skipped < query.offset {
+                skipped += 1;
+                continue;
+            }
+            if page.commits.len() == limit {
+                page.has_more = true;
+                break;
+            }
+            page.commits.push(Self::commit_info(&commit)?);
+        }
+
+        Ok(page)
+    }
+
+    /// 获取提交详情，包括完整说明和修改的文件
+    pub fn get_commit_details<P: AsRef<Path>>(repo_path: P, commit_id: &str) -> Result<GitCommitDetails> {
+        let repo = Self::open_repository(repo_path)?;
+        let commit = repo
+            .revparse_single(commit_id)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| anyhow!("Commit not found: {}: {}", commit_id, e))?;
+
+        // 与第一个父提交比较，根提交与空树比较
+        let parent_tree = commit.parent(0).ok().map(|parent| parent.tree()).transpose()?;
+        let options = GitDiffOptions {
+            max_lines_per_file: 0,
+            ..Default::default()
+        };
+        let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options.to_git2()))?;
+        git_diff::find_renames(&mut diff, &options)?;
+        let diff = git_diff::build_diff(&diff, &options, None)?;
+
+        Ok(GitCommitDetails {
+            commit: Self::commit_info(&commit)?,
+            message: commit.message().unwrap_or("").to_string(),
+            files: diff.files,
+            stats: diff.stats,
+        })
+    }
+
+    /// 校验并整理提交说明：去掉行尾空白（`strip_comments` 时同时去掉注释行），要求摘要行不为空、
+    /// 不超过给定的长度限制，且与正文之间有空行
+    pub fn validate_commit_message(
+        message: &str,
+        strip_comments: bool,
+        max_summary_length: Option<usize>,
+    ) -> Result<String> {
+        let mut lines: Vec<&str> = Vec::new();
+        for line in message
+            .lines()
+            .filter(|line| !(strip_comments && line.starts_with('#')))
+            .map(str::trim_end)
+        {
+            // 合并连续的空行
+            if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let summary = lines.first().ok_or_else(|| anyhow!("Commit message is empty"))?;
+        if let Some(max_length) = max_summary_length {
+            if summary.chars().count() > max_length {
+                return Err(anyhow!("Commit summary exceeds {} characters", max_length));
+            }
+        }
+        if lines.get(1).is_some_and(|line| !line.is_empty()) {
+            return Err(anyhow!("Commit summary must be followed by a blank line"));
+        }
+
+        Ok(format!("{}\n", lines.join("\n")))
+    }
+
+    /// 从仓库/用户配置获取签名，参数不为空时覆盖对应字段
+    fn resolve_signature(repo: &Repository, name: Option<&str>, email: Option<&str>) -> Result<Signature<'static>> {
+        let config = repo.config()?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => config
+                .get_string("user.name")
+                .map_err(|_| anyhow!("Git user.name is not configured"))?,
+        };
+        let email = match email {
+            Some(email) => email.to_string(),
+            None => config
+                .get_string("user.email")
+                .map_err(|_| anyhow!("Git user.email is not configured"))?,
+        };
+
+        Signature::now(&name, &email).map_err(|e| anyhow!("Invalid signature: {}", e))
+    }
+
+    fn ensure_changes(tree: &Tree, base: Option<&Tree>, allow_empty: bool) -> Result<()> {
+        let unchanged = match base {
+            Some(base) => base.id() == tree.id(),
+            None => tree.is_empty(),
+        };
+        if unchanged && !allow_empty {
+            return Err(anyhow!("Nothing to commit"));
+        }
+        Ok(())
+    }
+
+    /// 提交索引中的更改，返回新提交的ID
+    pub fn commit<P: AsRef<Path>>(repo_path: P, options: &CommitOptions) -> Result<String> {
+        let mut repo = Self::open_repository(repo_path)?;
+        let message = Self::validate_commit_message(
+            &options.message,
+            options.strip_comments,
+            options.max_summary_length,
+        )?;
+
+        // 合并过程中的提交以 MERGE_HEAD 作为额外的父提交
+        let merging = repo.state() == RepositoryState::Merge;
+        let mut merge_heads = Vec::new();
+        if merging {
+            repo.mergehead_foreach(|oid| {
+                merge_heads.push(*oid);
+                true
+            })?;
+        }
+
+        let mut index = repo.index()?;
+        if index.has_conflicts() {
+            return Err(anyhow!("Cannot commit with unresolved conflicts"));
+        }
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let committer = Self::resolve_signature(&repo, None, None)?;
+        let head = Self::head_commit(&repo)?;
+
+        let oid = if options.amend {
+            let head = head.ok_or_else(|| anyhow!("No commit to amend"))?;
+            let base_tree = head.parent(0).ok().map(|parent| parent.tree()).transpose()?;
+            Self::ensure_changes(&tree, base_tree.as_ref(), options.allow_empty)?;
+
+            // 未指定作者时保留原提交的作者
+            let author = if options.author_name.is_some() || options.author_email.is_some() {
+                Some(Self::resolve_signature(
+                    &repo,
+                    options.author_name.as_deref(),
+                    options.author_email.as_deref(),
+                )?)
+            } else {
+                None
+            };
+            head.amend(Some("HEAD"), author.as_ref(), Some(&committer), None, Some(&message), Some(&tree))?
+        } else {
+            let mut parents: Vec<Commit> = head.into_iter().collect();
+            if merging {
+                for oid in merge_heads {
+                    parents.push(repo.find_commit(oid)?);
+                }
+            } else {
+                let base_tree = parents.first().map(|parent| parent.tree()).transpose()?;
+                Self::ensure_changes(&tree, base_tree.as_ref(), options.allow_empty)?;
+            }
+
+            let author = Self::resolve_signature(
+                &repo,
+                options.author_name.as_deref(),
+                options.author_email.as_deref(),
+            )?;
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let oid = repo.commit(Some("HEAD"), &author, &committer, &message, &tree, &parent_refs)?;
+            if merging {
+                repo.cleanup_state()?;
+            }
+            oid
+        };
+
+        Ok(oid.to_string())
+    }
+
+    /// 当前分支上游所在的远程，没有上游时为 origin
+    fn default_remote(repo: &Repository) -> String {
+        repo.head()
+            .ok()
+            .and_then(|head| head.name().map(str::to_string))
+            .and_then(|name| repo.branch_upstream_remote(&name).ok())
+            .and_then(|remote| remote.as_str().map(str::to_string))
+            .unwrap_or_else(|| DEFAULT_REMOTE.to_string())
+    }
+
+    fn fetch_remote(
+        repo: &Repository,
+        remote_name: &str,
+        prune: bool,
+        progress: Option<RemoteProgressCallback>,
+    ) -> Result<FetchResult> {
+        let mut remote = repo
+            .find_remote(remote_name)
+            .map_err(|e| anyhow!("Remote not found: {}: {}", remote_name, e))?;
+
+        let mut updated_refs = Vec::new();
+        let mut pruned_refs = Vec::new();
+        {
+            let mut callbacks = git_remote::remote_callbacks(repo.config()?, progress);
+            callbacks.update_tips(|refname, _, new| {
+                // 被删除的引用新值为零
+                if new.is_zero() {
+                    pruned_refs.push(refname.to_string());
+                } else {
+                    updated_refs.push(refname.to_string());
+                }
+                true
+            });
+
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(callbacks);
+            fetch_options.prune(if prune { FetchPrune::On } else { FetchPrune::Unspecified });
+            remote
+                .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+                .map_err(|e| anyhow!("Failed to fetch from {}: {}", remote_name, e))?;
+        }
+
+        let stats = remote.stats();
+        Ok(FetchResult {
+            remote: remote_name.to_string(),
+            received_objects: stats.received_objects(),
+            received_bytes: stats.received_bytes(),
+            updated_refs,
+            pruned_refs,
+        })
+    }
+
+    /// 获取所有远程仓库名称
+    pub fn list_remotes<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        let remotes = repo.remotes()?;
+        Ok(remotes.iter().flatten().map(str::to_string).collect())
+    }
+
+    /// 删除远程已不存在的远程跟踪分支，不下载新对象
+    pub fn prune_remote<P: AsRef<Path>>(repo_path: P, remote_name: &str) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        let mut remote = repo
+            .find_remote(remote_name)
+            .map_err(|e| anyhow!("Remote not found: {}: {}", remote_name, e))?;
+
+        let callbacks = git_remote::remote_callbacks(repo.config()?, None);
+        remote
+            .connect_auth(Direction::Fetch, Some(callbacks), None)
+            .map_err(|e| anyhow!("Failed to connect to {}: {}", remote_name, e))?;
+
+        let mut pruned_refs = Vec::new();
+        {
+            let mut callbacks = git_remote::remote_callbacks(repo.config()?, None);
+            callbacks.update_tips(|refname, _, _| {
+                pruned_refs.push(refname.to_string());
+                true
+            });
+            remote
+                .prune(Some(callbacks))
+                .map_err(|e| anyhow!("Failed to prune {}: {}", remote_name, e))?;
+        }
+        remote.disconnect()?;
+
+        Ok(pruned_refs)
+    }
+
+    /// 从远程仓库获取更新，`prune` 为 true 时删除远程已不存在的远程跟踪分支
+    pub fn fetch<P: AsRef<Path>>(
+        repo_path: P,
+        remote_name: Option<&str>,
+        prune: bool,
+        progress: Option<RemoteProgressCallback>,
+    ) -> Result<FetchResult> {
+        let repo = Self::open_repository(repo_path)?;
+        let remote_name = remote_name
+            .map(str::to_string)
+            .unwrap_or_else(|| Self::default_remote(&repo));
+        Self::fetch_remote(&repo, &remote_name, prune, progress)
+    }
+
+    /// 获取当前分支上游的更新并按指定方式整合到当前分支
+    pub fn pull<P: AsRef<Path>>(
+        repo_path: P,
+        strategy: PullStrategy,
+        progress: Option<RemoteProgressCallback>,
+    ) -> Result<PullResult> {
+        let repo = Self::open_repository(repo_path)?;
+        if repo.state() != RepositoryState::Clean {
+            return Err(anyhow!("Another operation is in progress; continue or abort it first"));
+        }
+        let head = repo.head().map_err(|e| anyhow!("Failed to resolve HEAD: {}", e))?;
+        if !head.is_branch() {
+            return Err(anyhow!("Cannot pull with a detached HEAD"));
+        }
+        let branch_ref = head.name().ok_or_else(|| anyhow!("Invalid branch name"))?.to_string();
+        let head_id = head.target().ok_or_else(|| anyhow!("No target for HEAD"))?;
+
+        let upstream_ref = repo
+            .branch_upstream_name(&branch_ref)
+            .map_err(|_| anyhow!("Current branch has no upstream branch"))?
+            .as_str()
+            .ok_or_else(|| anyhow!("Invalid upstream branch name"))?
+            .to_string();
+        let remote_name = repo
+            .branch_upstream_remote(&branch_ref)?
+            .as_str()
+            .ok_or_else(|| anyhow!("Invalid remote name"))?
+            .to_string();
+        Self::fetch_remote(&repo, &remote_name, false, progress)?;
+
+        let upstream = repo.reference_to_annotated_commit(&repo.find_reference(&upstream_ref)?)?;
+        let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+
+        if analysis.is_up_to_date() {
+            return Ok(PullResult {
+                status: PullStatus::UpToDate,
+                head: Some(head_id.to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        if analysis.is_fast_forward() {
+            let target = repo.find_commit(upstream.id())?;
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.safe();
+            repo.checkout_tree(target.as_object(), Some(&mut checkout))?;
+            repo.find_reference(&branch_ref)?
+                .set_target(target.id(), "pull: fast-forward")?;
+            return Ok(PullResult {
+                status: PullStatus::FastForwarded,
+                head: Some(target.id().to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        match strategy {
+            PullStrategy::FastForwardOnly => Err(anyhow!(
+                "Cannot fast-forward: local and upstream branches have diverged"
+            )),
+            PullStrategy::Merge => {
+                Self::ensure_clean_worktree(&repo)?;
+                let upstream_name = upstream_ref.trim_start_matches("refs/remotes/");
+                Self::merge_annotated(&repo, &upstream, &format!("Merge remote-tracking branch '{}'", upstream_name))
+            }
+            PullStrategy::Rebase => {
+                Self::ensure_clean_worktree(&repo)?;
+                Self::rebase_onto(&repo, &upstream)
+            }
+        }
+    }
+
+    /// 合并或变基前检查工作目录，未跟踪的文件不影响合并，其他未提交的更改需要先处理
+    fn ensure_clean_worktree(repo: &Repository) -> Result<()> {
+        let mut status_options = StatusOptions::new();
+        status_options.include_untracked(false).include_ignored(false);
+        if !repo.statuses(Some(&mut status_options))?.is_empty() {
+            return Err(anyhow!("Working tree has uncommitted changes"));
+        }
+        Ok(())
+    }
+
+    /// 索引中有冲突的文件
+    fn conflicted_paths(index: &Index) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                paths.push(String::from_utf8_lossy(&entry.path).to_string());
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// 将提交合并到当前分支，没有冲突时创建合并提交
+    fn merge_annotated(repo: &Repository, other: &AnnotatedCommit, message: &str) -> Result<PullResult> {
+        repo.merge(&[other], None, None)
+            .map_err(|e| anyhow!("Failed to merge: {}", e))?;
+
+        let mut index = repo.index()?;
+        let head = repo.head()?.peel_to_commit()?;
+        if index.has_conflicts() {
+            return Ok(PullResult {
+                status: PullStatus::Conflicts,
+                head: Some(head.id().to_string()),
+                conflicts: Self::conflicted_paths(&index)?,
+            });
+        }
+
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = Self::resolve_signature(repo, None, None)?;
+        let other_commit = repo.find_commit(other.id())?;
+        let oid = repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("{}\n", message),
+            &tree,
+            &[&head, &other_commit],
+        )?;
+        repo.cleanup_state()?;
+
+        Ok(PullResult {
+            status: PullStatus::Merged,
+            head: Some(oid.to_string()),
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// 将当前分支变基到指定提交上，遇到冲突时停止并保留变基状态
+    fn rebase_onto(repo: &Repository, onto: &AnnotatedCommit) -> Result<PullResult> {
+        let signature = Self::resolve_signature(repo, None, None)?;
+        let mut rebase = repo
+            .rebase(None, Some(onto), None, None)
+            .map_err(|e| anyhow!("Failed to start rebase: {}", e))?;
+        Self::run_rebase(repo, &mut rebase, &signature, false)
+    }
+
+    fn commit_rebase_step(rebase: &mut git2::Rebase, signature: &Signature) -> Result<()> {
+        match rebase.commit(None, signature, None) {
+            Ok(_) => Ok(()),
+            // 更改已包含在上游中，跳过该提交
+            Err(e) if e.code() == git2::ErrorCode::Applied => Ok(()),
+            Err(e) => Err(anyhow!("Failed to commit rebased change: {}", e)),
+        }
+    }
+
+    /// 依次应用变基中剩余的提交，`resume` 为 true 时先提交解决冲突后的当前提交
+    fn run_rebase(
+        repo: &Repository,
+        rebase: &mut git2::Rebase,
+        signature: &Signature,
+        resume: bool,
+    ) -> Result<PullResult> {
+        if resume && rebase.operation_current().is_some() {
+            Self::commit_rebase_step(rebase, signature)?;
+        }
+
+        while let Some(operation) = rebase.next() {
+            operation?;
+            let index = repo.index()?;
+            if index.has_conflicts() {
+                return Ok(PullResult {
+                    status: PullStatus::Conflicts,
+                    head: repo.head()?.target().map(|oid| oid.to_string()),
+                    conflicts: Self::conflicted_paths(&index)?,
+                });
+            }
+            Self::commit_rebase_step(rebase, signature)?;
+        }
+        rebase.finish(Some(signature))?;
+
+        Ok(PullResult {
+            status: PullStatus::Rebased,
+            head: repo.head()?.target().map(|oid| oid.to_string()),
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// 将另一个分支或提交合并或变基到当前分支，产生冲突时保留进行中的状态，
+    /// 之后通过 continue_operation 或 abort_operation 完成或放弃
+    pub fn update_from_branch<P: AsRef<Path>>(
+        repo_path: P,
+        source: &str,
+        strategy: PullStrategy,
+    ) -> Result<PullResult> {
+        let repo = Self::open_repository(repo_path)?;
+        if repo.state() != RepositoryState::Clean {
+            return Err(anyhow!("Another operation is in progress; continue or abort it first"));
+        }
+        let head = repo.head().map_err(|e| anyhow!("Failed to resolve HEAD: {}", e))?;
+        if !head.is_branch() {
+            return Err(anyhow!("Cannot update a detached HEAD"));
+        }
+        let branch_ref = head.name().ok_or_else(|| anyhow!("Invalid branch name"))?.to_string();
+        let head_id = head.target().ok_or_else(|| anyhow!("No target for HEAD"))?;
+
+        Self::ensure_clean_worktree(&repo)?;
+
+        let (object, reference) = repo
+            .revparse_ext(source)
+            .map_err(|_| anyhow!("Source not found: {}", source))?;
+        let source_commit = match &reference {
+            Some(reference) => repo.reference_to_annotated_commit(reference)?,
+            None => repo.find_annotated_commit(object.peel_to_commit()?.id())?,
+        };
+
+        let (analysis, _) = repo.merge_analysis(&[&source_commit])?;
+        if analysis.is_up_to_date() {
+            return Ok(PullResult {
+                status: PullStatus::UpToDate,
+                head: Some(head_id.to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        if analysis.is_fast_forward() {
+            let target = repo.find_commit(source_commit.id())?;
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.safe();
+            repo.checkout_tree(target.as_object(), Some(&mut checkout))?;
+            repo.find_reference(&branch_ref)?
+                .set_target(target.id(), &format!("update: fast-forward to {}", source))?;
+            return Ok(PullResult {
+                status: PullStatus::FastForwarded,
+                head: Some(target.id().to_string()),
+                conflicts: Vec::new(),
+            });
+        }
+
+        match strategy {
+            PullStrategy::FastForwardOnly => Err(anyhow!(
+                "Cannot fast-forward: branch has diverged from {}",
+                source
+            )),
+            PullStrategy::Merge => Self::merge_annotated(&repo, &source_commit, &format!("Merge branch '{}'", source)),
+            PullStrategy::Rebase => Self::rebase_onto(&repo, &source_commit),
+        }
+    }
+
+    /// 获取进行中的合并或变基状态
+    pub fn get_operation_state<P: AsRef<Path>>(repo_path: P) -> Result<GitOperationState> {
+        let repo = Self::open_repository(repo_path)?;
+        let operation = match repo.state() {
+            RepositoryState::Clean => GitOperation::None,
+            RepositoryState::Merge => GitOperation::Merge,
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => {
+                GitOperation::Rebase
+            }
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => GitOperation::CherryPick,
+            RepositoryState::Revert | RepositoryState::RevertSequence => GitOperation::Revert,
+            _ => GitOperation::Other,
+        };
+
+        let rebase_progress = if operation == GitOperation::Rebase {
+            repo.open_rebase(None).ok().map(|mut rebase| {
+                let done = rebase.operation_current().map_or(0, |current| current + 1);
+                (done, rebase.len())
+            })
+        } else {
+            None
+        };
+
+        Ok(GitOperationState {
+            operation,
+            conflicts: Self::conflicted_paths(&repo.index()?)?,
+            rebase_progress,
+        })
+    }
+
+    /// 放弃进行中的合并或变基，恢复到操作开始前的状态
+    pub fn abort_operation<P: AsRef<Path>>(repo_path: P) -> Result<()> {
+        let repo = Self::open_repository(repo_path)?;
+        match repo.state() {
+            RepositoryState::Clean => Err(anyhow!("No operation in progress")),
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => {
+                repo.open_rebase(None)?
+                    .abort()
+                    .map_err(|e| anyhow!("Failed to abort rebase: {}", e))
+            }
+            RepositoryState::Merge
+            | RepositoryState::CherryPick
+            | RepositoryState::CherryPickSequence
+            | RepositoryState::Revert
+            | RepositoryState::RevertSequence => Self::reset_merge(&repo),
+            _ => Err(anyhow!("Unsupported operation in progress")),
+        }
+    }
+
+    /// 与 `git reset --merge` 相同，只恢复操作改动过的路径，保留其他未提交的更改
+    fn reset_merge(repo: &Repository) -> Result<()> {
+        let head = repo.head()?.peel_to_commit()?;
+        let head_tree = head.tree()?;
+        let index = repo.index()?;
+
+        let conflicts = Self::conflicted_paths(&index)?;
+        let mut paths = conflicts.clone();
+        let staged = repo.diff_tree_to_index(Some(&head_tree), Some(&index), None)?;
+        for delta in staged.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                if let Some(path) = file.path().and_then(|p| p.to_str()) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+
+        if !paths.is_empty() {
+            // 已暂存的合并结果在工作目录中又被修改过时无法安全恢复
+            let merged: Vec<&String> = paths.iter().filter(|path| !conflicts.contains(path)).collect();
+            if !merged.is_empty() {
+                let mut diff_options = DiffOptions::new();
+                diff_options.disable_pathspec_match(true);
+                for path in &merged {
+                    diff_options.pathspec(path);
+                }
+                let unstaged = repo.diff_index_to_workdir(Some(&index), Some(&mut diff_options))?;
+                let changed: Vec<String> = unstaged
+                    .deltas()
+                    .filter_map(|delta| delta.new_file().path().and_then(|p| p.to_str()).map(str::to_string))
+                    .collect();
+                if !changed.is_empty() {
+                    return Err(anyhow!(
+                        "Cannot abort: local changes to {} would be lost",
+                        changed.join(", ")
+                    ));
+                }
+            }
+
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force();
+            for path in &paths {
+                checkout.path(path);
+            }
+            repo.checkout_tree(head_tree.as_object(), Some(&mut checkout))?;
+            repo.reset_default(Some(head.as_object()), paths.iter())?;
+        }
+
+        repo.cleanup_state()?;
+        Ok(())
+    }
+
+    /// 冲突全部解决并暂存后继续进行中的合并或变基
+    pub fn continue_operation<P: AsRef<Path>>(repo_path: P) -> Result<PullResult> {
+        let repo_path = repo_path.as_ref();
+        let repo = Self::open_repository(repo_path)?;
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            return Err(anyhow!(
+                "Unresolved conflicts remain: {}",
+                Self::conflicted_paths(&index)?.join(", ")
+            ));
+        }
+
+        match repo.state() {
+            RepositoryState::Merge => {
+                // MERGE_MSG 中的冲突列表等以注释行给出
+                let message = repo.message().unwrap_or_else(|_| "Merge commit".to_string());
+                let oid = Self::commit(repo_path, &CommitOptions {
+                    message,
+                    strip_comments: true,
+                    ..Default::default()
+                })?;
+                Ok(PullResult {
+                    status: PullStatus::Merged,
+                    head: Some(oid),
+                    conflicts: Vec::new(),
+                })
+            }
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => {
+                let signature = Self::resolve_signature(&repo, None, None)?;
+                let mut rebase = repo.open_rebase(None)?;
+                Self::run_rebase(&repo, &mut rebase, &signature, true)
+            }
+            RepositoryState::Clean => Err(anyhow!("No operation in progress")),
+            _ => Err(anyhow!("Unsupported operation in progress")),
+        }
+    }
+
+    fn conflict_side(repo: &Repository, entry: Option<git2::IndexEntry>) -> Result<Option<ConflictSide>> {
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let blob = repo.find_blob(entry.id)?;
+        let is_binary = blob.is_binary();
+        Ok(Some(ConflictSide {
+            path: String::from_utf8_lossy(&entry.path).to_string(),
+            id: entry.id.to_string(),
+            mode: entry.mode,
+            is_binary,
+            content: if is_binary {
+                None
+            } else {
+                Some(String::from_utf8_lossy(blob.content()).to_string())
+            },
+        }))
+    }
+
+    /// 获取所有冲突文件及其各方版本
+    pub fn get_conflicts<P: AsRef<Path>>(repo_path: P) -> Result<Vec<GitConflict>> {
+        let repo = Self::open_repository(repo_path)?;
+        let index = repo.index()?;
+        let mut conflicts = Vec::new();
+
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            let path = conflict
+                .our
+                .as_ref()
+                .or(conflict.their.as_ref())
+                .or(conflict.ancestor.as_ref())
+                .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+                .ok_or_else(|| anyhow!("Invalid conflict entry"))?;
+
+            conflicts.push(GitConflict {
+                path,
+                ancestor: Self::conflict_side(&repo, conflict.ancestor)?,
+                ours: Self::conflict_side(&repo, conflict.our)?,
+                theirs: Self::conflict_side(&repo, conflict.their)?,
+            });
+        }
+
+        conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(conflicts)
+    }
+
+    /// 获取尚未解决冲突的文件
+    pub fn get_conflicted_paths<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        Self::conflicted_paths(&repo.index()?)
+    }
+
+    fn write_workdir_file(workdir: &Path, path: &str, content: &[u8], mode: Option<u32>) -> Result<()> {
+        let file_path = workdir.join(path);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&file_path, content)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = if mode == 0o100755 { 0o755 } else { 0o644 };
+            fs::set_permissions(&file_path, fs::Permissions::from_mode(permissions))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        Ok(())
+    }
+
+    /// 按指定方式解决文件冲突并标记为已解决，返回剩余的冲突文件
+    pub fn resolve_conflict<P: AsRef<Path>>(
+        repo_path: P,
+        path: &str,
+        resolution: &ConflictResolution,
+    ) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("Repository has no working directory"))?
+            .to_path_buf();
+        let mut index = repo.index()?;
+        let conflict = index
+            .conflicts()?
+            .filter_map(|conflict| conflict.ok())
+            .find(|conflict| {
+                [&conflict.our, &conflict.their, &conflict.ancestor]
+                    .iter()
+                    .any(|entry| entry.as_ref().is_some_and(|entry| entry.path == path.as_bytes()))
+            })
+            .ok_or_else(|| anyhow!("File is not in conflict: {}", path))?;
+
+        let chosen = match resolution {
+            ConflictResolution::Ours => Some(conflict.our),
+            ConflictResolution::Theirs => Some(conflict.their),
+            ConflictResolution::Merged(content) => {
+                Self::write_workdir_file(&workdir, path, content.as_bytes(), None)?;
+                None
+            }
+            ConflictResolution::Delete => Some(None),
+        };
+
+        match chosen {
+            // 选择的一方删除了文件
+            Some(None) => {
+                let file_path = workdir.join(path);
+                if file_path.exists() {
+                    fs::remove_file(&file_path)?;
+                }
+                index.remove_path(Path::new(path))?;
+            }
+            Some(Some(entry)) => {
+                let blob = repo.find_blob(entry.id)?;
+                Self::write_workdir_file(&workdir, path, blob.content(), Some(entry.mode))?;
+                index.add_path(Path::new(path))?;
+            }
+            None => index.add_path(Path::new(path))?,
+        }
+        index.write()?;
+
+        Self::conflicted_paths(&index)
+    }
+
+    /// 将工作目录中已手动解决的文件标记为已解决，返回剩余的冲突文件
+    pub fn mark_resolved<P: AsRef<Path>>(repo_path: P, paths: &[String]) -> Result<Vec<String>> {
+        let repo = Self::open_repository(repo_path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("Repository has no working directory"))?
+            .to_path_buf();
+        let mut index = repo.index()?;
+
+        for path in paths {
+            if workdir.join(path).exists() {
+                index.add_path(Path::new(path))?;
+            } else {
+                index.remove_path(Path::new(path))?;
+            }
+        }
+        index.write()?;
+
+        Self::conflicted_paths(&index)
+    }
+
+    /// 从储藏说明中解析分支，格式为 "On <branch>: ..." 或 "WIP on <branch>: ..."
+    fn stash_branch(message: &str) -> Option<String> {
+        let rest = message
+            .strip_prefix("WIP on ")
+            .or_else(|| message.strip_prefix("On "))?;
+        rest.split_once(':').map(|(branch, _)| branch.to_string())
+    }
+
+    fn stash_info(repo: &Repository, index: usize, message: &str, oid: Oid) -> Result<GitStash> {
+        let commit = repo.find_commit(oid)?;
+        Ok(GitStash {
+            index,
+            id: oid.to_string(),
+            message: message.to_string(),
+            branch: Self::stash_branch(message),
+            created_at: Self::to_datetime(commit.time()),
+            has_untracked: commit.parent_count() > 2,
+        })
+    }
+
+    /// 获取储藏列表，主仓库和所有worktree共用同一个列表
+    pub fn list_stashes<P: AsRef<Path>>(repo_path: P) -> Result<Vec<GitStash>> {
+        let mut repo = Self::open_repository(repo_path)?;
+        let mut entries = Vec::new();
+        repo.stash_foreach(|index, message, oid| {
+            entries.push((index, message.to_string(), *oid));
+            true
+        })?;
+
+        entries
+            .into_iter()
+            .map(|(index, message, oid)| Self::stash_info(&repo, index, &message, oid))
+            .collect()
+    }
+
+    /// 储藏工作目录和索引中的更改
+    pub fn save_stash<P: AsRef<Path>>(repo_path: P, options: &StashOptions) -> Result<GitStash> {
+        let mut repo = Self::open_repository(repo_path)?;
+        let signature = Self::resolve_signature(&repo, None, None)?;
+
+        let mut flags = git2::StashFlags::DEFAULT;
+        if options.include_untracked {
+            flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+        }
+        if options.keep_index {
+            flags |= git2::StashFlags::KEEP_INDEX;
+        }
+
+        let message = options.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        let oid = repo.stash_save2(&signature, message, Some(flags)).map_err(|e| {
+            if e.code() == git2::ErrorCode::NotFound {
+                anyhow!("No local changes to stash")
+            } else {
+                anyhow!("Failed to stash changes: {}", e)
+            }
+        })?;
+
+        let message = repo.find_commit(oid)?.message().unwrap_or_default().trim_end().to_string();
+        Self::stash_info(&repo, 0, &message, oid)
+    }
+
+    fn stash_apply_options<'a>(reinstate_index: bool) -> git2::StashApplyOptions<'a> {
+        let mut options = git2::StashApplyOptions::new();
+        if reinstate_index {
+            options.reinstantiate_index();
+        }
+        options
+    }
+
+    /// 查找储藏当前的位置
+    ///
+    /// 储藏列表由主仓库和所有worktree共享，其他工作区储藏或删除后位置会变化，因此按储藏ID查找。
+    fn stash_position(repo: &mut Repository, stash_id: &str) -> Result<usize> {
+        let oid = Oid::from_str(stash_id).map_err(|_| anyhow!("Invalid stash id: {}", stash_id))?;
+        let mut position = None;
+        repo.stash_foreach(|index, _, id| {
+            if *id == oid {
+                position = Some(index);
+            }
+            true
+        })?;
+        position.ok_or_else(|| anyhow!("Stash not found: {}", stash_id))
+    }
+
+    /// 应用储藏但保留储藏记录，返回产生冲突的文件
+    pub fn apply_stash<P: AsRef<Path>>(repo_path: P, stash_id: &str, reinstate_index: bool) -> Result<Vec<String>> {
+        let mut repo = Self::open_repository(repo_path)?;
+        let index = Self::stash_position(&mut repo, stash_id)?;
+        repo.stash_apply(index, Some(&mut Self::stash_apply_options(reinstate_index)))
+            .map_err(|e| anyhow!("Failed to apply stash@{{{}}}: {}", index, e))?;
+        Self::conflicted_paths(&repo.index()?)
+    }
+
+    /// 应用储藏并在成功后删除，有冲突时保留储藏记录
+    pub fn pop_stash<P: AsRef<Path>>(repo_path: P, stash_id: &str, reinstate_index: bool) -> Result<Vec<String>> {
+        let conflicts = Self::apply_stash(repo_path.as_ref(), stash_id, reinstate_index)?;
+        if conflicts.is_empty() {
+            // 应用期间其他工作区可能改变了储藏列表，重新按ID查找后再删除
+            Self::drop_stash(repo_path, stash_id)?;
+        }
+        Ok(conflicts)
+    }
+
+    /// 删除储藏
+    pub fn drop_stash<P: AsRef<Path>>(repo_path: P, stash_id: &str) -> Result<()> {
+        let mut repo = Self::open_repository(repo_path)?;
+        let index = Self::stash_position(&mut repo, stash_id)?;
+        repo.stash_drop(index)
+            .map_err(|e| anyhow!("Failed to drop stash@{{{}}}: {}", index, e))
+    }
+
+    /// 获取储藏相对于储藏时所在提交的差异，包括储藏的未跟踪文件
+    pub fn get_stash_diff<P: AsRef<Path>>(repo_path: P, stash_id: &str, options: &GitDiffOptions) -> Result<GitDiff> {
+        let mut repo = Self::open_repository(repo_path)?;
+        Self::stash_position(&mut repo, stash_id)?;
+        let commit = repo.find_commit(Oid::from_str(stash_id)?)?;
+        let base_tree = commit.parent(0)?.tree()?;
+
+        let mut opts = options.to_git2();
+        let mut diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&commit.tree()?), Some(&mut opts))?;
+        if commit.parent_count() > 2 {
+            let untracked_tree = commit.parent(2)?.tree()?;
+            let untracked = repo.diff_tree_to_tree(None, Some(&untracked_tree), Some(&mut opts))?;
+            diff.merge(&untracked)?;
+        }
+
+        git_diff::find_renames(&mut diff, options)?;
+        git_diff::build_diff(&diff, options, None)
+    }
+
+    /// 推送单个引用规格，远程拒绝更新时返回错误
+    ///
+    /// `lease` 为远程引用名及其期望指向的提交（None 表示引用不存在）。在推送连接协商时比较远程的实际值，
+    /// 不一致则放弃推送；服务器也会以协商时的旧值校验更新，因此期间的其他推送不会被覆盖。
+    fn push_refspec(
+        repo: &Repository,
+        remote: &mut Remote,
+        refspec: &str,
+        lease: Option<(&str, Option<Oid>)>,
+        progress: Option<RemoteProgressCallback>,
+    ) -> Result<()> {
+        let remote_name = remote.name().unwrap_or(DEFAULT_REMOTE).to_string();
+        let mut rejection = None;
+        // 回调返回的错误信息不会传回调用方，单独记录
+        let mut lease_broken = None;
+        let pushed = {
+            let mut callbacks = git_remote::remote_callbacks(repo.config()?, progress);
+            if let Some((lease_ref, expected)) = lease {
+                let lease_broken = &mut lease_broken;
+                callbacks.push_negotiation(move |updates| {
+                    for update in updates.iter().filter(|u| u.dst_refname() == Some(lease_ref)) {
+                        let actual = Some(update.src()).filter(|oid| !oid.is_zero());
+                        if actual != expected {
+                            *lease_broken = Some(format!(
+                                "Remote branch {} has changed since the last fetch",
+                                lease_ref.trim_start_matches("refs/heads/")
+                            ));
+                            return Err(git2::Error::from_str("Push lease is out of date"));
+                        }
+                    }
+                    Ok(())
+                });
+            }
+            callbacks.push_update_reference(|refname, status| {
+                if let Some(message) = status {
+                    rejection = Some(format!("{}: {}", refname, message));
+                }
+                Ok(())
+            });
+
+            let mut push_options = git2::PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+            remote.push(&[refspec], Some(&mut push_options))
+        };
+        if let Some(message) = lease_broken {
+            return Err(anyhow!(message));
+        }
+        pushed.map_err(|e| anyhow!("Failed to push to {}: {}", remote_name, e))?;
+        if let Some(rejection) = rejection {
+            return Err(anyhow!("Push rejected: {}", rejection));
+        }
+        Ok(())
+    }
+
+    /// 推送分支到远程仓库
+    pub fn push<P: AsRef<Path>>(
+        repo_path: P,
+        options: &PushOptions,
+        progress: Option<RemoteProgressCallback>,
+    ) -> Result<PushResult> {
+        let repo = Self::open_repository(repo_path)?;
+        let branch_name = match options.branch.as_deref() {
+            Some(branch) => branch.to_string(),
+            None => {
+                let head = repo.head().map_err(|e| anyhow!("Failed to resolve HEAD: {}", e))?;
+                if !head.is_branch() {
+                    return Err(anyhow!("Cannot push a detached HEAD without a branch name"));
+                }
+                head.shorthand().ok_or_else(|| anyhow!("Invalid branch name"))?.to_string()
+            }
+        };
+        let local_ref = format!("refs/heads/{}", branch_name);
+        let local_oid = repo
+            .refname_to_id(&local_ref)
+            .map_err(|_| anyhow!("Branch not found: {}", branch_name))?;
+
+        let remote_name = match options.remote.as_deref() {
+            Some(remote) => remote.to_string(),
+            None => repo
+                .branch_upstream_remote(&local_ref)
+                .ok()
+                .and_then(|remote| remote.as_str().map(str::to_string))
+                .unwrap_or_else(|| DEFAULT_REMOTE.to_string()),
+        };
+        let remote_branch = options.remote_branch.clone().unwrap_or_else(|| branch_name.clone());
+        let remote_ref = format!("refs/heads/{}", remote_branch);
+        let tracking_ref = format!("refs/remotes/{}/{}", remote_name, remote_branch);
+        let mut remote = repo
+            .find_remote(&remote_name)
+            .map_err(|e| anyhow!("Remote not found: {}: {}", remote_name, e))?;
+
+        // 远程分支必须仍是上次获取时的状态，否则拒绝强制推送
+        let lease = if options.force_with_lease {
+            Some((remote_ref.as_str(), repo.refname_to_id(&tracking_ref).ok()))
+        } else {
+            None
+        };
+
+        let force = if options.force_with_lease { "+" } else { "" };
+        let refspec = format!("{}{}:{}", force, local_ref, remote_ref);
+        Self::push_refspec(&repo, &mut remote, &refspec, lease, progress)?;
+
+        // 更新远程跟踪分支
+        repo.reference(&tracking_ref, local_oid, true, "push")?;
+        if options.set_upstream {
+            repo.find_branch(&branch_name, BranchType::Local)?
+                .set_upstream(Some(&format!("{}/{}", remote_name, remote_branch)))?;
+        }
+
+        Ok(PushResult {
+            remote: remote_name,
+            branch: branch_name,
+            remote_branch,
+            upstream_set: options.set_upstream,
+        })
+    }
+
+    /// 设置Git全局忽略配置
+    pub fn set_global_gitignore<P: AsRef<Path>>(gitignore_path: P) -> Result<()> {
+        let mut config = git2::Config::open_default()?;
+        let path_str = gitignore_path.as_ref().to_string_lossy();
+        config.set_str("core.excludesfile", &path_str)?;
+        Ok(())
+    }
+
+    /// 获取Git全局忽略配置
+    pub fn get_global_gitignore() -> Result<Option<String>> {
+        let config = git2::Config::open_default()?;
+        match config.get_string("core.excludesfile") {
+            Ok(path) => Ok(Some(path)),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(anyhow!("Failed to get global gitignore: {}", e)),
+        }
+    }
+
+    /// 验证路径是否为Git仓库
+    pub fn is_git_repository<P: AsRef<Path>>(path: P) -> bool {
+        Repository::open(path).is_ok()
+    }
+
+    /// 初始化新的Git仓库
+    pub fn init_repository<P: AsRef<Path>>(path: P, bare: bool) -> Result<Repository> {
+        if bare {
+            Repository::init_bare(path)
+        } else {
+            Repository::init(path)
+        }
+        .map_err(|e| anyhow!("Failed to initialize repository: {}", e))
+    }
+
+    /// 克隆远程仓库
+    pub fn clone_repository<P: AsRef<Path>>(
+        url: &str,
+        path: P,
+        progress_callback: Option<Box<dyn FnMut(git2::Progress) -> bool>>,
+    ) -> Result<Repository> {
+        let mut builder = git2::build::RepoBuilder::new();
+        
+        if let Some(mut callback) = progress_callback {
+            let mut remote_callbacks = git2::RemoteCallbacks::new();
+            remote_callbacks.transfer_progress(move |progress| {
+                callback(progress)
+            });
+            
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(remote_callbacks);
+            builder.fetch_options(fetch_options);
+        }
+
+        builder.clone(url, path.as_ref())
+            .map_err(|e| anyhow!("Failed to clone repository: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_support::{commit_file, init_repo_with_commit};
+    use tempfile::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_init_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+
+        let repo = GitService::init_repository(repo_path, false).unwrap();
+        assert!(repo_path.join(".git").exists());
+    }
+
+    #[test]
+    fn test_is_git_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+
+        // 未初始化时应该返回false
+        assert!(!GitService::is_git_repository(repo_path));
+
+        // 初始化后应该返回true
+        GitService::init_repository(repo_path, false).unwrap();
+        assert!(GitService::is_git_repository(repo_path));
+    }
+
+    #[test]
+    fn test_get_repository_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+
+        // 初始化仓库
+        GitService::init_repository(repo_path, false).unwrap();
+
+        // 创建一个文件
+        let file_path = repo_path.join("test.txt");
+        fs::write(&file_path, "test content").unwrap();
+
+        // 获取状态
+        let status = GitService::get_repository_status(repo_path).unwrap();
+        assert!(status.is_dirty);
+        assert!(!status.files.is_empty());
+        assert_eq!(status.files[0].path, "test.txt");
+    }
+
+    fn file_status(repo_path: &Path, path: &str) -> Option<GitFileStatus> {
+        GitService::get_repository_status(repo_path)
+            .unwrap()
+            .files
+            .into_iter()
+            .find(|f| f.path == path)
+    }
+
+    #[test]
+    fn test_stage_and_unstage_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo_with_commit(repo_path);
+
+        fs::write(repo_path.join("new.txt"), "new").unwrap();
+        fs::remove_file(repo_path.join("file.txt")).unwrap();
+        GitService::stage_paths(repo_path, &["new.txt".to_string(), "file.txt".to_string()]).unwrap();
+
+        let new_file = file_status(repo_path, "new.txt").unwrap();
+        assert!(new_file.is_staged && new_file.is_new);
+        let deleted = file_status(repo_path, "file.txt").unwrap();
+        assert!(deleted.is_staged && deleted.is_deleted);
+
+        GitService::unstage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        let deleted = file_status(repo_path, "file.txt").unwrap();
+        assert!(!deleted.is_staged);
+        assert_eq!(deleted.status, "D");
+
+        assert!(GitService::stage_paths(repo_path, &[]).is_err());
+    }
+
+    #[test]
+    fn test_stage_and_unstage_hunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let content: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        init_repo_with_commit(repo_path);
+        fs::write(repo_path.join("file.txt"), &content).unwrap();
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        GitService::commit(repo_path, &CommitOptions {
+            message: "Twenty lines".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // 文件首尾各修改一处，产生两个差异块
+        let modified = content.replace("line 1\n", "first\n").replace("line 20\n", "last\n");
+        fs::write(repo_path.join("file.txt"), &modified).unwrap();
+        let hunks = GitService::get_file_hunks(repo_path, "file.txt", false).unwrap();
+        assert_eq!(hunks.len(), 2);
+
+        GitService::stage_hunk(repo_path, "file.txt", &hunks[1].header).unwrap();
+        let staged = GitService::get_file_hunks(repo_path, "file.txt", true).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].new_start, hunks[1].new_start);
+        assert_eq!(GitService::get_file_hunks(repo_path, "file.txt", false).unwrap().len(), 1);
+
+        GitService::unstage_hunk(repo_path, "file.txt", &staged[0].header).unwrap();
+        assert!(GitService::get_file_hunks(repo_path, "file.txt", true).unwrap().is_empty());
+        assert_eq!(GitService::get_file_hunks(repo_path, "file.txt", false).unwrap().len(), 2);
+        // 工作区内容不受影响
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), modified);
+
+        assert!(GitService::stage_hunk(repo_path, "file.txt", "@@ -99,1 +99,1 @@").is_err());
+    }
+
+    #[test]
+    fn test_discard_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo_with_commit(repo_path);
+
+        fs::write(repo_path.join("file.txt"), "changed").unwrap();
+        fs::create_dir(repo_path.join("dir")).unwrap();
+        fs::write(repo_path.join("dir").join("untracked.txt"), "untracked").unwrap();
+
+        GitService::discard_changes(repo_path, &["file.txt".to_string(), "dir".to_string()]).unwrap();
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "line 1\nline 2\nline 3\n");
+        assert!(!repo_path.join("dir").join("untracked.txt").exists());
+        assert!(!GitService::get_repository_status(repo_path).unwrap().is_dirty);
+    }
+
+    #[test]
+    fn test_commit_message_validation() {
+        let validate = |message: &str| GitService::validate_commit_message(message, false, None);
+        assert!(validate("").is_err());
+        assert!(validate("Summary\nbody without blank line").is_err());
+
+        // 注释行只在要求时去掉
+        assert!(GitService::validate_commit_message("# only a comment\n\n", true, None).is_err());
+        assert_eq!(validate("#123 fix login").unwrap(), "#123 fix login\n");
+        assert_eq!(validate("Summary\n\n# Heading\nBody").unwrap(), "Summary\n\n# Heading\nBody\n");
+
+        // 摘要长度只在指定限制时检查
+        let long_summary = "x".repeat(73);
+        assert!(validate(&long_summary).is_ok());
+        assert!(GitService::validate_commit_message(&long_summary, false, Some(MAX_COMMIT_SUMMARY_LENGTH)).is_err());
+
+        let message = GitService::validate_commit_message("\nSummary  \n\n\nBody\n# comment\n\n", true, None).unwrap();
+        assert_eq!(message, "Summary\n\nBody\n");
+    }
+
+    #[test]
+    fn test_commit_amend_and_allow_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let repo = init_repo_with_commit(repo_path);
+
+        // 没有更改时需要 allow_empty
+        let options = CommitOptions {
+            message: "Empty".to_string(),
+            ..Default::default()
+        };
+        assert!(GitService::commit(repo_path, &options).is_err());
+        let empty_id = GitService::commit(repo_path, &CommitOptions { allow_empty: true, ..options }).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id().to_string(), empty_id);
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(head.author().name(), Some("Test User"));
+
+        // 修改最近一次提交，保留原作者并使用新的说明和内容
+        fs::write(repo_path.join("file.txt"), "amended").unwrap();
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        let amended_id = GitService::commit(repo_path, &CommitOptions {
+            message: "Amended\n\nWith body".to_string(),
+            amend: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let amended = repo.find_commit(git2::Oid::from_str(&amended_id).unwrap()).unwrap();
+        assert_eq!(amended.message(), Some("Amended\n\nWith body\n"));
+        assert_eq!(amended.parent_id(0).unwrap(), head.parent_id(0).unwrap());
+        assert_eq!(amended.author().email(), Some("test@example.com"));
+
+        // 指定作者
+        fs::write(repo_path.join("other.txt"), "other").unwrap();
+        GitService::stage_paths(repo_path, &["other.txt".to_string()]).unwrap();
+        let id = GitService::commit(repo_path, &CommitOptions {
+            message: "Other author".to_string(),
+            author_name: Some("Someone Else".to_string()),
+            author_email: Some("else@example.com".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let commit = repo.find_commit(git2::Oid::from_str(&id).unwrap()).unwrap();
+        assert_eq!(commit.author().name(), Some("Someone Else"));
+        assert_eq!(commit.committer().name(), Some("Test User"));
+    }
+
+    #[test]
+    fn test_commit_log_pagination_and_filters() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        init_repo_with_commit(repo_path);
+
+        fs::create_dir(repo_path.join("src")).unwrap();
+        for i in 1..=5 {
+            fs::write(repo_path.join("src").join("lib.rs"), format!("// version {}", i)).unwrap();
+            GitService::stage_paths(repo_path, &["src".to_string()]).unwrap();
+            GitService::commit(repo_path, &CommitOptions {
+                message: format!("Update lib {}", i),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        fs::write(repo_path.join("file.txt"), "docs").unwrap();
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        GitService::commit(repo_path, &CommitOptions {
+            message: "Fix docs".to_string(),
+            author_name: Some("Doc Writer".to_string()),
+            author_email: Some("docs@example.com".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let query = CommitLogQuery {
+            limit: Some(3),
+            ..Default::default()
+        };
+        let page = GitService::get_commit_log(repo_path, &query).unwrap();
+        assert_eq!(page.commits.len(), 3);
+        assert!(page.has_more);
+        assert_eq!(page.commits[0].summary, "Fix docs");
+        assert_eq!(page.commits[0].parent_ids[0], page.commits[1].id);
+
+        let page = GitService::get_commit_log(repo_path, &CommitLogQuery { offset: 6, ..query.clone() }).unwrap();
+        assert_eq!(page.commits.len(), 1);
+        assert_eq!(page.commits[0].summary, "Initial commit");
+        assert!(page.commits[0].parent_ids.is_empty());
+        assert!(!page.has_more);
+
+        let by_path = GitService::get_commit_log(repo_path, &CommitLogQuery {
+            path: Some("src/".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(by_path.commits.len(), 5);
+
+        let by_author = GitService::get_commit_log(repo_path, &CommitLogQuery {
+            author: Some("DOCS@".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(by_author.commits.len(), 1);
+        assert_eq!(by_author.commits[0].author_name, "Doc Writer");
+
+        let by_message = GitService::get_commit_log(repo_path, &CommitLogQuery {
+            message: Some("lib 3".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(by_message.commits.len(), 1);
+
+        let future = chrono::Utc::now() + chrono::Duration::days(1);
+        let by_date = GitService::get_commit_log(repo_path, &CommitLogQuery {
+            since: Some(future),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(by_date.commits.is_empty());
+    }
+
+    #[test]
+    fn test_commit_details_and_worktree_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        init_repo_with_commit(&repo_path);
+
+        fs::write(repo_path.join("file.txt"), "line 1\nchanged\nline 3\nline 4\n").unwrap();
+        fs::write(repo_path.join("added.txt"), "added\n").unwrap();
+        GitService::stage_paths(&repo_path, &[".".to_string()]).unwrap();
+        let commit_id = GitService::commit(&repo_path, &CommitOptions {
+            message: "Change files\n\nLonger description".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let details = GitService::get_commit_details(&repo_path, &commit_id[..10]).unwrap();
+        assert_eq!(details.commit.id, commit_id);
+        assert_eq!(details.commit.summary, "Change files");
+        assert_eq!(details.message, "Change files\n\nLonger description\n");
+        assert_eq!(details.commit.parent_ids.len(), 1);
+        assert_eq!(details.files.len(), 2);
+        assert_eq!((details.stats.additions, details.stats.deletions), (3, 1));
+        assert!(details.files.iter().all(|f| f.hunks.is_empty()));
+
+        let root = GitService::get_commit_details(&repo_path, &details.commit.parent_ids[0]).unwrap();
+        assert_eq!(root.files[0].status, "added");
+        assert!(GitService::get_commit_details(&repo_path, "0000000").is_err());
+
+        // worktree中查询到相同的历史
+        let worktree_path = temp_dir.path().join("wt");
+        GitService::create_worktree(&repo_path, "wt", &worktree_path, None).unwrap();
+        let page = GitService::get_commit_log(&worktree_path, &CommitLogQuery::default()).unwrap();
+        assert_eq!(page.commits.len(), 2);
+        assert_eq!(page.commits[0].id, commit_id);
+    }
+
+    /// 创建裸仓库作为远程，并从一个已有提交的仓库推送当前分支
+    fn setup_remote(temp_path: &Path) -> (PathBuf, PathBuf, String) {
+        let remote_path = temp_path.join("remote.git");
+        let bare = GitService::init_repository(&remote_path, true).unwrap();
+        let local_path = temp_path.join("local");
+        fs::create_dir(&local_path).unwrap();
+        let repo = init_repo_with_commit(&local_path);
+        repo.remote("origin", remote_path.to_str().unwrap()).unwrap();
+
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        let result = GitService::push(&local_path, &PushOptions {
+            set_upstream: true,
+            ..Default::default()
+        }, None)
+        .unwrap();
+        assert_eq!(result.branch, branch);
+        bare.set_head(&format!("refs/heads/{}", branch)).unwrap();
+
+        (remote_path, local_path, branch)
+    }
+
+    fn clone_repo(remote_path: &Path, path: &Path) -> Repository {
+        let repo = GitService::clone_repository(remote_path.to_str().unwrap(), path, None).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Other User").unwrap();
+        config.set_str("user.email", "other@example.com").unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_push_and_fetch() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, branch) = setup_remote(temp_dir.path());
+
+        let local = GitService::open_repository(&local_path).unwrap();
+        let upstream = local.find_branch(&branch, BranchType::Local).unwrap().upstream().unwrap();
+        assert_eq!(upstream.name().unwrap(), Some(format!("origin/{}", branch).as_str()));
+
+        let other_path = temp_dir.path().join("other");
+        clone_repo(&remote_path, &other_path);
+        let pushed = commit_file(&other_path, "other.txt", "other", "Other change");
+        GitService::push(&other_path, &PushOptions::default(), None).unwrap();
+
+        let events = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorder = events.clone();
+        let result = GitService::fetch(&local_path, None, true, Some(Box::new(move |progress| {
+            recorder.borrow_mut().push(progress);
+        })))
+        .unwrap();
+        assert_eq!(result.remote, "origin");
+        assert!(result.updated_refs.contains(&format!("refs/remotes/origin/{}", branch)));
+        assert!(!events.borrow().is_empty());
+        assert_eq!(
+            local.refname_to_id(&format!("refs/remotes/origin/{}", branch)).unwrap().to_string(),
+            pushed
+        );
+
+        // 获取后本地落后远程一个提交
+        let status = GitService::get_repository_status(&local_path).unwrap();
+        assert_eq!((status.ahead, status.behind), (0, 1));
+        assert!(GitService::fetch(&local_path, Some("missing"), false, None).is_err());
+    }
+
+    #[test]
+    fn test_pull_strategies() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, _) = setup_remote(temp_dir.path());
+        let other_path = temp_dir.path().join("other");
+        clone_repo(&remote_path, &other_path);
+
+        // 快进
+        commit_file(&other_path, "a.txt", "a", "Add a");
+        GitService::push(&other_path, &PushOptions::default(), None).unwrap();
+        let result = GitService::pull(&local_path, PullStrategy::FastForwardOnly, None).unwrap();
+        assert_eq!(result.status, PullStatus::FastForwarded);
+        assert!(local_path.join("a.txt").exists());
+        let result = GitService::pull(&local_path, PullStrategy::FastForwardOnly, None).unwrap();
+        assert_eq!(result.status, PullStatus::UpToDate);
+
+        // 分叉后只允许快进时失败，合并时创建合并提交
+        GitService::pull(&other_path, PullStrategy::FastForwardOnly, None).unwrap();
+        commit_file(&other_path, "b.txt", "b", "Add b");
+        GitService::push(&other_path, &PushOptions::default(), None).unwrap();
+        commit_file(&local_path, "c.txt", "c", "Add c");
+        assert!(GitService::pull(&local_path, PullStrategy::FastForwardOnly, None).is_err());
+        let result = GitService::pull(&local_path, PullStrategy::Merge, None).unwrap();
+        assert_eq!(result.status, PullStatus::Merged);
+        let local = GitService::open_repository(&local_path).unwrap();
+        assert_eq!(local.head().unwrap().peel_to_commit().unwrap().parent_count(), 2);
+        assert_eq!(local.state(), RepositoryState::Clean);
+        GitService::push(&local_path, &PushOptions::default(), None).unwrap();
+
+        // 变基后历史保持线性
+        GitService::pull(&other_path, PullStrategy::FastForwardOnly, None).unwrap();
+        let upstream_commit = commit_file(&other_path, "d.txt", "d", "Add d");
+        GitService::push(&other_path, &PushOptions::default(), None).unwrap();
+        commit_file(&local_path, "e.txt", "e", "Add e");
+        let result = GitService::pull(&local_path, PullStrategy::Rebase, None).unwrap();
+        assert_eq!(result.status, PullStatus::Rebased);
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Add e"));
+        assert_eq!(head.parent_id(0).unwrap().to_string(), upstream_commit);
+        assert!(local_path.join("d.txt").exists() && local_path.join("e.txt").exists());
+    }
+
+    #[test]
+    fn test_pull_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, _) = setup_remote(temp_dir.path());
+        let other_path = temp_dir.path().join("other");
+        clone_repo(&remote_path, &other_path);
+
+        commit_file(&other_path, "file.txt", "theirs\n", "Their change");
+        commit_file(&other_path, "new.txt", "new\n", "Add new");
+        GitService::push(&other_path, &PushOptions::default(), None).unwrap();
+        commit_file(&local_path, "file.txt", "ours\n", "Our change");
+
+        // 有未提交的更改时不开始合并
+        commit_file(&local_path, "other.txt", "base\n", "Add other");
+        fs::write(local_path.join("other.txt"), "local edit\n").unwrap();
+        assert!(GitService::pull(&local_path, PullStrategy::Merge, None).is_err());
+
+        // 冲突后放弃合并，合并未涉及的文件中的更改被保留
+        commit_file(&local_path, "other.txt", "committed\n", "Edit other");
+        let result = GitService::pull(&local_path, PullStrategy::Merge, None).unwrap();
+        assert_eq!(result.status, PullStatus::Conflicts);
+        assert_eq!(result.conflicts, vec!["file.txt".to_string()]);
+        let local = GitService::open_repository(&local_path).unwrap();
+        assert_eq!(local.state(), RepositoryState::Merge);
+
+        fs::write(local_path.join("other.txt"), "edited during merge\n").unwrap();
+        GitService::abort_operation(&local_path).unwrap();
+        assert_eq!(local.state(), RepositoryState::Clean);
+        assert_eq!(fs::read_to_string(local_path.join("file.txt")).unwrap(), "ours\n");
+        assert_eq!(fs::read_to_string(local_path.join("other.txt")).unwrap(), "edited during merge\n");
+        assert!(!local_path.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_push_force_with_lease() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, _) = setup_remote(temp_dir.path());
+        let other_path = temp_dir.path().join("other");
+        clone_repo(&remote_path, &other_path);
+
+        // 改写已推送的提交，普通推送被拒绝，远程未变化时可以强制推送
+        commit_file(&local_path, "a.txt", "a", "Add a");
+        GitService::push(&local_path, &PushOptions::default(), None).unwrap();
+        fs::write(local_path.join("a.txt"), "rewritten").unwrap();
+        GitService::stage_paths(&local_path, &["a.txt".to_string()]).unwrap();
+        GitService::commit(&local_path, &CommitOptions {
+            message: "Add a (rewritten)".to_string(),
+            amend: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(GitService::push(&local_path, &PushOptions::default(), None).is_err());
+        GitService::push(&local_path, &PushOptions {
+            force_with_lease: true,
+            ..Default::default()
+        }, None)
+        .unwrap();
+
+        // other 的远程跟踪分支已过期，强制推送被拒绝
+        commit_file(&other_path, "b.txt", "b", "Add b");
+        let lease = PushOptions {
+            force_with_lease: true,
+            ..Default::default()
+        };
+        let error = GitService::push(&other_path, &lease, None).unwrap_err();
+        assert!(error.to_string().contains("changed since the last fetch"));
+
+        GitService::fetch(&other_path, None, false, None).unwrap();
+        GitService::push(&other_path, &lease, None).unwrap();
+
+        // 推送到新的远程分支并设置上游
+        let result = GitService::push(&other_path, &PushOptions {
+            remote_branch: Some("feature".to_string()),
+            set_upstream: true,
+            ..Default::default()
+        }, None)
+        .unwrap();
+        assert_eq!(result.remote_branch, "feature");
+        let remote = GitService::open_repository(&remote_path).unwrap();
+        assert!(remote.find_reference("refs/heads/feature").is_ok());
+    }
+
+    /// 创建从当前提交分叉的 feature 分支，两边都修改 file.txt 的同一行
+    fn setup_diverged_branches(repo_path: &Path) -> (Repository, String) {
+        let repo = init_repo_with_commit(repo_path);
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        GitService::create_branch(repo_path, "feature", None).unwrap();
+
+        commit_file(repo_path, "file.txt", "line 1\nmain\nline 3\n", "Change on main");
+        {
+            let feature = repo.revparse_single("feature").unwrap();
+            repo.checkout_tree(&feature, Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+            repo.set_head("refs/heads/feature").unwrap();
+        }
+        commit_file(repo_path, "feature.txt", "feature", "Add feature");
+        commit_file(repo_path, "file.txt", "line 1\nfeature\nline 3\n", "Change on feature");
+        (repo, main)
+    }
+
+    #[test]
+    fn test_update_from_branch_merge_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let (repo, main) = setup_diverged_branches(repo_path);
+
+        let result = GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        assert_eq!(result.status, PullStatus::Conflicts);
+        assert_eq!(result.conflicts, vec!["file.txt".to_string()]);
+        let state = GitService::get_operation_state(repo_path).unwrap();
+        assert_eq!(state.operation, GitOperation::Merge);
+        assert!(GitService::continue_operation(repo_path).is_err());
+        assert!(GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).is_err());
+
+        // 放弃后恢复到合并前，未跟踪的文件保持不变
+        fs::write(repo_path.join("notes.txt"), "keep").unwrap();
+        GitService::abort_operation(repo_path).unwrap();
+        assert_eq!(repo.state(), RepositoryState::Clean);
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "line 1\nfeature\nline 3\n");
+        assert_eq!(fs::read_to_string(repo_path.join("notes.txt")).unwrap(), "keep");
+        fs::remove_file(repo_path.join("notes.txt")).unwrap();
+
+        // 解决冲突后继续，生成合并提交
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        fs::write(repo_path.join("file.txt"), "line 1\nboth\nline 3\n").unwrap();
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        let result = GitService::continue_operation(repo_path).unwrap();
+        assert_eq!(result.status, PullStatus::Merged);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(head.summary(), Some(format!("Merge branch '{}'", main).as_str()));
+        assert_eq!(GitService::get_operation_state(repo_path).unwrap().operation, GitOperation::None);
+
+        let result = GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        assert_eq!(result.status, PullStatus::UpToDate);
+    }
+
+    #[test]
+    fn test_update_from_branch_rebase_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let (repo, main) = setup_diverged_branches(repo_path);
+        let main_head = repo.refname_to_id(&format!("refs/heads/{}", main)).unwrap();
+
+        fs::write(repo_path.join("file.txt"), "dirty\n").unwrap();
+        assert!(GitService::update_from_branch(repo_path, &main, PullStrategy::Rebase).is_err());
+        GitService::discard_changes(repo_path, &["file.txt".to_string()]).unwrap();
+
+        // 第二个提交冲突
+        let result = GitService::update_from_branch(repo_path, &main, PullStrategy::Rebase).unwrap();
+        assert_eq!(result.status, PullStatus::Conflicts);
+        assert_eq!(result.conflicts, vec!["file.txt".to_string()]);
+        let state = GitService::get_operation_state(repo_path).unwrap();
+        assert_eq!(state.operation, GitOperation::Rebase);
+        assert_eq!(state.rebase_progress, Some((2, 2)));
+
+        GitService::abort_operation(repo_path).unwrap();
+        assert_eq!(repo.state(), RepositoryState::Clean);
+        assert_eq!(repo.head().unwrap().shorthand(), Some("feature"));
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().summary(), Some("Change on feature"));
+
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Rebase).unwrap();
+        fs::write(repo_path.join("file.txt"), "line 1\nboth\nline 3\n").unwrap();
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        let result = GitService::continue_operation(repo_path).unwrap();
+        assert_eq!(result.status, PullStatus::Rebased);
+
+        assert_eq!(repo.head().unwrap().shorthand(), Some("feature"));
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Change on feature"));
+        assert_eq!(head.parent(0).unwrap().parent_id(0).unwrap(), main_head);
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "line 1\nboth\nline 3\n");
+    }
+
+    #[test]
+    fn test_resolve_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let (_repo, main) = setup_diverged_branches(repo_path);
+        let read_file = || fs::read_to_string(repo_path.join("file.txt")).unwrap();
+
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        let conflicts = GitService::get_conflicts(repo_path).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.path, "file.txt");
+        let content = |side: &Option<ConflictSide>| side.as_ref().unwrap().content.clone().unwrap();
+        assert_eq!(content(&conflict.ancestor), "line 1\nline 2\nline 3\n");
+        assert_eq!(content(&conflict.ours), "line 1\nfeature\nline 3\n");
+        assert_eq!(content(&conflict.theirs), "line 1\nmain\nline 3\n");
+
+        // 选择我方
+        let remaining = GitService::resolve_conflict(repo_path, "file.txt", &ConflictResolution::Ours).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(read_file(), "line 1\nfeature\nline 3\n");
+        assert!(GitService::resolve_conflict(repo_path, "file.txt", &ConflictResolution::Theirs).is_err());
+        GitService::abort_operation(repo_path).unwrap();
+
+        // 选择对方
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        GitService::resolve_conflict(repo_path, "file.txt", &ConflictResolution::Theirs).unwrap();
+        assert_eq!(read_file(), "line 1\nmain\nline 3\n");
+        GitService::abort_operation(repo_path).unwrap();
+
+        // 手动合并的内容
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        let merged = ConflictResolution::Merged("line 1\nmerged\nline 3\n".to_string());
+        GitService::resolve_conflict(repo_path, "file.txt", &merged).unwrap();
+        assert_eq!(read_file(), "line 1\nmerged\nline 3\n");
+        GitService::abort_operation(repo_path).unwrap();
+
+        // 在工作目录中编辑后标记为已解决
+        GitService::update_from_branch(repo_path, &main, PullStrategy::Merge).unwrap();
+        assert_eq!(GitService::get_conflicted_paths(repo_path).unwrap(), vec!["file.txt".to_string()]);
+        fs::write(repo_path.join("file.txt"), "line 1\nedited\nline 3\n").unwrap();
+        let remaining = GitService::mark_resolved(repo_path, &["file.txt".to_string()]).unwrap();
+        assert!(remaining.is_empty());
+        assert!(GitService::get_conflicts(repo_path).unwrap().is_empty());
+
+        GitService::continue_operation(repo_path).unwrap();
+        let status = GitService::get_repository_status(repo_path).unwrap();
+        assert!(!status.is_dirty);
+        assert_eq!(read_file(), "line 1\nedited\nline 3\n");
+    }
+
+    #[test]
+    fn test_stash_save_apply_pop_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let repo = init_repo_with_commit(repo_path);
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        assert!(GitService::save_stash(repo_path, &StashOptions::default()).is_err());
+
+        fs::write(repo_path.join("file.txt"), "line 1\nchanged\nline 3\n").unwrap();
+        fs::write(repo_path.join("new.txt"), "new\n").unwrap();
+        let stash = GitService::save_stash(repo_path, &StashOptions {
+            message: Some("work in progress".to_string()),
+            include_untracked: true,
+            keep_index: false,
+        })
+        .unwrap();
+        assert_eq!(stash.message, format!("On {}: work in progress", branch));
+        assert_eq!(stash.branch, Some(branch.clone()));
+        assert!(stash.has_untracked);
+        assert!(!GitService::get_repository_status(repo_path).unwrap().is_dirty);
+        assert!(!repo_path.join("new.txt").exists());
+
+        let diff = GitService::get_stash_diff(repo_path, &stash.id, &GitDiffOptions::default()).unwrap();
+        let mut paths: Vec<String> = diff.files.iter().filter_map(|f| f.new_path.clone()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["file.txt".to_string(), "new.txt".to_string()]);
+
+        // 应用后保留储藏
+        assert!(GitService::apply_stash(repo_path, &stash.id, false).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(repo_path.join("new.txt")).unwrap(), "new\n");
+        assert_eq!(GitService::list_stashes(repo_path).unwrap().len(), 1);
+
+        fs::remove_file(repo_path.join("new.txt")).unwrap();
+        GitService::discard_changes(repo_path, &["file.txt".to_string()]).unwrap();
+        assert!(GitService::pop_stash(repo_path, &stash.id, false).unwrap().is_empty());
+        assert!(GitService::list_stashes(repo_path).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(repo_path.join("file.txt")).unwrap(), "line 1\nchanged\nline 3\n");
+
+        // keep_index 保留已暂存的更改
+        GitService::stage_paths(repo_path, &["file.txt".to_string()]).unwrap();
+        let older = GitService::save_stash(repo_path, &StashOptions {
+            keep_index: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let staged = file_status(repo_path, "file.txt").unwrap();
+        assert!(staged.is_staged);
+        assert!(repo_path.join("new.txt").exists());
+
+        GitService::save_stash(repo_path, &StashOptions {
+            include_untracked: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let stashes = GitService::list_stashes(repo_path).unwrap();
+        assert_eq!(stashes.len(), 2);
+        assert!(stashes[0].message.starts_with(&format!("WIP on {}:", branch)));
+        // 按ID删除，位置变化后仍指向同一个储藏
+        assert_eq!(stashes[1].id, older.id);
+        GitService::drop_stash(repo_path, &older.id).unwrap();
+        let stashes = GitService::list_stashes(repo_path).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_ne!(stashes[0].id, older.id);
+        assert!(GitService::drop_stash(repo_path, &older.id).is_err());
+    }
+
+    #[test]
+    fn test_stash_in_worktree() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        let repo = init_repo_with_commit(&repo_path);
+        let worktree_path = temp_dir.path().join("feature");
+        repo.worktree("feature", &worktree_path, None).unwrap();
+
+        fs::write(worktree_path.join("file.txt"), "worktree change\n").unwrap();
+        let stash = GitService::save_stash(&worktree_path, &StashOptions {
+            message: Some("park".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(stash.branch, Some("feature".to_string()));
+        assert_eq!(fs::read_to_string(worktree_path.join("file.txt")).unwrap(), "line 1\nline 2\nline 3\n");
+
+        // 储藏列表在主仓库和worktree之间共享
+        assert_eq!(GitService::list_stashes(&repo_path).unwrap().len(), 1);
+        GitService::pop_stash(&worktree_path, &stash.id, false).unwrap();
+        assert_eq!(fs::read_to_string(worktree_path.join("file.txt")).unwrap(), "worktree change\n");
+        assert!(GitService::get_repository_status(&repo_path).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_create_worktree_from_refs() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, branch) = setup_remote(temp_dir.path());
+        let other_path = temp_dir.path().join("other");
+        clone_repo(&remote_path, &other_path);
+        GitService::create_branch(&other_path, "remote-feature", None).unwrap();
+        let remote_commit = {
+            let other = GitService::open_repository(&other_path).unwrap();
+            let head = other.head().unwrap().peel_to_commit().unwrap();
+            head.id()
+        };
+        GitService::push(&other_path, &PushOptions {
+            branch: Some("remote-feature".to_string()),
+            ..Default::default()
+        }, None)
+        .unwrap();
+        GitService::fetch(&local_path, None, false, None).unwrap();
+
+        let repo = GitService::open_repository(&local_path).unwrap();
+        let initial = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("v1.0", initial.as_object(), false).unwrap();
+        commit_file(&local_path, "second.txt", "second", "Second commit");
+
+        // 检出已有分支
+        GitService::create_branch(&local_path, "existing", None).unwrap();
+        GitService::create_worktree(&local_path, "wt-existing", &temp_dir.path().join("wt-existing"), Some("existing"))
+            .unwrap();
+        let worktree = GitService::open_repository(temp_dir.path().join("wt-existing")).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("existing"));
+
+        // 分支已在主仓库或其他worktree检出
+        let error = GitService::create_worktree(&local_path, "wt-main", &temp_dir.path().join("wt-main"), Some(&branch))
+            .unwrap_err();
+        assert!(error.to_string().contains("already checked out"));
+        let error = GitService::create_worktree(&local_path, "wt-again", &temp_dir.path().join("wt-again"), Some("existing"))
+            .unwrap_err();
+        assert!(error.to_string().contains("already checked out"));
+
+        // 从同名远程分支创建并设置上游
+        let info = GitService::create_worktree_with_options(
+            &local_path,
+            "wt-remote",
+            &temp_dir.path().join("wt-remote"),
+            &WorktreeOptions {
+                branch: Some("remote-feature".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(info.branch, Some("remote-feature".to_string()));
+        let local_branch = repo.find_branch("remote-feature", BranchType::Local).unwrap();
+        assert_eq!(local_branch.get().target(), Some(remote_commit));
+        assert_eq!(local_branch.upstream().unwrap().name().unwrap(), Some("origin/remote-feature"));
+
+        // 从标签创建新分支
+        let info = GitService::create_worktree_with_options(
+            &local_path,
+            "wt-tag",
+            &temp_dir.path().join("wt-tag"),
+            &WorktreeOptions {
+                branch: Some("from-tag".to_string()),
+                start_point: Some("v1.0".to_string()),
+                detach: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(info.branch, Some("from-tag".to_string()));
+        assert!(!temp_dir.path().join("wt-tag").join("second.txt").exists());
+
+        // 以分离HEAD检出指定提交，不覆盖同名分支，也不留下临时分支
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("workhorse-worktree-wt-detached", &head, false).unwrap();
+        let info = GitService::create_worktree_with_options(
+            &local_path,
+            "wt-detached",
+            &temp_dir.path().join("wt-detached"),
+            &WorktreeOptions {
+                start_point: Some(initial.id().to_string()),
+                detach: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(info.branch, None);
+        let worktree = GitService::open_repository(temp_dir.path().join("wt-detached")).unwrap();
+        assert!(worktree.head_detached().unwrap());
+        assert_eq!(worktree.head().unwrap().target(), Some(initial.id()));
+        let existing_branch = repo.find_branch("workhorse-worktree-wt-detached", BranchType::Local).unwrap();
+        assert_eq!(existing_branch.get().target(), Some(head.id()));
+        let temp_branches = repo
+            .branches(Some(BranchType::Local))
+            .unwrap()
+            .filter(|branch| {
+                let name = branch.as_ref().unwrap().0.name().unwrap().unwrap().to_string();
+                name.starts_with("workhorse-worktree-") && name != "workhorse-worktree-wt-detached"
+            })
+            .count();
+        assert_eq!(temp_branches, 0);
+        assert!(GitService::get_repository_status(temp_dir.path().join("wt-detached")).unwrap().files.is_empty());
+
+        assert!(GitService::create_worktree_with_options(
+            &local_path,
+            "wt-missing",
+            &temp_dir.path().join("wt-missing"),
+            &WorktreeOptions {
+                branch: Some("missing".to_string()),
+                start_point: Some("no-such-ref".to_string()),
+                detach: false,
+            },
+        )
+        .is_err());
+        assert!(repo.find_branch("missing", BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn test_branch_merge_status_and_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let (remote_path, local_path, main) = setup_remote(temp_dir.path());
+        let repo = GitService::open_repository(&local_path).unwrap();
+
+        GitService::create_branch(&local_path, "merged", None).unwrap();
+        GitService::create_branch(&local_path, "unmerged", None).unwrap();
+        {
+            let unmerged = repo.revparse_single("unmerged").unwrap();
+            repo.checkout_tree(&unmerged, None).unwrap();
+            repo.set_head("refs/heads/unmerged").unwrap();
+        }
+        commit_file(&local_path, "extra.txt", "extra", "Unmerged work");
+        GitService::push(&local_path, &PushOptions {
+            set_upstream: true,
+            ..Default::default()
+        }, None)
+        .unwrap();
+        commit_file(&local_path, "extra.txt", "more", "Unpushed work");
+        {
+            let main_commit = repo.revparse_single(&main).unwrap();
+            repo.checkout_tree(&main_commit, Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+            repo.set_head(&format!("refs/heads/{}", main)).unwrap();
+        }
+
+        let branches = GitService::get_branches_with_merge_status(&local_path, &main).unwrap();
+        let find = |name: &str| branches.iter().find(|b| b.name == name).unwrap().clone();
+        assert_eq!(find("merged").is_merged, Some(true));
+        assert_eq!(find("unmerged").is_merged, Some(false));
+        assert_eq!(find("origin/unmerged").is_merged, Some(false));
+        assert!(find("unmerged").last_commit_at.is_some());
+        assert!(GitService::get_branches(&local_path).unwrap().iter().all(|b| b.is_merged.is_none()));
+
+        // 当前分支和未合并的分支不能直接删除
+        assert!(GitService::delete_branch(&local_path, &main, true, false).is_err());
+        GitService::delete_branch(&local_path, "merged", false, false).unwrap();
+        assert!(repo.find_branch("merged", BranchType::Local).is_err());
+        let error = GitService::delete_branch(&local_path, "unmerged", false, false).unwrap_err();
+        assert!(error.to_string().contains("not fully merged"));
+
+        // 强制删除并同时删除远程分支
+        GitService::delete_branch(&local_path, "unmerged", true, true).unwrap();
+        assert!(repo.find_branch("unmerged", BranchType::Local).is_err());
+        assert!(repo.find_reference("refs/remotes/origin/unmerged").is_err());
+        let remote = GitService::open_repository(&remote_path).unwrap();
+        assert!(remote.find_reference("refs/heads/unmerged").is_err());
+    }
+
+    #[test]
+    fn test_rename_branch_in_worktree() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path().join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        let repo = init_repo_with_commit(&repo_path);
+        let worktree_path = temp_dir.path().join("wt");
+        GitService::create_worktree(&repo_path, "wt", &worktree_path, Some("old-name")).unwrap();
+        GitService::create_branch(&repo_path, "taken", None).unwrap();
+
+        assert!(GitService::rename_branch(&repo_path, "old-name", "taken", false).is_err());
+        assert!(GitService::rename_branch(&repo_path, "missing", "other", false).is_err());
+        GitService::rename_branch(&repo_path, "old-name", "new-name", false).unwrap();
+
+        assert!(repo.find_branch("old-name", BranchType::Local).is_err());
+        let worktree = GitService::open_repository(&worktree_path).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("new-name"));
+        let worktrees = GitService::list_worktrees(&repo_path).unwrap();
+        assert_eq!(worktrees[0].branch, Some("new-name".to_string()));
     }
 }
\ No newline at end of file
@@ -0,0 +1,477 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::database::repository::RepositoryService;
+use crate::services::repository_service::{RepositoryConfig, RepositoryManagerService};
+use crate::services::GitService;
+
+/// 调度器检查到期仓库的间隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// 后台维护设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// 两次维护之间的间隔
+    pub interval_seconds: u64,
+    /// 连续失败后重试间隔的上限
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 15 * 60,
+            max_backoff_seconds: 4 * 60 * 60,
+        }
+    }
+}
+
+/// 单次维护的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub fetched_remotes: Vec<String>,
+    pub updated_refs: Vec<String>,
+    pub pruned_refs: Vec<String>,
+    pub pruned_worktrees: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl MaintenanceRun {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// 仓库的维护状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryMaintenance {
+    pub repository_path: PathBuf,
+    pub last_run: Option<MaintenanceRun>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    pub next_run_at: DateTime<Utc>,
+    pub running: bool,
+}
+
+impl RepositoryMaintenance {
+    fn new(repository_path: PathBuf) -> Self {
+        Self {
+            repository_path,
+            last_run: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            next_run_at: Utc::now(),
+            running: false,
+        }
+    }
+}
+
+/// 按仓库配置的 auto_fetch 和 auto_prune 在后台定期获取远程更新并清理过期引用
+#[derive(Debug, Default)]
+pub struct MaintenanceService {
+    /// 设置的保存路径，为空时只保存在内存中
+    settings_file: Option<PathBuf>,
+    settings: Mutex<MaintenanceSettings>,
+    repositories: Mutex<HashMap<PathBuf, RepositoryMaintenance>>,
+}
+
+impl MaintenanceService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从文件加载设置，之后的修改也写入该文件，文件不存在或无法解析时使用默认设置
+    pub fn with_settings_file(settings_file: PathBuf) -> Self {
+        let settings = match fs::read_to_string(&settings_file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("警告: 维护设置解析失败，使用默认设置: {}", e);
+                MaintenanceSettings::default()
+            }),
+            Err(_) => MaintenanceSettings::default(),
+        };
+
+        Self {
+            settings_file: Some(settings_file),
+            settings: Mutex::new(settings),
+            ..Self::default()
+        }
+    }
+
+    fn save_settings(&self, settings: &MaintenanceSettings) -> Result<(), String> {
+        let path = match &self.settings_file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write settings: {}", e))
+    }
+
+    pub fn get_settings(&self) -> MaintenanceSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: MaintenanceSettings) -> Result<(), String> {
+        if settings.interval_seconds == 0 {
+            return Err("Interval must be greater than zero".to_string());
+        }
+        if settings.max_backoff_seconds < settings.interval_seconds {
+            return Err("Maximum backoff must not be shorter than the interval".to_string());
+        }
+        self.save_settings(&settings)?;
+
+        // 按新的间隔重新安排未失败的仓库
+        let interval = chrono::Duration::seconds(settings.interval_seconds as i64);
+        let mut repositories = self.repositories.lock().unwrap();
+        for state in repositories.values_mut() {
+            if state.consecutive_failures == 0 {
+                if let Some(run) = &state.last_run {
+                    state.next_run_at = run.finished_at + interval;
+                }
+            }
+        }
+
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    /// 获取所有仓库的维护状态
+    pub fn get_statuses(&self) -> Vec<RepositoryMaintenance> {
+        let repositories = self.repositories.lock().unwrap();
+        let mut statuses: Vec<RepositoryMaintenance> = repositories.values().cloned().collect();
+        statuses.sort_by(|a, b| a.repository_path.cmp(&b.repository_path));
+        statuses
+    }
+
+    pub fn get_status<P: AsRef<Path>>(&self, repo_path: P) -> Option<RepositoryMaintenance> {
+        let repositories = self.repositories.lock().unwrap();
+        repositories.get(repo_path.as_ref()).cloned()
+    }
+
+    /// 仓库是否到了下一次维护的时间
+    pub fn is_due<P: AsRef<Path>>(&self, repo_path: P, now: DateTime<Utc>) -> bool {
+        let repositories = self.repositories.lock().unwrap();
+        match repositories.get(repo_path.as_ref()) {
+            Some(state) => !state.running && state.next_run_at <= now,
+            None => true,
+        }
+    }
+
+    /// 连续失败后的重试间隔，每次失败翻倍，不超过上限
+    pub fn retry_delay(settings: &MaintenanceSettings, consecutive_failures: u32) -> Duration {
+        let factor = 2u64.saturating_pow(consecutive_failures.min(32));
+        let seconds = settings
+            .interval_seconds
+            .saturating_mul(factor)
+            .min(settings.max_backoff_seconds.max(settings.interval_seconds));
+        Duration::from_secs(seconds)
+    }
+
+    /// 标记仓库开始维护，已在维护中时返回 false
+    fn begin(&self, repo_path: &Path) -> bool {
+        let mut repositories = self.repositories.lock().unwrap();
+        let state = repositories
+            .entry(repo_path.to_path_buf())
+            .or_insert_with(|| RepositoryMaintenance::new(repo_path.to_path_buf()));
+        if state.running {
+            return false;
+        }
+        state.running = true;
+        true
+    }
+
+    /// 记录维护结果并安排下一次维护
+    fn finish(&self, repo_path: &Path, run: &MaintenanceRun) {
+        let settings = self.get_settings();
+        let mut repositories = self.repositories.lock().unwrap();
+        let state = repositories
+            .entry(repo_path.to_path_buf())
+            .or_insert_with(|| RepositoryMaintenance::new(repo_path.to_path_buf()));
+
+        if run.is_success() {
+            state.consecutive_failures = 0;
+            state.last_success_at = Some(run.finished_at);
+        } else {
+            state.consecutive_failures += 1;
+        }
+
+        let delay = Self::retry_delay(&settings, state.consecutive_failures);
+        state.next_run_at = run.finished_at
+            + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::seconds(0));
+        state.last_run = Some(run.clone());
+        state.running = false;
+    }
+
+    /// 按仓库配置执行一次维护，各步骤的错误记录在结果中，不中断其余步骤
+    pub fn run_repository(config: &RepositoryConfig) -> MaintenanceRun {
+        let started_at = Utc::now();
+        let mut run = MaintenanceRun {
+            started_at,
+            finished_at: started_at,
+            fetched_remotes: Vec::new(),
+            updated_refs: Vec::new(),
+            pruned_refs: Vec::new(),
+            pruned_worktrees: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let remotes = match GitService::list_remotes(&config.path) {
+            Ok(remotes) => remotes,
+            Err(e) => {
+                run.errors.push(format!("Failed to list remotes: {}", e));
+                Vec::new()
+            }
+        };
+
+        for remote in &remotes {
+            if config.auto_fetch {
+                match GitService::fetch(&config.path, Some(remote), config.auto_prune, None) {
+                    Ok(result) => {
+                        run.fetched_remotes.push(result.remote);
+                        run.updated_refs.extend(result.updated_refs);
+                        run.pruned_refs.extend(result.pruned_refs);
+                    }
+                    Err(e) => run.errors.push(e.to_string()),
+                }
+            } else if config.auto_prune {
+                match GitService::prune_remote(&config.path, remote) {
+                    Ok(pruned) => run.pruned_refs.extend(pruned),
+                    Err(e) => run.errors.push(e.to_string()),
+                }
+            }
+        }
+
+        if config.auto_prune {
+            match GitService::prune_worktrees(&config.path) {
+                Ok(pruned) => run.pruned_worktrees = pruned,
+                Err(e) => run.errors.push(format!("Failed to prune worktrees: {}", e)),
+            }
+        }
+
+        run.finished_at = Utc::now();
+        run
+    }
+
+    /// 立即维护指定仓库，不受间隔限制
+    pub async fn run_now<P: AsRef<Path>>(&self, repo_path: P) -> Result<MaintenanceRun, String> {
+        let config = RepositoryManagerService::load_repository_config(repo_path.as_ref())
+            .map_err(|e| format!("Failed to load repository config: {}", e))?;
+        self.run_with_config(config).await
+    }
+
+    async fn run_with_config(&self, config: RepositoryConfig) -> Result<MaintenanceRun, String> {
+        let repo_path = config.path.clone();
+        if !self.begin(&repo_path) {
+            return Err("Maintenance is already running for this repository".to_string());
+        }
+
+        let run = match tokio::task::spawn_blocking(move || Self::run_repository(&config)).await {
+            Ok(run) => run,
+            Err(e) => {
+                let now = Utc::now();
+                MaintenanceRun {
+                    started_at: now,
+                    finished_at: now,
+                    fetched_remotes: Vec::new(),
+                    updated_refs: Vec::new(),
+                    pruned_refs: Vec::new(),
+                    pruned_worktrees: Vec::new(),
+                    errors: vec![format!("Maintenance task failed: {}", e)],
+                }
+            }
+        };
+
+        self.finish(&repo_path, &run);
+        Ok(run)
+    }
+
+    /// 维护所有已到期且开启了 auto_fetch 或 auto_prune 的仓库
+    pub async fn run_due(&self, repo_paths: &[PathBuf]) -> Vec<MaintenanceRun> {
+        let mut runs = Vec::new();
+        let now = Utc::now();
+
+        for repo_path in repo_paths {
+            let config = match RepositoryManagerService::load_repository_config(repo_path) {
+                Ok(config) => config,
+                Err(_) => continue,
+            };
+            if !config.auto_fetch && !config.auto_prune {
+                // 关闭后不再保留过期的状态
+                self.repositories.lock().unwrap().remove(repo_path);
+                continue;
+            }
+            if !self.is_due(repo_path, now) {
+                continue;
+            }
+
+            if let Ok(run) = self.run_with_config(config).await {
+                runs.push(run);
+            }
+        }
+
+        runs
+    }
+
+    /// 后台定期维护数据库中的所有仓库
+    pub async fn run_scheduler(self: Arc<Self>, repository_service: Arc<RepositoryService>) {
+        loop {
+            tokio::time::sleep(SCHEDULER_TICK).await;
+
+            if !self.get_settings().enabled {
+                continue;
+            }
+
+            let repo_paths: Vec<PathBuf> = match repository_service.list_all().await {
+                Ok(repositories) => repositories.into_iter().map(|r| PathBuf::from(r.path)).collect(),
+                Err(e) => {
+                    eprintln!("警告: 获取维护的仓库列表失败: {}", e);
+                    continue;
+                }
+            };
+
+            self.run_due(&repo_paths).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::git_remote::PushOptions;
+    use crate::services::repository_service::AddRepositoryRequest;
+    use crate::services::test_support::init_repo_with_commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        let settings = MaintenanceSettings {
+            enabled: true,
+            interval_seconds: 60,
+            max_backoff_seconds: 300,
+        };
+
+        assert_eq!(MaintenanceService::retry_delay(&settings, 0), Duration::from_secs(60));
+        assert_eq!(MaintenanceService::retry_delay(&settings, 1), Duration::from_secs(120));
+        assert_eq!(MaintenanceService::retry_delay(&settings, 2), Duration::from_secs(240));
+        assert_eq!(MaintenanceService::retry_delay(&settings, 3), Duration::from_secs(300));
+        assert_eq!(MaintenanceService::retry_delay(&settings, 100), Duration::from_secs(300));
+
+        let service = MaintenanceService::new();
+        assert!(service
+            .set_settings(MaintenanceSettings { interval_seconds: 0, ..settings.clone() })
+            .is_err());
+        service.set_settings(settings).unwrap();
+        assert_eq!(service.get_settings().interval_seconds, 60);
+    }
+
+    #[test]
+    fn test_settings_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings_file = temp_dir.path().join("maintenance.json");
+
+        let service = MaintenanceService::with_settings_file(settings_file.clone());
+        assert!(service.get_settings().enabled);
+        service
+            .set_settings(MaintenanceSettings {
+                enabled: false,
+                interval_seconds: 120,
+                max_backoff_seconds: 600,
+            })
+            .unwrap();
+
+        // 重新启动后读回保存的设置
+        let reloaded = MaintenanceService::with_settings_file(settings_file);
+        let settings = reloaded.get_settings();
+        assert!(!settings.enabled);
+        assert_eq!(settings.interval_seconds, 120);
+        assert_eq!(settings.max_backoff_seconds, 600);
+    }
+
+    #[tokio::test]
+    async fn test_run_due_fetches_and_prunes() {
+        let temp_dir = TempDir::new().unwrap();
+        let remote_path = temp_dir.path().join("remote.git");
+        GitService::init_repository(&remote_path, true).unwrap();
+
+        // 推送一个分支后在远程删除它，并留下一个工作目录已删除的worktree
+        let repo_path = temp_dir.path().join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        init_repo_with_commit(&repo_path);
+        let repo = GitService::open_repository(&repo_path).unwrap();
+        repo.remote("origin", remote_path.to_str().unwrap()).unwrap();
+        GitService::create_branch(&repo_path, "stale", None).unwrap();
+        GitService::push(&repo_path, &PushOptions {
+            branch: Some("stale".to_string()),
+            ..Default::default()
+        }, None)
+        .unwrap();
+        let remote = GitService::open_repository(&remote_path).unwrap();
+        remote.find_reference("refs/heads/stale").unwrap().delete().unwrap();
+
+        let worktree_path = temp_dir.path().join("gone");
+        GitService::create_worktree(&repo_path, "gone", &worktree_path, None).unwrap();
+        fs::remove_dir_all(&worktree_path).unwrap();
+
+        RepositoryManagerService::add_repository(AddRepositoryRequest {
+            path: repo_path.clone(),
+            name: None,
+            default_branch: None,
+            auto_fetch: true,
+            auto_prune: true,
+        })
+        .unwrap();
+
+        let service = MaintenanceService::new();
+        let repo_paths = vec![repo_path.clone()];
+        let runs = service.run_due(&repo_paths).await;
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert!(run.is_success(), "{:?}", run.errors);
+        assert_eq!(run.fetched_remotes, vec!["origin".to_string()]);
+        assert_eq!(run.pruned_refs, vec!["refs/remotes/origin/stale".to_string()]);
+        assert_eq!(run.pruned_worktrees, vec!["gone".to_string()]);
+        assert!(repo.find_reference("refs/remotes/origin/stale").is_err());
+
+        // 未到下一次维护时间，不重复执行
+        assert!(service.run_due(&repo_paths).await.is_empty());
+        let status = service.get_status(&repo_path).unwrap();
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.last_success_at.is_some());
+
+        // 远程不可用时记录失败并延长下一次间隔
+        repo.remote_set_url("origin", temp_dir.path().join("missing.git").to_str().unwrap())
+            .unwrap();
+        let run = service.run_now(&repo_path).await.unwrap();
+        assert!(!run.is_success());
+        let status = service.get_status(&repo_path).unwrap();
+        assert_eq!(status.consecutive_failures, 1);
+        let delay = MaintenanceService::retry_delay(&service.get_settings(), 1);
+        assert_eq!(
+            status.next_run_at,
+            run.finished_at + chrono::Duration::from_std(delay).unwrap()
+        );
+
+        // 关闭 auto_fetch 和 auto_prune 后不再维护
+        RepositoryManagerService::update_repository_config(&repo_path, |config| {
+            config.auto_fetch = false;
+            config.auto_prune = false;
+            Ok(())
+        })
+        .unwrap();
+        assert!(service.run_due(&repo_paths).await.is_empty());
+        assert!(service.get_status(&repo_path).is_none());
+    }
+}
\ No newline at end of file
@@ -1,8 +1,22 @@
 pub mod git_service;
+pub mod git_diff;
+pub mod git_remote;
 pub mod repository_service;
 pub mod workspace_service;
 pub mod script_executor;
 pub mod terminal_service;
+pub mod terminal_history;
+pub mod terminal_recording;
+pub mod shell_integration;
+pub mod process_inspector;
+pub mod output_capture;
+pub mod command_history;
+pub mod shutdown;
+pub mod execution_journal;
+pub mod maintenance;
+
+#[cfg(test)]
+pub mod test_support;
 
 pub use git_service::GitService;
 pub use repository_service::RepositoryManagerService;
@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::task::JoinHandle;
+
+/// 进程退出后等待输出读取结束的最长时间，进程的后台子进程可能继续占用管道
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 在后台任务中读取子进程的输出流，已读取的内容保存在共享缓冲区中
+pub struct StreamCapture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    task: JoinHandle<()>,
+}
+
+impl StreamCapture {
+    /// 开始读取输出流
+    pub fn spawn<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        Self::spawn_with(reader, |_| {})
+    }
+
+    /// 开始读取输出流，每读取一段内容调用一次 `on_chunk`
+    pub fn spawn_with<R, F>(mut reader: R, mut on_chunk: F) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let task_buffer = buffer.clone();
+        let task = tokio::spawn(async move {
+            let mut chunk = [0u8; 8192];
+            loop {
+                let read = match reader.read(&mut chunk).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+                task_buffer.lock().unwrap().extend_from_slice(&chunk[..read]);
+                on_chunk(&chunk[..read]);
+            }
+        });
+
+        Self { buffer, task }
+    }
+
+    /// 等待读取结束并返回输出，超时后停止读取并返回已读取的部分
+    pub async fn finish(self) -> String {
+        let StreamCapture { buffer, mut task } = self;
+        if tokio::time::timeout(DRAIN_TIMEOUT, &mut task).await.is_err() {
+            task.abort();
+        }
+
+        let buffer = buffer.lock().unwrap();
+        String::from_utf8_lossy(&buffer).to_string()
+    }
+}
+
+/// 结束可能不存在的输出流读取
+pub async fn collect(capture: Option<StreamCapture>) -> String {
+    match capture {
+        Some(capture) => capture.finish().await,
+        None => String::new(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    #[tokio::test]
+    async fn test_partial_output_kept_when_pipe_stays_open() {
+        // 后台进程继承了stdout，sh退出后管道仍未关闭
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("echo before; sleep 5 & echo after")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let capture = StreamCapture::spawn(child.stdout.take().unwrap());
+        child.wait().await.unwrap();
+
+        let started = std::time::Instant::now();
+        assert_eq!(capture.finish().await, "before\nafter\n");
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+/// 进程树中的单个进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    /// 完整命令行，内核线程等没有命令行时为进程名
+    pub command_line: String,
+    /// 累计CPU时间（用户态+内核态）
+    pub cpu_time_ms: u64,
+    /// 进程生命周期内的平均CPU占用百分比
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub children: Vec<ProcessInfo>,
+}
+
+/// 进程监听的TCP端口
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ListeningPort {
+    pub pid: u32,
+    /// "tcp" 或 "tcp6"
+    pub protocol: String,
+    pub address: String,
+    pub port: u16,
+}
+
+/// 以某个进程为根的进程树及其监听端口
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessTree {
+    pub root: ProcessInfo,
+    pub ports: Vec<ListeningPort>,
+}
+
+impl ProcessTree {
+    /// 树中所有进程的PID（先序遍历）
+    pub fn pids(&self) -> Vec<u32> {
+        let mut pids = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(process) = stack.pop() {
+            pids.push(process.pid);
+            stack.extend(process.children.iter().rev());
+        }
+        pids
+    }
+}
+
+/// 从 /proc/<pid>/stat 解析出的字段
+struct ProcStat {
+    pid: u32,
+    ppid: u32,
+    pgrp: u32,
+    name: String,
+    cpu_ticks: u64,
+    start_ticks: u64,
+    rss_pages: u64,
+}
+
+fn parse_stat(pid: u32, stat: &str) -> Option<ProcStat> {
+    // 进程名可能包含空格和括号，取第一个 '(' 与最后一个 ')' 之间的内容
+    let name_start = stat.find('(')?;
+    let name_end = stat.rfind(')')?;
+    let name = stat[name_start + 1..name_end].to_string();
+
+    // ')' 之后从第3个字段（state）开始
+    let fields: Vec<&str> = stat[name_end + 1..].split_whitespace().collect();
+    let field = |index: usize| -> Option<u64> { fields.get(index - 3)?.parse().ok() };
+
+    Some(ProcStat {
+        pid,
+        ppid: field(4)? as u32,
+        pgrp: field(5)? as u32,
+        name,
+        cpu_ticks: field(14)? + field(15)?,
+        start_ticks: field(22)?,
+        rss_pages: field(24)?,
+    })
+}
+
+/// 读取所有进程的stat信息
+fn read_process_table() -> Result<HashMap<u32, ProcStat>, String> {
+    let entries = fs::read_dir("/proc")
+        .map_err(|e| format!("Process inspection is not supported on this platform: {}", e))?;
+
+    let mut table = HashMap::new();
+    for entry in entries.flatten() {
+        let pid = match entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        // 进程可能在遍历过程中退出
+        if let Some(stat) = fs::read_to_string(entry.path().join("stat"))
+            .ok()
+            .and_then(|stat| parse_stat(pid, &stat))
+        {
+            table.insert(pid, stat);
+        }
+    }
+
+    Ok(table)
+}
+
+fn children_by_parent(table: &HashMap<u32, ProcStat>) -> HashMap<u32, Vec<u32>> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for stat in table.values() {
+        children.entry(stat.ppid).or_default().push(stat.pid);
+    }
+    for pids in children.values_mut() {
+        pids.sort_unstable();
+    }
+    children
+}
+
+/// 查找指定进程的所有子孙进程
+pub fn descendant_pids(root_pid: u32) -> Vec<u32> {
+    let table = match read_process_table() {
+        Ok(table) => table,
+        Err(_) => return Vec::new(),
+    };
+    let children = children_by_parent(&table);
+
+    let mut descendants = Vec::new();
+    let mut queue = vec![root_pid];
+    while let Some(parent) = queue.pop() {
+        for pid in children.get(&parent).into_iter().flatten() {
+            if !descendants.contains(pid) {
+                descendants.push(*pid);
+                queue.push(*pid);
+            }
+        }
+    }
+
+    descendants
+}
+
+/// 进程组中是否有组长以外的进程，例如shell启动的前台或后台命令
+#[cfg(target_os = "linux")]
+pub fn group_has_other_members(pgid: u32) -> bool {
+    read_process_table()
+        .map(|table| table.values().any(|stat| stat.pgrp == pgid && stat.pid != pgid))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+pub fn group_has_other_members(pgid: u32) -> bool {
+    // libproc.h 中的 PROC_PGRP_ONLY
+    const PROC_PGRP_ONLY: u32 = 2;
+    let mut pids = [0 as libc::c_int; 64];
+    let bytes = unsafe {
+        libc::proc_listpids(
+            PROC_PGRP_ONLY,
+            pgid,
+            pids.as_mut_ptr().cast(),
+            std::mem::size_of_val(&pids) as libc::c_int,
+        )
+    };
+    if bytes <= 0 {
+        return false;
+    }
+    let count = (bytes as usize / std::mem::size_of::<libc::c_int>()).min(pids.len());
+    pids[..count].iter().any(|pid| *pid > 0 && *pid as u32 != pgid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn group_has_other_members(_pgid: u32) -> bool {
+    false
+}
+
+/// 向进程及其所有子孙进程发送信号（先子孙后根进程），返回成功发送的进程数
+#[cfg(unix)]
+pub fn signal_tree(root_pid: u32, signal: i32) -> usize {
+    let mut targets = descendant_pids(root_pid);
+    targets.reverse();
+    targets.push(root_pid);
+
+    targets
+        .into_iter()
+        .filter(|pid| unsafe { libc::kill(*pid as libc::pid_t, signal) } == 0)
+        .count()
+}
+
+#[cfg(not(unix))]
+pub fn signal_tree(_root_pid: u32, _signal: i32) -> usize {
+    0
+}
+
+/// 让子进程成为新进程组的组长，之后可以通过 signal_group 结束它启动的所有进程
+pub fn spawn_in_new_group(cmd: &mut tokio::process::Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// 向进程组发送信号，返回是否发送成功
+#[cfg(unix)]
+pub fn signal_group(pgid: u32, signal: i32) -> bool {
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn signal_group(_pgid: u32, _signal: i32) -> bool {
+    false
+}
+
+fn clock_ticks_per_second() -> u64 {
+    #[cfg(unix)]
+    {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as u64;
+        }
+    }
+    100
+}
+
+fn page_size() -> u64 {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as u64;
+        }
+    }
+    4096
+}
+
+fn system_uptime_seconds() -> f64 {
+    fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|uptime| uptime.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn read_command_line(pid: u32) -> Option<String> {
+    let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let command_line = raw
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if command_line.is_empty() {
+        None
+    } else {
+        Some(command_line)
+    }
+}
+
+fn build_process(
+    pid: u32,
+    table: &HashMap<u32, ProcStat>,
+    children: &HashMap<u32, Vec<u32>>,
+    visited: &mut HashSet<u32>,
+) -> Option<ProcessInfo> {
+    if !visited.insert(pid) {
+        return None;
+    }
+    let stat = table.get(&pid)?;
+
+    let ticks = clock_ticks_per_second() as f64;
+    let cpu_seconds = stat.cpu_ticks as f64 / ticks;
+    let running_seconds = system_uptime_seconds() - stat.start_ticks as f64 / ticks;
+    let cpu_percent = if running_seconds > 0.0 {
+        cpu_seconds / running_seconds * 100.0
+    } else {
+        0.0
+    };
+
+    let child_processes = children
+        .get(&pid)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| build_process(*child, table, children, visited))
+        .collect();
+
+    Some(ProcessInfo {
+        pid,
+        ppid: stat.ppid,
+        name: stat.name.clone(),
+        command_line: read_command_line(pid).unwrap_or_else(|| stat.name.clone()),
+        cpu_time_ms: (cpu_seconds * 1000.0) as u64,
+        cpu_percent,
+        rss_bytes: stat.rss_pages * page_size(),
+        children: child_processes,
+    })
+}
+
+/// 获取进程打开的socket inode
+fn socket_inodes(pid: u32) -> Vec<u64> {
+    let entries = match fs::read_dir(format!("/proc/{}/fd", pid)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| fs::read_link(entry.path()).ok())
+        .filter_map(|target| {
+            let target = target.to_string_lossy().to_string();
+            target
+                .strip_prefix("socket:[")?
+                .strip_suffix(']')?
+                .parse()
+                .ok()
+        })
+        .collect()
+}
+
+/// 解析 /proc/net/tcp 中的十六进制地址（IPv4 按小端存储）
+fn parse_ipv4(hex: &str) -> Option<String> {
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Ipv4Addr::from(value.to_le_bytes()).to_string())
+}
+
+/// 解析 /proc/net/tcp6 中的十六进制地址（每4字节一组，组内小端）
+fn parse_ipv6(hex: &str) -> Option<String> {
+    if hex.len() != 32 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 16];
+    for group in 0..4 {
+        let value = u32::from_str_radix(&hex[group * 8..group * 8 + 8], 16).ok()?;
+        bytes[group * 4..group * 4 + 4].copy_from_slice(&value.to_le_bytes());
+    }
+    Some(Ipv6Addr::from(bytes).to_string())
+}
+
+/// 读取处于 LISTEN 状态的socket，返回 inode 到 (地址, 端口) 的映射
+fn listening_sockets(path: &Path, ipv6: bool) -> HashMap<u64, (String, u16)> {
+    const TCP_LISTEN: &str = "0A";
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return HashMap::new(),
+    };
+
+    let mut sockets = HashMap::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || fields[3] != TCP_LISTEN {
+            continue;
+        }
+
+        let (address, port) = match fields[1].split_once(':') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let address = if ipv6 { parse_ipv6(address) } else { parse_ipv4(address) };
+        let port = u16::from_str_radix(port, 16).ok();
+        let inode = fields[9].parse::<u64>().ok();
+
+        if let (Some(address), Some(port), Some(inode)) = (address, port, inode) {
+            sockets.insert(inode, (address, port));
+        }
+    }
+
+    sockets
+}
+
+/// 获取指定进程集合监听的TCP端口
+pub fn listening_ports(pids: &[u32]) -> Vec<ListeningPort> {
+    let tcp = listening_sockets(Path::new("/proc/net/tcp"), false);
+    let tcp6 = listening_sockets(Path::new("/proc/net/tcp6"), true);
+
+    let mut ports = Vec::new();
+    for pid in pids {
+        for inode in socket_inodes(*pid) {
+            let (protocol, socket) = match (tcp.get(&inode), tcp6.get(&inode)) {
+                (Some(socket), _) => ("tcp", socket),
+                (None, Some(socket)) => ("tcp6", socket),
+                (None, None) => continue,
+            };
+
+            let port = ListeningPort {
+                pid: *pid,
+                protocol: protocol.to_string(),
+                address: socket.0.clone(),
+                port: socket.1,
+            };
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+    }
+
+    ports.sort_by_key(|p| (p.port, p.pid));
+    ports
+}
+
+/// 查找环境变量中包含 `key=value` 的进程（不含当前进程），子进程会继承该变量，
+/// 因此可以找到父进程已退出、被重新挂到init下的遗留进程
+#[cfg(target_os = "linux")]
+pub fn processes_with_env(key: &str, value: &str) -> Result<Vec<ProcessInfo>, String> {
+    let table = read_process_table()?;
+    let needle = format!("{}={}", key, value);
+    let own_pid = std::process::id();
+    let no_children = HashMap::new();
+
+    let mut pids: Vec<u32> = table
+        .keys()
+        .copied()
+        .filter(|pid| *pid != own_pid)
+        .filter(|pid| {
+            fs::read(format!("/proc/{}/environ", pid))
+                .map(|environ| environ.split(|b| *b == 0).any(|var| var == needle.as_bytes()))
+                .unwrap_or(false)
+        })
+        .collect();
+    pids.sort_unstable();
+
+    Ok(pids
+        .into_iter()
+        .filter_map(|pid| build_process(pid, &table, &no_children, &mut HashSet::new()))
+        .collect())
+}
+
+/// 其他平台无法读取进程的环境变量
+#[cfg(not(target_os = "linux"))]
+pub fn processes_with_env(_key: &str, _value: &str) -> Result<Vec<ProcessInfo>, String> {
+    Err("Finding processes by environment variable is not supported on this platform".to_string())
+}
+
+/// 获取以 `root_pid` 为根的进程树及其监听的端口
+pub fn process_tree(root_pid: u32) -> Result<ProcessTree, String> {
+    let table = read_process_table()?;
+    let children = children_by_parent(&table);
+
+    let root = build_process(root_pid, &table, &children, &mut HashSet::new())
+        .ok_or_else(|| format!("Process {} not found", root_pid))?;
+
+    let tree = ProcessTree {
+        root,
+        ports: Vec::new(),
+    };
+    let ports = listening_ports(&tree.pids());
+
+    Ok(ProcessTree { ports, ..tree })
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::process::Command;
+
+    #[test]
+    fn test_parse_addresses() {
+        assert_eq!(parse_ipv4("0100007F").as_deref(), Some("127.0.0.1"));
+        assert_eq!(parse_ipv6("00000000000000000000000001000000").as_deref(), Some("::1"));
+        assert_eq!(parse_ipv6("0000000000000000FFFF00000100007F").as_deref(), Some("::ffff:127.0.0.1"));
+    }
+
+    #[test]
+    fn test_process_tree_and_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+
+        let tree = process_tree(std::process::id()).unwrap();
+        assert_eq!(tree.root.pid, std::process::id());
+        assert!(tree.root.rss_bytes > 0);
+
+        let sleeper = tree.root.children.iter().find(|p| p.pid == child.id()).unwrap();
+        assert_eq!(sleeper.command_line, "sleep 5");
+        assert!(descendant_pids(std::process::id()).contains(&child.id()));
+
+        assert!(tree
+            .ports
+            .iter()
+            .any(|p| p.port == port && p.address == "127.0.0.1" && p.protocol == "tcp"));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_group_has_other_members() {
+        use std::os::unix::process::CommandExt;
+
+        let mut leader = Command::new("sleep").arg("5").process_group(0).spawn().unwrap();
+        let pgid = leader.id();
+        assert!(!group_has_other_members(pgid));
+
+        let mut member = Command::new("sleep").arg("5").process_group(pgid as i32).spawn().unwrap();
+        assert!(group_has_other_members(pgid));
+
+        assert!(signal_group(pgid, libc::SIGKILL));
+        leader.wait().unwrap();
+        member.wait().unwrap();
+    }
+}
\ No newline at end of file
@@ -1,11 +1,17 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 use std::sync::{Arc, Mutex};
-use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+use crate::services::execution_journal::{
+    ExecutionJournal, InterruptedExecution, JournalRecord, LogStream, OutputLog, EXECUTION_ID_ENV,
+};
+use crate::services::output_capture::{self, StreamCapture};
+use crate::services::process_inspector::{self, ProcessTree};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptExecution {
@@ -19,6 +25,9 @@ pub struct ScriptExecution {
     pub exit_code: Option<i32>,
     pub stdout: String,
     pub stderr: String,
+    /// 脚本进程的PID，仅在运行期间存在
+    #[serde(default)]
+    pub pid: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +37,8 @@ pub enum ExecutionStatus {
     Completed,
     Failed,
     Cancelled,
+    /// 应用异常退出时仍在运行
+    Interrupted,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,11 +51,23 @@ pub struct ScriptExecutionResult {
     pub duration_ms: u64,
 }
 
+/// 停止所有脚本的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptShutdownReport {
+    /// 收到 SIGTERM 后在超时前退出的脚本数量
+    pub stopped: usize,
+    /// 超时后被强制结束的脚本数量
+    pub killed: usize,
+}
+
 #[derive(Debug)]
 pub struct ScriptExecutor {
     executions: Arc<Mutex<HashMap<String, ScriptExecution>>>,
     max_concurrent_executions: usize,
     running_count: Arc<Mutex<usize>>,
+    /// 正在运行的脚本的结束通知
+    kill_senders: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+    journal: Arc<ExecutionJournal>,
 }
 
 impl Default for ScriptExecutor {
@@ -59,9 +82,56 @@ impl ScriptExecutor {
             executions: Arc::new(Mutex::new(HashMap::new())),
             max_concurrent_executions: 5, // 限制最大并发执行数
             running_count: Arc::new(Mutex::new(0)),
+            kill_senders: Arc::new(Mutex::new(HashMap::new())),
+            journal: Arc::new(ExecutionJournal::in_memory()),
+        }
+    }
+
+    /// 使用指定的执行日志，运行中的脚本会写入日志以便崩溃后恢复
+    pub fn with_journal(self, journal: Arc<ExecutionJournal>) -> Self {
+        Self { journal, ..self }
+    }
+
+    /// 将上次运行中断的脚本加入执行列表，保留部分输出
+    pub fn restore_interrupted(&self, interrupted: &[InterruptedExecution]) {
+        let mut executions = self.executions.lock().unwrap();
+        for entry in interrupted {
+            if let JournalRecord::Script(ref execution) = entry.record {
+                executions
+                    .entry(execution.id.clone())
+                    .or_insert_with(|| execution.clone());
+            }
         }
     }
 
+    /// 重新运行中断的脚本：创建新的执行并在后台运行，返回新的执行ID。
+    /// 调用前应先结束中断执行的遗留进程
+    pub async fn rerun_interrupted(self: Arc<Self>, interrupted: &InterruptedExecution) -> Result<String, String> {
+        let execution = match interrupted.record {
+            JournalRecord::Script(ref execution) => execution,
+            _ => return Err("Interrupted execution is not a script".to_string()),
+        };
+
+        let execution_id = self
+            .create_execution(
+                execution.script_content.clone(),
+                execution.working_directory.clone(),
+                Some(execution.environment.clone()),
+            )
+            .await?;
+        self.journal.dismiss(&interrupted.id);
+
+        let executor = Arc::clone(&self);
+        let id = execution_id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = executor.execute_script(id).await {
+                eprintln!("警告: 重新运行中断的脚本失败: {}", e);
+            }
+        });
+
+        Ok(execution_id)
+    }
+
     /// 生成唯一的执行ID
     fn generate_execution_id() -> String {
         let timestamp = SystemTime::now()
@@ -153,6 +223,7 @@ impl ScriptExecutor {
             exit_code: None,
             stdout: String::new(),
             stderr: String::new(),
+            pid: None,
         };
 
         {
@@ -199,9 +270,16 @@ impl ScriptExecutor {
                 .as_millis() as u64,
         );
 
+        let cancelled = matches!(
+            self.get_execution_status(&execution_id).map(|e| e.status),
+            Some(ExecutionStatus::Cancelled)
+        );
+
         match &result {
             Ok(exec_result) => {
-                execution.status = if exec_result.success {
+                execution.status = if cancelled {
+                    ExecutionStatus::Cancelled
+                } else if exec_result.success {
                     ExecutionStatus::Completed
                 } else {
                     ExecutionStatus::Failed
@@ -253,16 +331,68 @@ impl ScriptExecutor {
         let mut cmd = Command::new("sh");
         cmd.arg(&script_file)
             .current_dir(&execution.working_directory)
+            .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // 脚本作为进程组组长，取消和关闭时通过进程组结束它启动的所有进程
+        process_inspector::spawn_in_new_group(&mut cmd);
 
         // 设置环境变量
         for (key, value) in &execution.environment {
             cmd.env(key, value);
         }
+        cmd.env(EXECUTION_ID_ENV, &execution.id);
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = std::fs::remove_file(&script_file);
+                return Err(format!("Failed to execute script: {}", e));
+            }
+        };
+
+        // 记录PID和结束通知，以便查看进程树和取消/关闭时结束脚本
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        self.kill_senders.lock().unwrap().insert(execution.id.clone(), kill_tx);
+        if let Some(running) = self.executions.lock().unwrap().get_mut(&execution.id) {
+            running.pid = child.id();
+        }
 
-        let output = cmd.output()
-            .map_err(|e| format!("Failed to execute script: {}", e))?;
+        // 写入执行日志，应用崩溃后可恢复状态和部分输出
+        let mut journal_record = execution.clone();
+        journal_record.pid = child.id();
+        self.journal.begin(JournalRecord::Script(journal_record), child.id());
+
+        // 并行读取两个输出流，防止管道写满导致进程阻塞
+        let stdout_log = self.journal.output_log(&execution.id, LogStream::Stdout);
+        let stderr_log = self.journal.output_log(&execution.id, LogStream::Stderr);
+        let stdout_capture = child.stdout.take().map(|stdout| Self::capture_stream(stdout, stdout_log));
+        let stderr_capture = child.stderr.take().map(|stderr| Self::capture_stream(stderr, stderr_log));
+
+        let status = tokio::select! {
+            status = child.wait() => status,
+            _ = kill_rx => {
+                if let Some(pid) = child.id() {
+                    process_inspector::signal_group(pid, Self::KILL_SIGNAL);
+                }
+                let _ = child.kill().await;
+                child.wait().await
+            }
+        };
+        self.kill_senders.lock().unwrap().remove(&execution.id);
+
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                let _ = std::fs::remove_file(&script_file);
+                self.journal.finish(&execution.id);
+                return Err(format!("Failed to wait for script: {}", e));
+            }
+        };
+        let stdout = output_capture::collect(stdout_capture).await;
+        let stderr = output_capture::collect(stderr_capture).await;
+        self.journal.finish(&execution.id);
 
         // 清理临时脚本文件
         let _ = std::fs::remove_file(&script_file);
@@ -271,10 +401,10 @@ impl ScriptExecutor {
         
         let result = ScriptExecutionResult {
             id: execution.id.clone(),
-            success: output.status.success(),
-            exit_code: output.status.code(),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            success: status.success(),
+            exit_code: status.code(),
+            stdout,
+            stderr,
             duration_ms,
         };
 
@@ -295,6 +425,11 @@ impl ScriptExecutor {
                             .unwrap()
                             .as_millis() as u64,
                     );
+
+                    // 结束正在运行的脚本进程
+                    if let Some(kill_sender) = self.kill_senders.lock().unwrap().remove(execution_id) {
+                        let _ = kill_sender.send(());
+                    }
                     Ok(())
                 }
                 _ => Err("Cannot cancel execution in current status".to_string()),
@@ -304,6 +439,98 @@ impl ScriptExecutor {
         }
     }
 
+    #[cfg(unix)]
+    const TERM_SIGNAL: i32 = libc::SIGTERM;
+    #[cfg(unix)]
+    const KILL_SIGNAL: i32 = libc::SIGKILL;
+    #[cfg(not(unix))]
+    const TERM_SIGNAL: i32 = 15;
+    #[cfg(not(unix))]
+    const KILL_SIGNAL: i32 = 9;
+
+    /// 读取输出流，同时写入输出日志
+    fn capture_stream<R>(reader: R, mut log: Option<OutputLog>) -> StreamCapture
+    where
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        StreamCapture::spawn_with(reader, move |chunk| {
+            // 写入失败时停止记录，不影响脚本本身
+            let failed = log
+                .as_mut()
+                .map(|log| log.write(chunk).and_then(|_| log.flush()).is_err())
+                .unwrap_or(false);
+            if failed {
+                log = None;
+            }
+        })
+    }
+
+    /// 正在运行的脚本ID
+    pub fn running_executions(&self) -> Vec<String> {
+        self.kill_senders.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// 停止所有正在运行的脚本：先向脚本的进程组发送 SIGTERM，超时后强制结束
+    pub async fn shutdown(&self, timeout: Duration) -> ScriptShutdownReport {
+        let running: Vec<(String, Option<u32>)> = {
+            let executions = self.executions.lock().unwrap();
+            self.running_executions()
+                .into_iter()
+                .map(|id| {
+                    let pid = executions.get(&id).and_then(|e| e.pid);
+                    (id, pid)
+                })
+                .collect()
+        };
+
+        let mut report = ScriptShutdownReport::default();
+        for (_, pid) in &running {
+            if let Some(pid) = pid {
+                process_inspector::signal_group(*pid, Self::TERM_SIGNAL);
+            }
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !self.kill_senders.lock().unwrap().is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        // 超时仍未退出的脚本强制结束
+        let remaining: Vec<oneshot::Sender<()>> = self
+            .kill_senders
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, sender)| sender)
+            .collect();
+        report.killed = remaining.len();
+        report.stopped = running.len().saturating_sub(remaining.len());
+        for sender in remaining {
+            let _ = sender.send(());
+        }
+
+        // 主动停止的脚本不算中断，应用可能在执行任务收尾前退出，这里直接移除日志
+        for (execution_id, _) in &running {
+            self.journal.finish(execution_id);
+        }
+
+        report
+    }
+
+    /// 获取正在运行的脚本的进程树
+    pub fn get_process_tree(&self, execution_id: &str) -> Result<Option<ProcessTree>, String> {
+        let pid = {
+            let executions = self.executions.lock().unwrap();
+            let execution = executions.get(execution_id).ok_or("Execution not found")?;
+            execution.pid
+        };
+
+        match pid {
+            Some(pid) => process_inspector::process_tree(pid).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// 获取执行状态
     pub fn get_execution_status(&self, execution_id: &str) -> Option<ScriptExecution> {
         let executions = self.executions.lock().unwrap();
@@ -322,7 +549,7 @@ impl ScriptExecutor {
         
         let mut completed_execution_ids: Vec<String> = executions
             .values()
-            .filter(|exec| matches!(exec.status, ExecutionStatus::Completed | ExecutionStatus::Failed | ExecutionStatus::Cancelled))
+            .filter(|exec| matches!(exec.status, ExecutionStatus::Completed | ExecutionStatus::Failed | ExecutionStatus::Cancelled | ExecutionStatus::Interrupted))
             .map(|exec| exec.id.clone())
             .collect();
 
@@ -393,4 +620,103 @@ mod tests {
         assert!(result.success);
         assert!(result.stdout.contains("test_value"));
     }
+
+    #[tokio::test]
+    async fn test_cancel_running_script() {
+        let executor = Arc::new(ScriptExecutor::new());
+        let execution_id = executor
+            .create_execution("sleep 30".to_string(), env::temp_dir(), None)
+            .await
+            .unwrap();
+
+        let running = executor.clone();
+        let id = execution_id.clone();
+        let handle = tokio::spawn(async move { running.execute_script(id).await });
+
+        for _ in 0..50 {
+            if !executor.running_executions().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(executor.get_process_tree(&execution_id).unwrap().is_some());
+
+        executor.cancel_execution(&execution_id).await.unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert!(!result.success);
+        let execution = executor.get_execution_status(&execution_id).unwrap();
+        assert!(matches!(execution.status, ExecutionStatus::Cancelled));
+        assert!(execution.pid.is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_cancel_kills_background_children() {
+        let executor = Arc::new(ScriptExecutor::new());
+        let execution_id = executor
+            .create_execution("sleep 30 & sleep 30".to_string(), env::temp_dir(), None)
+            .await
+            .unwrap();
+
+        let running = executor.clone();
+        let id = execution_id.clone();
+        let handle = tokio::spawn(async move { running.execute_script(id).await });
+        for _ in 0..50 {
+            if process_inspector::processes_with_env(EXECUTION_ID_ENV, &execution_id).unwrap().len() >= 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        executor.cancel_execution(&execution_id).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap().unwrap();
+
+        // 后台的 sleep 与脚本在同一进程组中，随脚本一起结束
+        let mut leftover = process_inspector::processes_with_env(EXECUTION_ID_ENV, &execution_id).unwrap();
+        for _ in 0..50 {
+            if leftover.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            leftover = process_inspector::processes_with_env(EXECUTION_ID_ENV, &execution_id).unwrap();
+        }
+        assert!(leftover.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rerun_interrupted_script() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let journal = Arc::new(ExecutionJournal::new(temp_dir.path().to_path_buf()));
+        let executor = ScriptExecutor::new().with_journal(journal.clone());
+        let execution_id = executor
+            .create_execution("echo rerun-output".to_string(), env::temp_dir(), None)
+            .await
+            .unwrap();
+        let execution = executor.get_execution_status(&execution_id).unwrap();
+        journal.begin(JournalRecord::Script(execution), None);
+        drop(executor);
+
+        // 模拟应用崩溃后重新启动
+        let journal = Arc::new(ExecutionJournal::new(temp_dir.path().to_path_buf()));
+        let interrupted = journal.get_interrupted(&execution_id).unwrap();
+        let executor = Arc::new(ScriptExecutor::new().with_journal(journal.clone()));
+        let new_id = executor.clone().rerun_interrupted(&interrupted).await.unwrap();
+        assert!(journal.get_interrupted(&execution_id).is_none());
+
+        let mut execution = executor.get_execution_status(&new_id).unwrap();
+        for _ in 0..100 {
+            if matches!(execution.status, ExecutionStatus::Completed) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            execution = executor.get_execution_status(&new_id).unwrap();
+        }
+        assert!(matches!(execution.status, ExecutionStatus::Completed));
+        assert!(execution.stdout.contains("rerun-output"));
+    }
 }
\ No newline at end of file
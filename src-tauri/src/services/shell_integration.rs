@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+/// 保留的已完成命令数量上限
+const MAX_COMPLETED_COMMANDS: usize = 500;
+
+/// 支持注入提示符钩子的shell类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Posix,
+}
+
+impl ShellKind {
+    /// 根据shell程序名判断类型，不支持的shell返回None
+    pub fn from_program(program: &str) -> Option<Self> {
+        let name = std::path::Path::new(program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(program)
+            .trim_start_matches('-');
+
+        match name {
+            "bash" => Some(ShellKind::Bash),
+            "zsh" => Some(ShellKind::Zsh),
+            "sh" | "dash" | "ash" | "ksh" | "mksh" => Some(ShellKind::Posix),
+            _ => None,
+        }
+    }
+}
+
+/// 提示符钩子函数名，非交互shell没有提示符，由服务附加在每条命令之后调用
+pub const PROMPT_HOOK: &str = "__workhorse_prompt";
+
+/// 生成注入shell的初始化脚本
+///
+/// 钩子在每条命令结束后输出 OSC 133;D（带退出码）、OSC 7（当前目录）和 OSC 133;A（提示符开始）。
+pub fn init_script(kind: ShellKind, interactive: bool) -> String {
+    let mut script = format!(
+        "{hook}() {{ __wh_status=$?; printf '\\033]133;D;%s\\007\\033]7;file://%s\\007\\033]133;A\\007\\n' \"$__wh_status\" \"$PWD\"; return $__wh_status; }}\n",
+        hook = PROMPT_HOOK
+    );
+
+    if interactive {
+        match kind {
+            ShellKind::Bash => script.push_str(&format!(
+                "PROMPT_COMMAND=\"{}${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}\"\n",
+                PROMPT_HOOK
+            )),
+            ShellKind::Zsh => script.push_str(&format!("precmd_functions+=({})\n", PROMPT_HOOK)),
+            ShellKind::Posix => {}
+        }
+    }
+
+    script
+}
+
+/// 钩子是否由shell在提示符前自动调用
+pub fn hook_runs_automatically(kind: ShellKind, interactive: bool) -> bool {
+    interactive && matches!(kind, ShellKind::Bash | ShellKind::Zsh)
+}
+
+/// 生成写入shell的命令行
+///
+/// 需要附加钩子时（钩子不会由shell自动调用），把命令包在命令组里并在同一输入中调用钩子。shell会先解析完整个命令组再执行，
+/// 因此读取stdin的前台程序不会读到钩子调用。命令组也兼容以 `&`、`;` 结尾或多行的命令。
+pub fn command_line(command: &str, append_hook: bool) -> String {
+    if append_hook {
+        format!("{{ {}\n}}; {}\n", command, PROMPT_HOOK)
+    } else {
+        format!("{}\n", command)
+    }
+}
+
+/// 从输出中解析出的shell集成标记
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellMarker {
+    PromptStart,
+    CommandStart,
+    CommandExecuted,
+    CommandFinished(Option<i32>),
+    WorkingDirectory(PathBuf),
+}
+
+/// 从一行输出中提取 OSC 133 / OSC 7 标记，返回去除标记后的文本
+pub fn extract_markers(line: &str) -> (String, Vec<ShellMarker>) {
+    let mut text = String::with_capacity(line.len());
+    let mut markers = Vec::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("\u{1b}]") {
+        text.push_str(&rest[..start]);
+        let body_start = start + 2;
+
+        // OSC 以 BEL 或 ST(ESC \) 结束
+        let (body_end, next) = match (rest[body_start..].find('\u{07}'), rest[body_start..].find("\u{1b}\\")) {
+            (Some(bel), Some(st)) if st < bel => (body_start + st, body_start + st + 2),
+            (Some(bel), _) => (body_start + bel, body_start + bel + 1),
+            (None, Some(st)) => (body_start + st, body_start + st + 2),
+            (None, None) => {
+                // 未结束的序列原样保留
+                text.push_str(&rest[start..]);
+                rest = "";
+                break;
+            }
+        };
+
+        let body = &rest[body_start..body_end];
+        match parse_marker(body) {
+            Some(marker) => markers.push(marker),
+            None => text.push_str(&rest[start..next]),
+        }
+        rest = &rest[next..];
+    }
+    text.push_str(rest);
+
+    (text, markers)
+}
+
+fn parse_marker(body: &str) -> Option<ShellMarker> {
+    if let Some(params) = body.strip_prefix("133;") {
+        let mut parts = params.split(';');
+        return match parts.next()? {
+            "A" => Some(ShellMarker::PromptStart),
+            "B" => Some(ShellMarker::CommandStart),
+            "C" => Some(ShellMarker::CommandExecuted),
+            "D" => Some(ShellMarker::CommandFinished(
+                parts.next().and_then(|code| code.parse().ok()),
+            )),
+            _ => None,
+        };
+    }
+
+    if let Some(url) = body.strip_prefix("7;") {
+        let path = url.strip_prefix("file://")?;
+        // 跳过主机名部分
+        let path = &path[path.find('/')?..];
+        return Some(ShellMarker::WorkingDirectory(PathBuf::from(path)));
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum CommandStatus {
+    Running,
+    Completed,
+    /// 终端在命令结束前关闭或退出
+    Interrupted,
+}
+
+/// 一条命令及其输出在历史记录中的范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalCommand {
+    pub id: u64,
+    pub command: String,
+    pub cwd: Option<PathBuf>,
+    pub status: CommandStatus,
+    pub exit_code: Option<i32>,
+    pub start_time: u64,
+    pub end_time: Option<u64>,
+    /// 命令输入记录的序列号
+    pub start_seq: u64,
+    /// 命令结束时的最后一条记录序列号
+    pub end_seq: Option<u64>,
+}
+
+/// 根据shell集成标记把终端历史分割为命令块
+#[derive(Debug, Default)]
+pub struct CommandTracker {
+    next_id: u64,
+    cwd: Option<PathBuf>,
+    running: VecDeque<TerminalCommand>,
+    completed: VecDeque<TerminalCommand>,
+}
+
+impl CommandTracker {
+    pub fn new(cwd: PathBuf) -> Self {
+        Self {
+            next_id: 1,
+            cwd: Some(cwd),
+            running: VecDeque::new(),
+            completed: VecDeque::new(),
+        }
+    }
+
+    /// 记录开始执行的命令
+    pub fn begin(&mut self, command: &str, seq: u64, timestamp: u64) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.running.push_back(TerminalCommand {
+            id,
+            command: command.to_string(),
+            cwd: self.cwd.clone(),
+            status: CommandStatus::Running,
+            exit_code: None,
+            start_time: timestamp,
+            end_time: None,
+            start_seq: seq,
+            end_seq: None,
+        });
+    }
+
+    /// 处理一个标记，`last_seq` 为标记出现前历史中的最后一条记录
+    ///
+    /// 标记结束了一条命令时返回该命令。
+    pub fn apply(&mut self, marker: ShellMarker, last_seq: Option<u64>, timestamp: u64) -> Option<TerminalCommand> {
+        match marker {
+            ShellMarker::CommandFinished(exit_code) => {
+                // 没有正在运行的命令时（例如交互shell的首个提示符）忽略
+                let mut command = self.running.pop_front()?;
+                command.status = CommandStatus::Completed;
+                command.exit_code = exit_code;
+                command.end_time = Some(timestamp);
+                command.end_seq = last_seq.filter(|seq| *seq >= command.start_seq);
+                self.push_completed(command.clone());
+                Some(command)
+            }
+            ShellMarker::WorkingDirectory(path) => {
+                self.cwd = Some(path);
+                None
+            }
+            ShellMarker::PromptStart | ShellMarker::CommandStart | ShellMarker::CommandExecuted => None,
+        }
+    }
+
+    /// 终端退出时将仍在运行的命令标记为中断
+    pub fn interrupt_all(&mut self, last_seq: Option<u64>, timestamp: u64) {
+        while let Some(mut command) = self.running.pop_front() {
+            command.status = CommandStatus::Interrupted;
+            command.end_time = Some(timestamp);
+            command.end_seq = last_seq;
+            self.push_completed(command);
+        }
+    }
+
+    fn push_completed(&mut self, command: TerminalCommand) {
+        self.completed.push_back(command);
+        while self.completed.len() > MAX_COMPLETED_COMMANDS {
+            self.completed.pop_front();
+        }
+    }
+
+    /// 按执行顺序返回所有命令块
+    pub fn commands(&self) -> Vec<TerminalCommand> {
+        self.completed.iter().chain(self.running.iter()).cloned().collect()
+    }
+
+    /// 是否有尚未结束的命令
+    pub fn has_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// 最近一次报告的工作目录
+    pub fn cwd(&self) -> Option<&PathBuf> {
+        self.cwd.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_kind_detection() {
+        assert_eq!(ShellKind::from_program("/bin/bash"), Some(ShellKind::Bash));
+        assert_eq!(ShellKind::from_program("-zsh"), Some(ShellKind::Zsh));
+        assert_eq!(ShellKind::from_program("sh"), Some(ShellKind::Posix));
+        assert_eq!(ShellKind::from_program("/usr/bin/fish"), None);
+    }
+
+    #[test]
+    fn test_command_line() {
+        assert_eq!(command_line("ls", false), "ls\n");
+        assert_eq!(command_line("sleep 1 &", true), "{ sleep 1 &\n}; __workhorse_prompt\n");
+    }
+
+    #[test]
+    fn test_extract_markers() {
+        let line = "partial\u{1b}]133;D;2\u{07}\u{1b}]7;file:///tmp/work\u{07}\u{1b}]133;A\u{07}";
+        let (text, markers) = extract_markers(line);
+
+        assert_eq!(text, "partial");
+        assert_eq!(
+            markers,
+            vec![
+                ShellMarker::CommandFinished(Some(2)),
+                ShellMarker::WorkingDirectory(PathBuf::from("/tmp/work")),
+                ShellMarker::PromptStart,
+            ]
+        );
+
+        // 其他OSC序列原样保留
+        let (text, markers) = extract_markers("\u{1b}]0;title\u{07}hello");
+        assert_eq!(text, "\u{1b}]0;title\u{07}hello");
+        assert!(markers.is_empty());
+    }
+
+    #[test]
+    fn test_command_tracker() {
+        let mut tracker = CommandTracker::new(PathBuf::from("/repo"));
+
+        // 首个提示符没有对应的命令
+        assert!(tracker.apply(ShellMarker::CommandFinished(Some(0)), None, 0).is_none());
+        assert!(tracker.commands().is_empty());
+
+        tracker.begin("cd src", 1, 10);
+        tracker.begin("false", 2, 11);
+        let finished = tracker.apply(ShellMarker::CommandFinished(Some(0)), Some(1), 12);
+        assert_eq!(finished.map(|c| c.command), Some("cd src".to_string()));
+        tracker.apply(ShellMarker::WorkingDirectory(PathBuf::from("/repo/src")), Some(1), 12);
+        tracker.apply(ShellMarker::CommandFinished(Some(1)), Some(3), 13);
+
+        let commands = tracker.commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].exit_code, Some(0));
+        assert_eq!(commands[0].cwd, Some(PathBuf::from("/repo")));
+        assert_eq!(commands[1].exit_code, Some(1));
+        assert_eq!(commands[1].end_seq, Some(3));
+        assert_eq!(tracker.cwd(), Some(&PathBuf::from("/repo/src")));
+
+        tracker.begin("sleep 10", 4, 14);
+        tracker.interrupt_all(Some(4), 15);
+        assert_eq!(tracker.commands()[2].status, CommandStatus::Interrupted);
+    }
+}
\ No newline at end of file
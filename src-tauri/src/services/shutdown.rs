@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use crate::services::script_executor::ScriptShutdownReport;
+use crate::services::terminal_service::{TerminalShutdownReport, TerminalStatus};
+use crate::services::{ScriptExecutor, TerminalService};
+
+/// 等待子进程退出的默认时间
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 退出前仍在运行的进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownStatus {
+    pub active_terminals: usize,
+    /// 仍有命令或子进程在运行的终端名称
+    pub busy_terminals: Vec<String>,
+    pub running_scripts: Vec<String>,
+    /// 是否需要用户确认后再退出
+    pub requires_confirmation: bool,
+}
+
+/// 关闭过程的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    pub terminals: TerminalShutdownReport,
+    pub scripts: ScriptShutdownReport,
+    pub duration_ms: u64,
+}
+
+/// 应用退出时统一结束终端和脚本进程
+#[derive(Debug)]
+pub struct ShutdownCoordinator {
+    terminal_service: Arc<TerminalService>,
+    script_executor: Arc<ScriptExecutor>,
+    confirm_on_exit: AtomicBool,
+    exit_confirmed: AtomicBool,
+    shutdown_started: AtomicBool,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(terminal_service: Arc<TerminalService>, script_executor: Arc<ScriptExecutor>) -> Self {
+        Self {
+            terminal_service,
+            script_executor,
+            confirm_on_exit: AtomicBool::new(true),
+            exit_confirmed: AtomicBool::new(false),
+            shutdown_started: AtomicBool::new(false),
+        }
+    }
+
+    /// 设置有进程运行时是否在退出前询问用户
+    pub fn set_confirm_on_exit(&self, confirm: bool) {
+        self.confirm_on_exit.store(confirm, Ordering::SeqCst);
+    }
+
+    pub fn confirm_on_exit(&self) -> bool {
+        self.confirm_on_exit.load(Ordering::SeqCst)
+    }
+
+    /// 用户已确认退出，之后的关闭请求不再询问
+    pub fn confirm_exit(&self) {
+        self.exit_confirmed.store(true, Ordering::SeqCst);
+    }
+
+    /// 获取当前运行中的进程概况
+    pub fn status(&self) -> ShutdownStatus {
+        let active_terminals = self
+            .terminal_service
+            .get_all_terminals()
+            .into_iter()
+            .filter(|t| matches!(t.status, TerminalStatus::Active))
+            .count();
+        let busy_terminals: Vec<String> = self
+            .terminal_service
+            .busy_terminals()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        let running_scripts = self.script_executor.running_executions();
+
+        let requires_confirmation = self.confirm_on_exit()
+            && !self.exit_confirmed.load(Ordering::SeqCst)
+            && (!busy_terminals.is_empty() || !running_scripts.is_empty());
+
+        ShutdownStatus {
+            active_terminals,
+            busy_terminals,
+            running_scripts,
+            requires_confirmation,
+        }
+    }
+
+    /// 结束所有终端和脚本，只执行一次，重复调用返回None
+    pub async fn shutdown(&self, timeout: Duration) -> Option<ShutdownReport> {
+        if self.shutdown_started.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        let started = Instant::now();
+        let (terminals, scripts) = tokio::join!(
+            self.terminal_service.shutdown(timeout),
+            self.script_executor.shutdown(timeout),
+        );
+
+        Some(ShutdownReport {
+            terminals,
+            scripts,
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_terminals_and_scripts() {
+        let terminal_service = Arc::new(TerminalService::new());
+        let script_executor = Arc::new(ScriptExecutor::new());
+        let coordinator = ShutdownCoordinator::new(terminal_service.clone(), script_executor.clone());
+
+        let terminal_id = terminal_service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+        terminal_service.start_terminal(&terminal_id).await.unwrap();
+        terminal_service.send_command(&terminal_id, "sleep 30").await.unwrap();
+
+        let execution_id = script_executor
+            .create_execution("sleep 30".to_string(), env::temp_dir(), None)
+            .await
+            .unwrap();
+        let executor = script_executor.clone();
+        let script = tokio::spawn(async move { executor.execute_script(execution_id).await });
+
+        let mut status = coordinator.status();
+        for _ in 0..50 {
+            if !status.busy_terminals.is_empty() && !status.running_scripts.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            status = coordinator.status();
+        }
+        assert_eq!(status.active_terminals, 1);
+        assert_eq!(status.busy_terminals.len(), 1);
+        assert_eq!(status.running_scripts.len(), 1);
+        assert!(status.requires_confirmation);
+
+        coordinator.confirm_exit();
+        assert!(!coordinator.status().requires_confirmation);
+
+        let report = coordinator.shutdown(Duration::from_secs(2)).await.unwrap();
+        assert_eq!(report.terminals.closed, 1);
+        assert_eq!(report.terminals.killed, 0);
+        assert_eq!(report.scripts.stopped, 1);
+        assert_eq!(report.scripts.killed, 0);
+        assert!(report.duration_ms < 5000);
+
+        let session = terminal_service.get_terminal_session(&terminal_id).unwrap();
+        assert_ne!(session.status, TerminalStatus::Active);
+        let result = script.await.unwrap().unwrap();
+        assert!(!result.success);
+
+        // 只执行一次
+        assert!(coordinator.shutdown(Duration::from_secs(1)).await.is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::terminal_service::{OutputType, TerminalOutput};
+
+/// 默认每个终端的历史记录内存上限（字节）
+pub const DEFAULT_HISTORY_BYTES: usize = 4 * 1024 * 1024;
+
+/// 单行输出的最大长度（字节），超出部分会被截断
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// 每条记录除内容外的额外开销估算
+const ENTRY_OVERHEAD: usize = std::mem::size_of::<TerminalOutput>();
+
+/// 增量获取历史记录的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryChunk {
+    pub entries: Vec<TerminalOutput>,
+    /// 缓冲区中最早的序列号
+    pub first_seq: u64,
+    /// 下一条记录将使用的序列号，客户端可用它作为下次请求的起点
+    pub next_seq: u64,
+    /// 请求的起点早于缓冲区中最早的记录，中间有记录已被丢弃
+    pub truncated: bool,
+}
+
+/// 历史记录搜索条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySearchQuery {
+    pub pattern: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    /// 只搜索指定类型的输出，为空时搜索所有类型
+    pub output_types: Option<Vec<OutputType>>,
+    /// 匹配行前后附带的上下文行数
+    pub context_lines: usize,
+    pub max_results: Option<usize>,
+}
+
+/// 单条匹配结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryMatch {
+    pub entry: TerminalOutput,
+    /// 匹配内容在行内的字节范围
+    pub ranges: Vec<(usize, usize)>,
+    pub context_before: Vec<TerminalOutput>,
+    pub context_after: Vec<TerminalOutput>,
+}
+
+/// 编译后的搜索条件
+pub struct HistoryMatcher {
+    regex: Regex,
+    output_types: Option<Vec<OutputType>>,
+    context_lines: usize,
+}
+
+impl HistoryMatcher {
+    pub fn new(query: &HistorySearchQuery) -> Result<Self, String> {
+        if query.pattern.is_empty() {
+            return Err("Search pattern cannot be empty".to_string());
+        }
+
+        let pattern = if query.is_regex {
+            query.pattern.clone()
+        } else {
+            regex::escape(&query.pattern)
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!query.case_sensitive)
+            .size_limit(1024 * 1024)
+            .build()
+            .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+        Ok(Self {
+            regex,
+            output_types: query.output_types.clone().filter(|types| !types.is_empty()),
+            context_lines: query.context_lines,
+        })
+    }
+
+    fn accepts(&self, output: &TerminalOutput) -> bool {
+        self.output_types
+            .as_ref()
+            .map(|types| types.contains(&output.output_type))
+            .unwrap_or(true)
+    }
+
+    fn find_ranges(&self, content: &str) -> Vec<(usize, usize)> {
+        self.regex
+            .find_iter(content)
+            .filter(|m| !m.as_str().is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect()
+    }
+}
+
+/// 按字节预算限制大小的终端输出环形缓冲区
+#[derive(Debug)]
+pub struct TerminalHistory {
+    entries: VecDeque<TerminalOutput>,
+    next_seq: u64,
+    total_bytes: usize,
+    max_bytes: usize,
+}
+
+impl Default for TerminalHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_BYTES)
+    }
+}
+
+impl TerminalHistory {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            next_seq: 1,
+            total_bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// 估算一条记录占用的字节数
+    fn entry_size(output: &TerminalOutput) -> usize {
+        output.content.len() + ENTRY_OVERHEAD
+    }
+
+    /// 截断过长的行（保证在字符边界处截断）
+    fn truncate_line(content: &mut String) {
+        if content.len() <= MAX_LINE_BYTES {
+            return;
+        }
+
+        let mut end = MAX_LINE_BYTES;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let dropped = content.len() - end;
+        content.truncate(end);
+        content.push_str(&format!(" … [truncated {} bytes]", dropped));
+    }
+
+    /// 追加一条记录，分配序列号并返回存储后的记录
+    pub fn push(&mut self, mut output: TerminalOutput) -> TerminalOutput {
+        Self::truncate_line(&mut output.content);
+        output.seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.total_bytes += Self::entry_size(&output);
+        self.entries.push_back(output.clone());
+        self.evict();
+
+        output
+    }
+
+    /// 淘汰最旧的记录直到满足字节预算（至少保留最新的一条）
+    fn evict(&mut self) {
+        while self.total_bytes > self.max_bytes && self.entries.len() > 1 {
+            if let Some(oldest) = self.entries.pop_front() {
+                self.total_bytes -= Self::entry_size(&oldest);
+            }
+        }
+    }
+
+    /// 获取序列号大于 `seq` 的记录，最多返回 `limit` 条
+    pub fn since(&self, seq: u64, limit: Option<usize>) -> HistoryChunk {
+        let first_seq = self.first_seq();
+        // 序列号连续，可以直接计算起始位置
+        let start = seq.saturating_add(1).saturating_sub(first_seq) as usize;
+        let limit = limit.unwrap_or(usize::MAX);
+
+        let entries: Vec<TerminalOutput> = self
+            .entries
+            .iter()
+            .skip(start)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        HistoryChunk {
+            entries,
+            first_seq,
+            next_seq: self.next_seq,
+            truncated: seq.saturating_add(1) < first_seq,
+        }
+    }
+
+    /// 搜索历史记录，最多返回 `limit` 条匹配
+    pub fn search(&self, matcher: &HistoryMatcher, limit: usize) -> Vec<HistoryMatch> {
+        let mut matches = Vec::new();
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            if matches.len() >= limit {
+                break;
+            }
+            if !matcher.accepts(entry) {
+                continue;
+            }
+
+            let ranges = matcher.find_ranges(&entry.content);
+            if ranges.is_empty() {
+                continue;
+            }
+
+            let before_start = index.saturating_sub(matcher.context_lines);
+            let context_before = self.entries.range(before_start..index).cloned().collect();
+            let after_end = (index + 1 + matcher.context_lines).min(self.entries.len());
+            let context_after = self.entries.range(index + 1..after_end).cloned().collect();
+
+            matches.push(HistoryMatch {
+                entry: entry.clone(),
+                ranges,
+                context_before,
+                context_after,
+            });
+        }
+
+        matches
+    }
+
+    /// 获取所有记录
+    pub fn entries(&self) -> Vec<TerminalOutput> {
+        self.entries.iter().cloned().collect()
+    }
+
+    /// 遍历所有记录
+    pub fn iter(&self) -> impl Iterator<Item = &TerminalOutput> {
+        self.entries.iter()
+    }
+
+    /// 缓冲区中最早的序列号（为空时等于下一个序列号）
+    pub fn first_seq(&self) -> u64 {
+        self.entries.front().map(|e| e.seq).unwrap_or(self.next_seq)
+    }
+
+    /// 最新记录的序列号
+    pub fn last_seq(&self) -> Option<u64> {
+        self.entries.back().map(|e| e.seq)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// 调整字节预算，缩小时立即淘汰旧记录
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.evict();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::terminal_service::OutputType;
+
+    fn line(content: &str) -> TerminalOutput {
+        TerminalOutput::new(content.to_string(), OutputType::Stdout)
+    }
+
+    #[test]
+    fn test_sequence_numbers_and_since() {
+        let mut history = TerminalHistory::default();
+        for i in 0..5 {
+            history.push(line(&format!("line {}", i)));
+        }
+
+        assert_eq!(history.first_seq(), 1);
+        assert_eq!(history.last_seq(), Some(5));
+
+        let chunk = history.since(3, None);
+        assert_eq!(chunk.entries.len(), 2);
+        assert_eq!(chunk.entries[0].seq, 4);
+        assert_eq!(chunk.next_seq, 6);
+        assert!(!chunk.truncated);
+
+        let chunk = history.since(0, Some(2));
+        assert_eq!(chunk.entries.len(), 2);
+        assert_eq!(chunk.entries[1].content, "line 1");
+    }
+
+    #[test]
+    fn test_byte_budget_eviction() {
+        let budget = 10 * (100 + ENTRY_OVERHEAD);
+        let mut history = TerminalHistory::new(budget);
+        for _ in 0..50 {
+            history.push(line(&"x".repeat(100)));
+        }
+
+        assert_eq!(history.len(), 10);
+        assert!(history.total_bytes() <= budget);
+        assert_eq!(history.first_seq(), 41);
+
+        let chunk = history.since(5, None);
+        assert!(chunk.truncated);
+        assert_eq!(chunk.entries.len(), 10);
+
+        history.set_max_bytes(budget / 2);
+        assert_eq!(history.len(), 5);
+    }
+
+    #[test]
+    fn test_long_line_truncated() {
+        let mut history = TerminalHistory::default();
+        let stored = history.push(line(&"é".repeat(MAX_LINE_BYTES)));
+
+        assert!(stored.content.len() < MAX_LINE_BYTES + 64);
+        assert!(stored.content.contains("truncated"));
+        assert!(history.total_bytes() < MAX_LINE_BYTES + 64 + ENTRY_OVERHEAD);
+    }
+
+    fn query(pattern: &str, is_regex: bool, case_sensitive: bool) -> HistorySearchQuery {
+        HistorySearchQuery {
+            pattern: pattern.to_string(),
+            is_regex,
+            case_sensitive,
+            output_types: None,
+            context_lines: 1,
+            max_results: None,
+        }
+    }
+
+    #[test]
+    fn test_search_history() {
+        let mut history = TerminalHistory::default();
+        history.push(TerminalOutput::new("npm run dev".to_string(), OutputType::Input));
+        history.push(line("Local: http://localhost:5173/"));
+        history.push(TerminalOutput::new("Error: port in use".to_string(), OutputType::Stderr));
+        history.push(line("ready in 300ms"));
+
+        let matcher = HistoryMatcher::new(&query("localhost", false, true)).unwrap();
+        let matches = history.search(&matcher, usize::MAX);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.seq, 2);
+        assert_eq!(matches[0].ranges, vec![(14, 23)]);
+        assert_eq!(matches[0].context_before[0].content, "npm run dev");
+        assert_eq!(matches[0].context_after[0].content, "Error: port in use");
+
+        let matcher = HistoryMatcher::new(&query("error", false, true)).unwrap();
+        assert!(history.search(&matcher, usize::MAX).is_empty());
+        let matcher = HistoryMatcher::new(&query("error", false, false)).unwrap();
+        assert_eq!(history.search(&matcher, usize::MAX).len(), 1);
+
+        let matcher = HistoryMatcher::new(&query(r"\d+ms$", true, true)).unwrap();
+        assert_eq!(history.search(&matcher, usize::MAX)[0].entry.seq, 4);
+
+        let mut typed = query("e", false, false);
+        typed.output_types = Some(vec![OutputType::Stderr]);
+        let matcher = HistoryMatcher::new(&typed).unwrap();
+        let matches = history.search(&matcher, usize::MAX);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.output_type, OutputType::Stderr);
+
+        assert!(HistoryMatcher::new(&query("(", true, true)).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,317 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::mpsc;
+
+use crate::services::terminal_service::{OutputType, TerminalOutput, TerminalSession};
+
+/// 录制文件的扩展名
+const RECORDING_EXTENSION: &str = "cast";
+
+/// asciicast v2 文件头
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsciicastHeader {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    pub timestamp: Option<u64>,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub env: std::collections::HashMap<String, String>,
+    /// 以下为Workhorse扩展字段，asciinema播放时会忽略
+    pub terminal_id: Option<String>,
+    pub working_directory: Option<PathBuf>,
+}
+
+/// 录制中的单个事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingEvent {
+    /// 相对录制开始的秒数
+    pub time: f64,
+    /// "o" 输出、"i" 输入、"m" 标记
+    pub event_type: String,
+    pub data: String,
+}
+
+/// 录制文件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub path: PathBuf,
+    pub terminal_id: Option<String>,
+    pub title: Option<String>,
+    pub working_directory: Option<PathBuf>,
+    pub started_at: Option<u64>,
+    pub duration: f64,
+    pub size_bytes: u64,
+}
+
+/// 回放选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayOptions {
+    /// 播放速度倍数，1.0 为原始速度
+    pub speed: f64,
+    /// 事件之间的最大等待秒数，用于跳过长时间空闲
+    pub max_idle: Option<f64>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            max_idle: None,
+        }
+    }
+}
+
+/// 将终端输出写入 asciicast v2 文件
+#[derive(Debug)]
+pub struct AsciicastRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started_at_ms: u64,
+    last_time: f64,
+}
+
+impl AsciicastRecorder {
+    /// 在指定目录创建新的录制文件并写入文件头
+    pub fn create(logs_dir: &Path, session: &TerminalSession, started_at_ms: u64) -> Result<Self, String> {
+        fs::create_dir_all(logs_dir)
+            .map_err(|e| format!("Failed to create logs directory: {}", e))?;
+
+        let path = logs_dir.join(format!(
+            "{}-{}.{}",
+            session.id, started_at_ms, RECORDING_EXTENSION
+        ));
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create recording file: {}", e))?;
+
+        let mut env = std::collections::HashMap::new();
+        env.insert("SHELL".to_string(), session.shell.program.clone());
+        env.insert("TERM".to_string(), "dumb".to_string());
+
+        let header = AsciicastHeader {
+            version: 2,
+            width: 80,
+            height: 24,
+            timestamp: Some(started_at_ms / 1000),
+            title: Some(session.name.clone()),
+            env,
+            terminal_id: Some(session.id.clone()),
+            working_directory: Some(session.working_directory.clone()),
+        };
+
+        let mut recorder = Self {
+            path,
+            writer: BufWriter::new(file),
+            started_at_ms,
+            last_time: 0.0,
+        };
+        let header_json = serde_json::to_string(&header)
+            .map_err(|e| format!("Failed to serialize recording header: {}", e))?;
+        recorder.write_line(&header_json)?;
+        recorder.flush()?;
+
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.writer, "{}", line)
+            .map_err(|e| format!("Failed to write recording: {}", e))
+    }
+
+    /// 记录一条终端输出
+    pub fn record(&mut self, output: &TerminalOutput) -> Result<(), String> {
+        let (event_type, data) = match output.output_type {
+            OutputType::Stdout | OutputType::Stderr => ("o", format!("{}\r\n", output.content)),
+            OutputType::Input => ("i", format!("{}\n", output.content)),
+            OutputType::System => ("m", output.content.clone()),
+        };
+
+        // 不同读取任务产生的时间戳可能略有乱序，保证事件时间单调递增
+        let elapsed = output.timestamp.saturating_sub(self.started_at_ms) as f64 / 1000.0;
+        let time = elapsed.max(self.last_time);
+        self.last_time = time;
+
+        let event = serde_json::json!([time, event_type, data]);
+        self.write_line(&event.to_string())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Failed to flush recording: {}", e))
+    }
+}
+
+/// 读取录制文件头
+fn read_header(path: &Path) -> Result<AsciicastHeader, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+    let mut first_line = String::new();
+    BufReader::new(file)
+        .read_line(&mut first_line)
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+
+    serde_json::from_str(&first_line).map_err(|e| format!("Invalid recording header: {}", e))
+}
+
+/// 解析单行事件
+fn parse_event(line: &str) -> Option<RecordingEvent> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let array = value.as_array()?;
+
+    Some(RecordingEvent {
+        time: array.first()?.as_f64()?,
+        event_type: array.get(1)?.as_str()?.to_string(),
+        data: array.get(2)?.as_str()?.to_string(),
+    })
+}
+
+/// 读取最后一个事件的时间（只读取文件末尾部分）
+fn read_duration(path: &Path) -> f64 {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return 0.0,
+    };
+
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let tail_start = len.saturating_sub(64 * 1024);
+    if file.seek(SeekFrom::Start(tail_start)).is_err() {
+        return 0.0;
+    }
+
+    let mut tail = Vec::new();
+    if file.read_to_end(&mut tail).is_err() {
+        return 0.0;
+    }
+
+    String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .find_map(parse_event)
+        .map(|event| event.time)
+        .unwrap_or(0.0)
+}
+
+/// 列出目录中的录制文件，可按工作区路径过滤
+pub fn list_recordings(logs_dir: &Path, workspace_path: Option<&Path>) -> Result<Vec<RecordingInfo>, String> {
+    if !logs_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(logs_dir)
+        .map_err(|e| format!("Failed to read logs directory: {}", e))?;
+
+    let mut recordings = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some(RECORDING_EXTENSION) {
+            continue;
+        }
+
+        let header = match read_header(&path) {
+            Ok(header) => header,
+            Err(_) => continue,
+        };
+
+        if let Some(workspace_path) = workspace_path {
+            let in_workspace = header
+                .working_directory
+                .as_ref()
+                .map(|dir| dir.starts_with(workspace_path))
+                .unwrap_or(false);
+            if !in_workspace {
+                continue;
+            }
+        }
+
+        recordings.push(RecordingInfo {
+            size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            duration: read_duration(&path),
+            path,
+            terminal_id: header.terminal_id,
+            title: header.title,
+            working_directory: header.working_directory,
+            started_at: header.timestamp,
+        });
+    }
+
+    recordings.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(recordings)
+}
+
+/// 根据文件名找到日志目录中的录制文件，拒绝指向目录之外的名称
+pub fn resolve_recording(logs_dir: &Path, file_name: &str) -> Result<PathBuf, String> {
+    let logs_dir = logs_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to read logs directory: {}", e))?;
+    let path = logs_dir
+        .join(file_name)
+        .canonicalize()
+        .map_err(|_| format!("Recording not found: {}", file_name))?;
+
+    if !path.starts_with(&logs_dir)
+        || path.extension().and_then(|s| s.to_str()) != Some(RECORDING_EXTENSION)
+    {
+        return Err(format!("Invalid recording: {}", file_name));
+    }
+    Ok(path)
+}
+
+/// 回放录制文件，按原始（或加速后的）时间间隔发送事件
+pub fn replay_recording(
+    path: &Path,
+    options: ReplayOptions,
+) -> Result<mpsc::UnboundedReceiver<RecordingEvent>, String> {
+    if options.speed <= 0.0 {
+        return Err("Replay speed must be greater than zero".to_string());
+    }
+
+    read_header(path)?;
+    let path = path.to_path_buf();
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        let mut lines = tokio::io::BufReader::new(file).lines();
+        let mut previous_time = 0.0;
+
+        // 跳过文件头
+        if !matches!(lines.next_line().await, Ok(Some(_))) {
+            return;
+        }
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let event = match parse_event(&line) {
+                Some(event) => event,
+                None => continue,
+            };
+
+            let mut gap = (event.time - previous_time).max(0.0);
+            if let Some(max_idle) = options.max_idle {
+                gap = gap.min(max_idle);
+            }
+            previous_time = event.time;
+
+            let delay = gap / options.speed;
+            if delay > 0.0 {
+                tokio::time::sleep(Duration::from_secs_f64(delay)).await;
+            }
+
+            if event_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(event_rx)
+}
\ No newline at end of file
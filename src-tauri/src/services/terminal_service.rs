@@ -6,7 +6,26 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child as TokioChild, Command as TokioCommand};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::services::command_history::CommandHistoryStore;
+use crate::services::execution_journal::{
+    ExecutionJournal, InterruptedExecution, JournalRecord, LogStream, OutputLog, EXECUTION_ID_ENV,
+};
+use crate::services::output_capture::{self, StreamCapture};
+use crate::services::process_inspector::{self, ListeningPort, ProcessTree};
+use crate::services::shell_integration::{self, CommandTracker, ShellKind, TerminalCommand};
+use crate::services::terminal_recording::AsciicastRecorder;
+use crate::services::terminal_history::{
+    HistoryChunk, HistoryMatch, HistoryMatcher, HistorySearchQuery, TerminalHistory, DEFAULT_HISTORY_BYTES,
+};
+
+/// 自动重启shell的最大次数
+const MAX_AUTO_RESTARTS: u32 = 3;
+
+/// shell启动后设置的信号捕获，发送给进程组的这些信号不会结束shell本身，
+/// 而捕获的信号在子进程中恢复默认处理，前台命令照常被中断
+const SHELL_SIGNAL_TRAP: &str = "trap : INT TERM TSTP\n";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalSession {
@@ -17,7 +36,96 @@ pub struct TerminalSession {
     pub status: TerminalStatus,
     pub created_at: u64,
     pub last_activity: u64,
-    pub output_history: Vec<TerminalOutput>,
+    pub shell: ShellConfig,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub auto_restart: bool,
+    pub restart_count: u32,
+    pub history_limit_bytes: usize,
+    pub recording_path: Option<PathBuf>,
+    /// 终端所属的工作区，独立终端为None
+    #[serde(default)]
+    pub workspace: Option<TerminalWorkspace>,
+    /// 当前附加的客户端数量
+    #[serde(default)]
+    pub attached_clients: usize,
+}
+
+/// 终端与工作区/仓库的关联信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TerminalWorkspace {
+    pub workspace_id: String,
+    pub workspace_path: PathBuf,
+    pub repository_id: Option<String>,
+    pub repository_path: Option<PathBuf>,
+    pub branch: Option<String>,
+}
+
+impl TerminalWorkspace {
+    /// 注入终端进程的环境变量
+    pub fn environment(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("WORKHORSE_WORKSPACE_ID", self.workspace_id.clone()),
+            ("WORKHORSE_WORKSPACE_PATH", self.workspace_path.to_string_lossy().to_string()),
+        ];
+        if let Some(ref repository_id) = self.repository_id {
+            env.push(("WORKHORSE_REPOSITORY_ID", repository_id.clone()));
+        }
+        if let Some(ref repository_path) = self.repository_path {
+            env.push(("WORKHORSE_REPO_PATH", repository_path.to_string_lossy().to_string()));
+        }
+        if let Some(ref branch) = self.branch {
+            env.push(("WORKHORSE_BRANCH", branch.clone()));
+        }
+        env
+    }
+}
+
+/// 终端使用的shell配置（解析后的结果，保存在会话中）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShellConfig {
+    pub program: String,
+    pub args: Vec<String>,
+    pub login: bool,
+    pub interactive: bool,
+    /// 是否启用了shell集成（仅支持 bash/zsh/sh）
+    pub shell_integration: bool,
+}
+
+/// shell的选择方式
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ShellSelection {
+    /// 从 $SHELL 或 /etc/passwd 检测用户的shell
+    Detect,
+    /// 使用服务配置的默认shell
+    Default,
+    /// 指定程序及参数
+    Custom { program: String, args: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellOptions {
+    pub selection: ShellSelection,
+    pub login: bool,
+    pub interactive: bool,
+    /// 注入提示符钩子以跟踪每条命令的退出码和工作目录
+    #[serde(default = "default_shell_integration")]
+    pub shell_integration: bool,
+}
+
+fn default_shell_integration() -> bool {
+    true
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            selection: ShellSelection::Default,
+            login: false,
+            interactive: false,
+            shell_integration: default_shell_integration(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -26,16 +134,32 @@ pub enum TerminalStatus {
     Inactive,
     Closed,
     Error,
+    /// 应用异常退出时仍在运行，可重新启动
+    Interrupted,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalOutput {
+    /// 在终端历史中的序列号，写入历史前为0
+    #[serde(default)]
+    pub seq: u64,
     pub timestamp: u64,
     pub content: String,
     pub output_type: OutputType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TerminalOutput {
+    pub fn new(content: String, output_type: OutputType) -> Self {
+        Self {
+            seq: 0,
+            timestamp: TerminalService::current_timestamp(),
+            content,
+            output_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum OutputType {
     Stdout,
     Stderr,
@@ -43,28 +167,385 @@ pub enum OutputType {
     System,
 }
 
+/// 可以发送给终端前台进程的信号
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TerminalSignal {
+    SigInt,
+    SigTerm,
+    SigKill,
+    SigTstp,
+    SigHup,
+}
+
+impl TerminalSignal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TerminalSignal::SigInt => "SIGINT",
+            TerminalSignal::SigTerm => "SIGTERM",
+            TerminalSignal::SigKill => "SIGKILL",
+            TerminalSignal::SigTstp => "SIGTSTP",
+            TerminalSignal::SigHup => "SIGHUP",
+        }
+    }
+}
+
+/// 控制按键（终端基于管道而非pty，Ctrl-C/Ctrl-Z/Ctrl-D 由服务模拟行规程处理）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ControlKey {
+    CtrlC,
+    CtrlD,
+    CtrlZ,
+    ArrowUp,
+    ArrowDown,
+    ArrowRight,
+    ArrowLeft,
+    Tab,
+    Escape,
+    Enter,
+}
+
+/// 历史搜索范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SearchScope {
+    /// 单个终端
+    Terminal { terminal_id: String },
+    /// 工作目录位于指定工作区路径下的所有终端
+    Workspace { workspace_path: PathBuf },
+}
+
+/// 终端历史搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalSearchResult {
+    pub terminal_id: String,
+    pub terminal_name: String,
+    pub matches: Vec<HistoryMatch>,
+}
+
+/// 终端数量限制与空闲回收策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TerminalLimits {
+    /// 同时存在的终端总数上限
+    pub max_terminals: usize,
+    /// 单个工作区中未关闭终端的数量上限
+    pub max_terminals_per_workspace: Option<usize>,
+    /// 活动终端空闲超过该分钟数后自动关闭，None 表示不自动关闭
+    pub idle_timeout_minutes: Option<u64>,
+    /// 自动关闭前提前发出警告的秒数
+    pub idle_warning_seconds: u64,
+    /// 已关闭的终端保留的分钟数，超时后从列表中清除
+    pub closed_retention_minutes: Option<u64>,
+    /// 回收任务的检查间隔（秒）
+    pub reap_interval_seconds: u64,
+}
+
+impl Default for TerminalLimits {
+    fn default() -> Self {
+        Self {
+            max_terminals: 10,
+            max_terminals_per_workspace: None,
+            idle_timeout_minutes: None,
+            idle_warning_seconds: 60,
+            closed_retention_minutes: Some(30),
+            reap_interval_seconds: 30,
+        }
+    }
+}
+
+/// 关闭所有终端的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerminalShutdownReport {
+    /// 收到 SIGHUP 后在超时前退出的终端数量
+    pub closed: usize,
+    /// 超时后被强制结束的终端数量
+    pub killed: usize,
+}
+
+/// 一次回收检查的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReapReport {
+    /// 收到空闲警告的终端
+    pub warned: Vec<String>,
+    /// 因空闲被关闭的终端
+    pub closed: Vec<String>,
+    /// 超过保留时间被清除的终端
+    pub purged: Vec<String>,
+}
+
+/// 工作区中某个终端进程树监听的端口
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspacePort {
+    pub terminal_id: String,
+    pub terminal_name: String,
+    pub port: ListeningPort,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandExecution {
     pub command: String,
     pub args: Vec<String>,
     pub working_directory: PathBuf,
     pub environment: HashMap<String, String>,
+    /// 超时时间（毫秒），超时后结束进程
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// 写入进程标准输入的内容
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// 通过默认shell执行，`command` 作为完整的命令行，参数会被转义后追加
+    #[serde(default)]
+    pub use_shell: bool,
+    /// 所属工作区，设置后命令会写入该工作区的命令历史
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+}
+
+/// 单次命令执行的完整结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandExecutionResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    /// 进程被信号结束时的信号编号
+    pub signal: Option<i32>,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    pub success: bool,
+}
+
+/// 广播通道容量，订阅者落后超过该数量时需要从历史中补齐
+const OUTPUT_BROADCAST_CAPACITY: usize = 1024;
+
+/// 附加到终端的客户端
+#[derive(Debug)]
+struct TerminalSubscriber {
+    /// 通过 `poll_subscriber` 拉取时已读取到的序列号
+    cursor: u64,
+    /// 分离时通知推送任务结束
+    detach_sender: Option<oneshot::Sender<()>>,
+}
+
+/// 客户端附加到终端后得到的订阅
+#[derive(Debug)]
+pub struct TerminalAttachment {
+    pub subscriber_id: String,
+    /// 从请求的序列号开始的历史记录
+    pub backlog: HistoryChunk,
+    /// 之后产生的实时输出
+    pub receiver: broadcast::Receiver<TerminalOutput>,
+    /// 客户端被分离或终端被移除时触发
+    pub detached: oneshot::Receiver<()>,
 }
 
 pub struct TerminalInstance {
     pub session: TerminalSession,
-    pub process: Option<TokioChild>,
+    pub pid: Option<u32>,
+    pub kill_sender: Option<oneshot::Sender<()>>,
     pub input_sender: Option<mpsc::UnboundedSender<String>>,
-    pub output_receiver: Option<mpsc::UnboundedReceiver<TerminalOutput>>,
+    pub output_broadcast: broadcast::Sender<TerminalOutput>,
+    pub history: TerminalHistory,
+    pub recorder: Option<AsciicastRecorder>,
+    pub commands: CommandTracker,
+    /// `get_terminal_output` 已返回的最后一条记录序列号
+    pub output_cursor: u64,
+    /// 已发出空闲警告时的时间戳，有新活动后失效
+    pub idle_warned_at: Option<u64>,
+    subscribers: HashMap<String, TerminalSubscriber>,
+    command_history: Arc<CommandHistoryStore>,
+    journal: Arc<ExecutionJournal>,
+    /// 运行期间输出日志的写入通道，崩溃后用于恢复历史
+    journal_sender: Option<mpsc::UnboundedSender<TerminalOutput>>,
+}
+
+impl TerminalInstance {
+    /// 处理一条进程输出：提取shell集成标记后写入历史并广播给订阅者
+    fn ingest(&mut self, mut output: TerminalOutput) -> Option<TerminalOutput> {
+        // 提取shell集成标记，只含标记的行不写入历史
+        if self.session.shell.shell_integration && output.output_type == OutputType::Stdout {
+            let (text, markers) = shell_integration::extract_markers(&output.content);
+            if !markers.is_empty() {
+                for marker in markers {
+                    let finished = self.commands.apply(marker, self.history.last_seq(), output.timestamp);
+                    if let Some(command) = finished {
+                        self.record_exit_code(&command);
+                    }
+                }
+                if text.is_empty() {
+                    return None;
+                }
+                output.content = text;
+            }
+        }
+
+        // 进程输出视为终端活动
+        if output.output_type != OutputType::System {
+            self.session.last_activity = self.session.last_activity.max(output.timestamp);
+        }
+
+        Some(self.publish(output))
+    }
+
+    /// 写入一条服务自身产生的记录（输入回显、系统消息），返回分配了序列号的记录
+    fn record(&mut self, output: TerminalOutput) -> TerminalOutput {
+        let output = self.publish(output);
+        self.flush_logs();
+        output
+    }
+
+    /// 写入历史和录制文件，并广播给所有订阅者
+    fn publish(&mut self, output: TerminalOutput) -> TerminalOutput {
+        let output = self.history.push(output);
+        self.write_recording(&output);
+        self.write_journal(&output);
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.output_broadcast.send(output.clone());
+        output
+    }
+
+    /// 将shell集成报告的退出码写入工作区命令历史
+    fn record_exit_code(&self, command: &TerminalCommand) {
+        if let Some(workspace_id) = self.workspace_id() {
+            if let Err(e) = self.command_history.set_exit_code(workspace_id, &command.command, command.exit_code) {
+                eprintln!("警告: 更新命令历史失败: {}", e);
+            }
+        }
+    }
+
+    fn attach(&mut self, from_seq: Option<u64>) -> TerminalAttachment {
+        let subscriber_id = uuid::Uuid::new_v4().to_string();
+        // 默认只接收附加之后的新输出
+        let from_seq = from_seq.unwrap_or_else(|| self.history.last_seq().unwrap_or(0));
+        let backlog = self.history.since(from_seq, None);
+        let (detach_tx, detach_rx) = oneshot::channel();
+
+        self.subscribers.insert(
+            subscriber_id.clone(),
+            TerminalSubscriber {
+                cursor: backlog.next_seq - 1,
+                detach_sender: Some(detach_tx),
+            },
+        );
+        self.session.attached_clients = self.subscribers.len();
+
+        TerminalAttachment {
+            subscriber_id,
+            backlog,
+            receiver: self.output_broadcast.subscribe(),
+            detached: detach_rx,
+        }
+    }
+
+    fn detach(&mut self, subscriber_id: &str) -> bool {
+        let removed = match self.subscribers.remove(subscriber_id) {
+            Some(mut subscriber) => {
+                if let Some(detach_sender) = subscriber.detach_sender.take() {
+                    let _ = detach_sender.send(());
+                }
+                true
+            }
+            None => false,
+        };
+        self.session.attached_clients = self.subscribers.len();
+        removed
+    }
+
+    /// 写入录制文件，失败时停止录制，避免影响终端本身
+    fn write_recording(&mut self, output: &TerminalOutput) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(output) {
+                eprintln!("警告: 终端录制写入失败: {}", e);
+                self.recorder = None;
+                self.session.recording_path = None;
+            }
+        }
+    }
+
+    /// 交给后台任务写入输出日志，写入失败后任务结束，不再发送
+    fn write_journal(&mut self, output: &TerminalOutput) {
+        if let Some(sender) = self.journal_sender.as_ref() {
+            if sender.send(output.clone()).is_err() {
+                self.journal_sender = None;
+            }
+        }
+    }
+
+    /// 写出录制文件的缓冲内容
+    fn flush_logs(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            let _ = recorder.flush();
+        }
+    }
+
+    /// 开始在执行日志中跟踪终端
+    fn begin_journal(&mut self) {
+        self.journal.begin(JournalRecord::Terminal(self.session.clone()), self.pid);
+        self.journal_sender = self
+            .journal
+            .output_log(&self.session.id, LogStream::Terminal)
+            .map(Self::spawn_journal_writer);
+    }
+
+    /// 在后台任务中批量写入输出日志，文件读写不占用终端锁
+    fn spawn_journal_writer(mut log: OutputLog) -> mpsc::UnboundedSender<TerminalOutput> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<TerminalOutput>();
+        tokio::spawn(async move {
+            while let Some(output) = receiver.recv().await {
+                let mut batch = vec![output];
+                while let Ok(output) = receiver.try_recv() {
+                    batch.push(output);
+                }
+
+                let written = tokio::task::spawn_blocking(move || {
+                    let result = batch
+                        .iter()
+                        .try_for_each(|output| log.write_output(output))
+                        .and_then(|_| log.flush());
+                    (log, result)
+                })
+                .await;
+                log = match written {
+                    Ok((log, Ok(()))) => log,
+                    Ok((_, Err(e))) => {
+                        eprintln!("警告: 终端输出日志写入失败: {}", e);
+                        return;
+                    }
+                    Err(_) => return,
+                };
+            }
+        });
+        sender
+    }
+
+    /// 终端正常结束，从执行日志中移除
+    fn end_journal(&mut self) {
+        self.journal_sender = None;
+        self.journal.finish(&self.session.id);
+    }
+
+    /// 终端是否仍占用资源（未关闭也未出错退出）
+    fn is_open(&self) -> bool {
+        !matches!(
+            self.session.status,
+            TerminalStatus::Closed | TerminalStatus::Error | TerminalStatus::Interrupted
+        )
+    }
+
+    fn workspace_id(&self) -> Option<&str> {
+        self.session.workspace.as_ref().map(|w| w.workspace_id.as_str())
+    }
+
 }
 
 impl std::fmt::Debug for TerminalInstance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TerminalInstance")
             .field("session", &self.session)
-            .field("process", &self.process.is_some())
+            .field("pid", &self.pid)
+            .field("kill_sender", &self.kill_sender.is_some())
             .field("input_sender", &self.input_sender.is_some())
-            .field("output_receiver", &self.output_receiver.is_some())
+            .field("subscribers", &self.subscribers.len())
+            .field("history_len", &self.history.len())
+            .field("recorder", &self.recorder.as_ref().map(|r| r.path().to_path_buf()))
             .finish()
     }
 }
@@ -72,8 +553,11 @@ impl std::fmt::Debug for TerminalInstance {
 #[derive(Debug)]
 pub struct TerminalService {
     terminals: Arc<Mutex<HashMap<String, TerminalInstance>>>,
-    max_terminals: usize,
-    max_history_size: usize,
+    limits: Arc<Mutex<TerminalLimits>>,
+    max_history_bytes: usize,
+    default_shell: Arc<Mutex<Option<(String, Vec<String>)>>>,
+    command_history: Arc<CommandHistoryStore>,
+    journal: Arc<ExecutionJournal>,
 }
 
 impl Default for TerminalService {
@@ -86,9 +570,156 @@ impl TerminalService {
     pub fn new() -> Self {
         Self {
             terminals: Arc::new(Mutex::new(HashMap::new())),
-            max_terminals: 10,
-            max_history_size: 1000,
+            limits: Arc::new(Mutex::new(TerminalLimits::default())),
+            max_history_bytes: DEFAULT_HISTORY_BYTES,
+            default_shell: Arc::new(Mutex::new(None)),
+            command_history: Arc::new(CommandHistoryStore::in_memory()),
+            journal: Arc::new(ExecutionJournal::in_memory()),
+        }
+    }
+
+    /// 使用指定的命令历史存储创建服务
+    pub fn with_command_history(command_history: Arc<CommandHistoryStore>) -> Self {
+        Self {
+            command_history,
+            ..Self::new()
+        }
+    }
+
+    /// 使用指定的执行日志，运行中的终端会写入日志以便崩溃后恢复
+    pub fn with_journal(self, journal: Arc<ExecutionJournal>) -> Self {
+        Self { journal, ..self }
+    }
+
+    /// 工作区命令历史存储
+    pub fn command_history(&self) -> Arc<CommandHistoryStore> {
+        Arc::clone(&self.command_history)
+    }
+
+    /// 设置默认shell，program为None时恢复为自动检测
+    pub fn set_default_shell(&self, program: Option<String>, args: Vec<String>) -> Result<(), String> {
+        if let Some(ref program) = program {
+            if program.trim().is_empty() {
+                return Err("Shell program cannot be empty".to_string());
+            }
+        }
+
+        let mut default_shell = self.default_shell.lock().unwrap();
+        *default_shell = program.map(|program| (program, args));
+        Ok(())
+    }
+
+    /// 获取默认shell（未配置时返回检测到的shell）
+    pub fn get_default_shell(&self) -> ShellConfig {
+        let default_shell = self.default_shell.lock().unwrap();
+        let (program, args) = default_shell
+            .clone()
+            .unwrap_or_else(|| (Self::detect_user_shell(), Vec::new()));
+
+        ShellConfig {
+            program,
+            args,
+            login: false,
+            interactive: false,
+            shell_integration: false,
+        }
+    }
+
+    /// 检测当前用户的shell：优先 $SHELL，其次 /etc/passwd，最后回退到系统shell
+    pub fn detect_user_shell() -> String {
+        if cfg!(windows) {
+            return std::env::var("COMSPEC").unwrap_or_else(|_| "cmd".to_string());
+        }
+
+        if let Ok(shell) = std::env::var("SHELL") {
+            if !shell.is_empty() && std::path::Path::new(&shell).exists() {
+                return shell;
+            }
+        }
+
+        if let Some(shell) = Self::shell_from_passwd() {
+            return shell;
+        }
+
+        "/bin/sh".to_string()
+    }
+
+    /// 从 /etc/passwd 中读取当前用户的登录shell
+    fn shell_from_passwd() -> Option<String> {
+        let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("LOGNAME"))
+            .ok();
+        let uid = Self::current_uid();
+
+        passwd
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .filter(|fields| fields.len() >= 7)
+            .find(|fields| match (&user, uid) {
+                (Some(user), _) => fields[0] == user,
+                (None, Some(uid)) => fields[2] == uid.to_string(),
+                (None, None) => false,
+            })
+            .map(|fields| fields[6].to_string())
+            .filter(|shell| !shell.is_empty() && std::path::Path::new(shell).exists())
+    }
+
+    #[cfg(unix)]
+    fn current_uid() -> Option<u32> {
+        Some(unsafe { libc::getuid() })
+    }
+
+    #[cfg(not(unix))]
+    fn current_uid() -> Option<u32> {
+        None
+    }
+
+    /// 根据选项解析出最终的shell配置
+    fn resolve_shell(&self, options: ShellOptions) -> Result<ShellConfig, String> {
+        let (program, args) = match options.selection {
+            ShellSelection::Detect => (Self::detect_user_shell(), Vec::new()),
+            ShellSelection::Default => {
+                let default_shell = self.get_default_shell();
+                (default_shell.program, default_shell.args)
+            }
+            ShellSelection::Custom { program, args } => {
+                if program.trim().is_empty() {
+                    return Err("Shell program cannot be empty".to_string());
+                }
+                (program, args)
+            }
+        };
+
+        let shell_integration = options.shell_integration
+            && !cfg!(windows)
+            && ShellKind::from_program(&program).is_some();
+
+        Ok(ShellConfig {
+            program,
+            args,
+            login: options.login,
+            interactive: options.interactive,
+            shell_integration,
+        })
+    }
+
+    /// 构造shell启动参数（登录/交互模式的标志放在自定义参数之前）
+    fn shell_arguments(shell: &ShellConfig) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if !cfg!(windows) {
+            if shell.login {
+                args.push("-l".to_string());
+            }
+            if shell.interactive {
+                args.push("-i".to_string());
+            }
         }
+
+        args.extend(shell.args.iter().cloned());
+        args
     }
 
     /// 生成唯一的终端ID
@@ -97,7 +728,9 @@ impl TerminalService {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_millis();
-        format!("term_{}", timestamp)
+        // 同一毫秒内创建多个终端时用随机后缀避免ID冲突
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("term_{}_{}", timestamp, &suffix[..8])
     }
 
     /// 获取当前时间戳
@@ -114,11 +747,13 @@ impl TerminalService {
         name: Option<String>,
         working_directory: PathBuf,
         environment: Option<HashMap<String, String>>,
+        shell: Option<ShellOptions>,
     ) -> Result<String, String> {
         // 检查终端数量限制
         {
+            let max_terminals = self.limits.lock().unwrap().max_terminals;
             let terminals = self.terminals.lock().unwrap();
-            if terminals.len() >= self.max_terminals {
+            if terminals.len() >= max_terminals {
                 return Err("Maximum number of terminals reached".to_string());
             }
         }
@@ -131,6 +766,7 @@ impl TerminalService {
         let terminal_id = Self::generate_terminal_id();
         let session_name = name.unwrap_or_else(|| format!("Terminal {}", terminal_id));
         let env = environment.unwrap_or_default();
+        let shell = self.resolve_shell(shell.unwrap_or_default())?;
         let timestamp = Self::current_timestamp();
 
         let session = TerminalSession {
@@ -141,15 +777,18 @@ impl TerminalService {
             status: TerminalStatus::Inactive,
             created_at: timestamp,
             last_activity: timestamp,
-            output_history: Vec::new(),
+            shell,
+            exit_code: None,
+            exit_signal: None,
+            auto_restart: false,
+            restart_count: 0,
+            history_limit_bytes: self.max_history_bytes,
+            recording_path: None,
+            workspace: None,
+            attached_clients: 0,
         };
 
-        let terminal_instance = TerminalInstance {
-            session,
-            process: None,
-            input_sender: None,
-            output_receiver: None,
-        };
+        let terminal_instance = self.new_instance(session);
 
         {
             let mut terminals = self.terminals.lock().unwrap();
@@ -159,6 +798,97 @@ impl TerminalService {
         Ok(terminal_id)
     }
 
+    fn new_instance(&self, session: TerminalSession) -> TerminalInstance {
+        let commands = CommandTracker::new(session.working_directory.clone());
+        TerminalInstance {
+            history: TerminalHistory::new(session.history_limit_bytes),
+            session,
+            pid: None,
+            kill_sender: None,
+            input_sender: None,
+            output_broadcast: broadcast::channel(OUTPUT_BROADCAST_CAPACITY).0,
+            recorder: None,
+            commands,
+            output_cursor: 0,
+            idle_warned_at: None,
+            subscribers: HashMap::new(),
+            command_history: Arc::clone(&self.command_history),
+            journal: Arc::clone(&self.journal),
+            journal_sender: None,
+        }
+    }
+
+    /// 将上次运行中断的终端加入终端列表，保留中断前的输出，可通过 `start_terminal` 重新启动
+    pub fn restore_interrupted(&self, interrupted: &[InterruptedExecution]) {
+        let mut terminals = self.terminals.lock().unwrap();
+        for entry in interrupted {
+            let session = match entry.record {
+                JournalRecord::Terminal(ref session) => session.clone(),
+                _ => continue,
+            };
+            if terminals.contains_key(&session.id) {
+                continue;
+            }
+
+            let mut terminal = self.new_instance(session);
+            for output in &entry.output {
+                terminal.history.push(output.clone());
+            }
+            terminal.record(TerminalOutput::new(
+                "Terminal was interrupted because the application exited unexpectedly".to_string(),
+                OutputType::System,
+            ));
+            terminals.insert(terminal.session.id.clone(), terminal);
+        }
+    }
+
+    /// 重新启动中断的终端，终端已被清除时按原有配置重新加入
+    pub async fn restart_interrupted(&self, interrupted: &InterruptedExecution) -> Result<String, String> {
+        let terminal_id = match interrupted.record {
+            JournalRecord::Terminal(ref session) => session.id.clone(),
+            _ => return Err("Interrupted execution is not a terminal".to_string()),
+        };
+
+        self.restore_interrupted(std::slice::from_ref(interrupted));
+        self.start_terminal(&terminal_id).await?;
+        Ok(terminal_id)
+    }
+
+    /// 在工作区目录中创建终端并记录关联关系
+    pub async fn create_workspace_terminal(
+        &self,
+        name: Option<String>,
+        workspace: TerminalWorkspace,
+        environment: Option<HashMap<String, String>>,
+        shell: Option<ShellOptions>,
+    ) -> Result<String, String> {
+        // 检查工作区终端数量限制
+        if let Some(max_per_workspace) = self.limits.lock().unwrap().max_terminals_per_workspace {
+            let terminals = self.terminals.lock().unwrap();
+            let open_count = terminals
+                .values()
+                .filter(|t| t.is_open() && t.workspace_id() == Some(workspace.workspace_id.as_str()))
+                .count();
+            if open_count >= max_per_workspace {
+                return Err(format!(
+                    "Maximum number of terminals for workspace reached ({})",
+                    max_per_workspace
+                ));
+            }
+        }
+
+        let terminal_id = self
+            .create_terminal(name, workspace.workspace_path.clone(), environment, shell)
+            .await?;
+
+        let mut terminals = self.terminals.lock().unwrap();
+        if let Some(terminal) = terminals.get_mut(&terminal_id) {
+            terminal.session.workspace = Some(workspace);
+        }
+
+        Ok(terminal_id)
+    }
+
     /// 启动终端会话
     pub async fn start_terminal(&self, terminal_id: &str) -> Result<(), String> {
         let mut terminals = self.terminals.lock().unwrap();
@@ -170,27 +900,110 @@ impl TerminalService {
             return Err("Terminal is already active".to_string());
         }
 
-        // 启动shell进程
-        let shell = if cfg!(windows) { "cmd" } else { "sh" };
-        let mut cmd = TokioCommand::new(shell);
-        
-        cmd.current_dir(&terminal.session.working_directory)
+        // 设置输出通道（重启shell时复用同一个通道）
+        let (output_tx, output_rx) = mpsc::unbounded_channel::<TerminalOutput>();
+        let (child, input_tx) = Self::spawn_shell(&terminal.session, &output_tx)?;
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+
+        terminal.pid = child.id();
+        terminal.kill_sender = Some(kill_tx);
+        terminal.input_sender = Some(input_tx);
+        terminal.session.status = TerminalStatus::Active;
+        terminal.session.exit_code = None;
+        terminal.session.exit_signal = None;
+        terminal.session.restart_count = 0;
+        terminal.session.last_activity = Self::current_timestamp();
+        terminal.begin_journal();
+
+        // 添加系统消息
+        let system_msg = TerminalOutput::new(
+            format!("Terminal {} started ({})", terminal_id, terminal.session.shell.program),
+            OutputType::System,
+        );
+        terminal.record(system_msg);
+
+        // 启动输出转发任务，所有输出经由它写入历史并广播
+        tokio::spawn(Self::pump_output(
+            Arc::clone(&self.terminals),
+            terminal_id.to_string(),
+            output_rx,
+        ));
+
+        // 启动监督任务，等待shell进程退出
+        tokio::spawn(Self::supervise_terminal(
+            Arc::clone(&self.terminals),
+            terminal_id.to_string(),
+            child,
+            output_tx,
+            kill_rx,
+        ));
+
+        Ok(())
+    }
+
+    /// 将读取任务产生的输出写入终端，通道关闭（shell和监督任务都已结束）后退出
+    async fn pump_output(
+        terminals: Arc<Mutex<HashMap<String, TerminalInstance>>>,
+        terminal_id: String,
+        mut output_rx: mpsc::UnboundedReceiver<TerminalOutput>,
+    ) {
+        while let Some(output) = output_rx.recv().await {
+            let mut terminals = terminals.lock().unwrap();
+            let terminal = match terminals.get_mut(&terminal_id) {
+                Some(terminal) => terminal,
+                None => return,
+            };
+
+            // 批量处理已到达的输出，减少加锁和刷新录制文件的次数
+            terminal.ingest(output);
+            while let Ok(output) = output_rx.try_recv() {
+                terminal.ingest(output);
+            }
+            terminal.flush_logs();
+        }
+    }
+
+    /// 启动shell进程并连接输入输出
+    fn spawn_shell(
+        session: &TerminalSession,
+        output_tx: &mpsc::UnboundedSender<TerminalOutput>,
+    ) -> Result<(TokioChild, mpsc::UnboundedSender<String>), String> {
+        let shell = &session.shell;
+        let mut cmd = TokioCommand::new(&shell.program);
+
+        cmd.args(Self::shell_arguments(shell))
+            .current_dir(&session.working_directory)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .kill_on_drop(true);
+        // shell作为进程组组长，信号通过进程组发送给前台命令
+        process_inspector::spawn_in_new_group(&mut cmd);
 
-        // 设置环境变量
-        for (key, value) in &terminal.session.environment {
+        // 设置环境变量（工作区变量在前，允许用户环境变量覆盖）
+        if let Some(ref workspace) = session.workspace {
+            for (key, value) in workspace.environment() {
+                cmd.env(key, value);
+            }
+        }
+        for (key, value) in &session.environment {
             cmd.env(key, value);
         }
+        cmd.env(EXECUTION_ID_ENV, &session.id);
 
         let mut child = cmd.spawn()
             .map_err(|e| format!("Failed to start terminal process: {}", e))?;
 
-        // 设置输入输出通道
         let (input_tx, mut input_rx) = mpsc::unbounded_channel::<String>();
-        let (output_tx, output_rx) = mpsc::unbounded_channel::<TerminalOutput>();
+
+        if let Some(kind) = ShellKind::from_program(&shell.program) {
+            let _ = input_tx.send(SHELL_SIGNAL_TRAP.to_string());
+
+            // 注入shell集成脚本
+            if shell.shell_integration {
+                let _ = input_tx.send(shell_integration::init_script(kind, shell.interactive));
+            }
+        }
 
         // 处理标准输入
         if let Some(stdin) = child.stdin.take() {
@@ -214,11 +1027,7 @@ impl TerminalService {
                 let reader = BufReader::new(stdout);
                 let mut lines = reader.lines();
                 while let Ok(Some(line)) = lines.next_line().await {
-                    let output = TerminalOutput {
-                        timestamp: Self::current_timestamp(),
-                        content: line,
-                        output_type: OutputType::Stdout,
-                    };
+                    let output = TerminalOutput::new(line, OutputType::Stdout);
                     if output_tx_clone.send(output).is_err() {
                         break;
                     }
@@ -233,11 +1042,7 @@ impl TerminalService {
                 let reader = BufReader::new(stderr);
                 let mut lines = reader.lines();
                 while let Ok(Some(line)) = lines.next_line().await {
-                    let output = TerminalOutput {
-                        timestamp: Self::current_timestamp(),
-                        content: line,
-                        output_type: OutputType::Stderr,
-                    };
+                    let output = TerminalOutput::new(line, OutputType::Stderr);
                     if output_tx_clone.send(output).is_err() {
                         break;
                     }
@@ -245,26 +1050,121 @@ impl TerminalService {
             });
         }
 
-        terminal.process = Some(child);
-        terminal.input_sender = Some(input_tx);
-        terminal.output_receiver = Some(output_rx);
-        terminal.session.status = TerminalStatus::Active;
-        terminal.session.last_activity = Self::current_timestamp();
+        Ok((child, input_tx))
+    }
 
-        // 添加系统消息
-        let system_msg = TerminalOutput {
-            timestamp: Self::current_timestamp(),
-            content: format!("Terminal {} started", terminal_id),
-            output_type: OutputType::System,
-        };
-        terminal.session.output_history.push(system_msg);
+    /// 监督终端进程：记录退出状态、更新终端状态，并在需要时自动重启shell
+    async fn supervise_terminal(
+        terminals: Arc<Mutex<HashMap<String, TerminalInstance>>>,
+        terminal_id: String,
+        mut child: TokioChild,
+        output_tx: mpsc::UnboundedSender<TerminalOutput>,
+        mut kill_rx: oneshot::Receiver<()>,
+    ) {
+        loop {
+            let exit_status = tokio::select! {
+                status = child.wait() => status,
+                _ = &mut kill_rx => {
+                    // 终端被主动关闭
+                    let _ = child.kill().await;
+                    return;
+                }
+            };
 
-        Ok(())
-    }
+            let (exit_code, exit_signal) = match &exit_status {
+                Ok(status) => (status.code(), Self::exit_signal(status)),
+                Err(_) => (None, None),
+            };
+            let message = match (&exit_status, exit_code, exit_signal) {
+                (Err(e), _, _) => format!("Failed to wait for terminal process: {}", e),
+                (_, Some(code), _) => format!("Process exited with code {}", code),
+                (_, None, Some(signal)) => format!("Process terminated by signal {}", signal),
+                _ => "Process exited".to_string(),
+            };
+            let _ = output_tx.send(TerminalOutput::new(message, OutputType::System));
 
-    /// 向终端发送命令
-    pub async fn send_command(&self, terminal_id: &str, command: &str) -> Result<(), String> {
-        let mut terminals = self.terminals.lock().unwrap();
+            let mut terminals = terminals.lock().unwrap();
+            let terminal = match terminals.get_mut(&terminal_id) {
+                Some(terminal) => terminal,
+                None => return,
+            };
+
+            // 终端已被关闭时不再处理
+            if !matches!(terminal.session.status, TerminalStatus::Active) {
+                return;
+            }
+
+            terminal.session.exit_code = exit_code;
+            terminal.session.exit_signal = exit_signal;
+            terminal.session.last_activity = Self::current_timestamp();
+
+            if terminal.session.auto_restart && terminal.session.restart_count < MAX_AUTO_RESTARTS {
+                terminal.session.restart_count += 1;
+                let restart_msg = format!(
+                    "Restarting shell (attempt {}/{})",
+                    terminal.session.restart_count, MAX_AUTO_RESTARTS
+                );
+
+                match Self::spawn_shell(&terminal.session, &output_tx) {
+                    Ok((new_child, input_tx)) => {
+                        // 输出仍留在通道中供前端读取，这里只结束未完成的命令
+                        let last_seq = terminal.history.last_seq();
+                        terminal.commands.interrupt_all(last_seq, Self::current_timestamp());
+
+                        let _ = output_tx.send(TerminalOutput::new(restart_msg, OutputType::System));
+                        terminal.pid = new_child.id();
+                        terminal.input_sender = Some(input_tx);
+                        terminal.journal.set_pid(&terminal_id, terminal.pid);
+                        child = new_child;
+                        continue;
+                    }
+                    Err(e) => {
+                        let _ = output_tx.send(TerminalOutput::new(e, OutputType::System));
+                    }
+                }
+            }
+
+            let exited_cleanly = matches!(exit_status, Ok(status) if status.success());
+            terminal.session.status = if exited_cleanly {
+                TerminalStatus::Closed
+            } else {
+                TerminalStatus::Error
+            };
+            terminal.pid = None;
+            terminal.kill_sender = None;
+            terminal.input_sender = None;
+            let last_seq = terminal.history.last_seq();
+            terminal.commands.interrupt_all(last_seq, Self::current_timestamp());
+            terminal.end_journal();
+            return;
+        }
+    }
+
+    #[cfg(unix)]
+    fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal()
+    }
+
+    #[cfg(not(unix))]
+    fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+        None
+    }
+
+    /// 设置终端退出后是否自动重启shell
+    pub fn set_auto_restart(&self, terminal_id: &str, auto_restart: bool) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        terminal.session.auto_restart = auto_restart;
+        Ok(())
+    }
+
+    /// 向终端发送命令
+    pub async fn send_command(&self, terminal_id: &str, command: &str) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
         let terminal = terminals
             .get_mut(terminal_id)
             .ok_or("Terminal not found")?;
@@ -273,23 +1173,37 @@ impl TerminalService {
             return Err("Terminal is not active".to_string());
         }
 
-        if let Some(ref input_sender) = terminal.input_sender {
-            let command_with_newline = format!("{}\n", command);
-            input_sender.send(command_with_newline)
+        if let Some(input_sender) = terminal.input_sender.clone() {
+            // 非交互shell没有提示符，需要在同一命令行中调用钩子报告退出码
+            let shell = &terminal.session.shell;
+            let append_hook = shell.shell_integration
+                && !ShellKind::from_program(&shell.program)
+                    .map(|kind| shell_integration::hook_runs_automatically(kind, shell.interactive))
+                    .unwrap_or(false);
+            let command_line = shell_integration::command_line(command, append_hook);
+            input_sender.send(command_line)
                 .map_err(|_| "Failed to send command to terminal")?;
 
             // 记录输入命令到历史
-            let input_output = TerminalOutput {
-                timestamp: Self::current_timestamp(),
-                content: command.to_string(),
-                output_type: OutputType::Input,
-            };
-            terminal.session.output_history.push(input_output);
+            let input_output = TerminalOutput::new(command.to_string(), OutputType::Input);
+            let input_output = terminal.record(input_output);
             terminal.session.last_activity = Self::current_timestamp();
 
-            // 限制历史记录大小
-            if terminal.session.output_history.len() > self.max_history_size {
-                terminal.session.output_history.drain(..100); // 删除最老的100条记录
+            // 写入工作区命令历史，退出码由shell集成在命令结束后补充
+            if let Some(workspace_id) = terminal.workspace_id() {
+                let cwd = terminal
+                    .commands
+                    .cwd()
+                    .cloned()
+                    .unwrap_or_else(|| terminal.session.working_directory.clone());
+                if let Err(e) = self.command_history.record(workspace_id, command, None, Some(cwd)) {
+                    eprintln!("警告: 写入命令历史失败: {}", e);
+                }
+            }
+
+            // 开始新的命令块
+            if terminal.session.shell.shell_integration {
+                terminal.commands.begin(command, input_output.seq, input_output.timestamp);
             }
         } else {
             return Err("Terminal input channel not available".to_string());
@@ -298,45 +1212,243 @@ impl TerminalService {
         Ok(())
     }
 
+    /// 向终端写入原始输入（不追加换行）
+    pub async fn send_input(&self, terminal_id: &str, data: &str) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        if !matches!(terminal.session.status, TerminalStatus::Active) {
+            return Err("Terminal is not active".to_string());
+        }
+
+        let input_sender = terminal
+            .input_sender
+            .as_ref()
+            .ok_or("Terminal input channel not available")?;
+        input_sender.send(data.to_string())
+            .map_err(|_| "Failed to send input to terminal")?;
+
+        terminal.session.last_activity = Self::current_timestamp();
+        Ok(())
+    }
+
+    /// 向终端发送控制按键
+    pub async fn send_control_key(&self, terminal_id: &str, key: ControlKey) -> Result<(), String> {
+        match key {
+            ControlKey::CtrlC => self.send_signal(terminal_id, TerminalSignal::SigInt).await,
+            ControlKey::CtrlZ => self.send_signal(terminal_id, TerminalSignal::SigTstp).await,
+            // CtrlD 作为普通输入发送，不关闭shell的标准输入，终端之后仍可继续使用
+            _ => self.send_input(terminal_id, Self::control_sequence(key)).await,
+        }
+    }
+
+    /// 控制按键对应的转义序列
+    fn control_sequence(key: ControlKey) -> &'static str {
+        match key {
+            ControlKey::CtrlC => "\x03",
+            ControlKey::CtrlD => "\x04",
+            ControlKey::CtrlZ => "\x1a",
+            ControlKey::ArrowUp => "\x1b[A",
+            ControlKey::ArrowDown => "\x1b[B",
+            ControlKey::ArrowRight => "\x1b[C",
+            ControlKey::ArrowLeft => "\x1b[D",
+            ControlKey::Tab => "\t",
+            ControlKey::Escape => "\x1b",
+            ControlKey::Enter => "\n",
+        }
+    }
+
+    /// 向终端的前台进程发送信号
+    ///
+    /// 终端没有作业控制，shell启动的命令与shell处于同一进程组，信号发送给整个进程组。
+    /// shell自身捕获 SIGINT/SIGTERM/SIGTSTP 因而不受影响；按POSIX规定，没有作业控制时
+    /// 后台命令忽略 SIGINT，因此 Ctrl-C 只结束前台命令。无法设置信号捕获的shell不支持这些信号。
+    pub async fn send_signal(&self, terminal_id: &str, signal: TerminalSignal) -> Result<(), String> {
+        let (pid, shell) = {
+            let mut terminals = self.terminals.lock().unwrap();
+            let terminal = terminals
+                .get_mut(terminal_id)
+                .ok_or("Terminal not found")?;
+
+            if !matches!(terminal.session.status, TerminalStatus::Active) {
+                return Err("Terminal is not active".to_string());
+            }
+
+            terminal.session.last_activity = Self::current_timestamp();
+            let pid = terminal.pid.ok_or("Terminal process not available")?;
+            (pid, terminal.session.shell.program.clone())
+        };
+
+        Self::signal_foreground(pid, &shell, signal)?;
+
+        let mut terminals = self.terminals.lock().unwrap();
+        if let Some(terminal) = terminals.get_mut(terminal_id) {
+            terminal.record(TerminalOutput::new(
+                format!("Sent {} to terminal", signal.name()),
+                OutputType::System,
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn signal_foreground(shell_pid: u32, shell_program: &str, signal: TerminalSignal) -> Result<(), String> {
+        let signo = match signal {
+            TerminalSignal::SigInt => libc::SIGINT,
+            TerminalSignal::SigTerm => libc::SIGTERM,
+            TerminalSignal::SigKill => libc::SIGKILL,
+            TerminalSignal::SigTstp => libc::SIGTSTP,
+            TerminalSignal::SigHup => libc::SIGHUP,
+        };
+
+        let shell_survives = matches!(
+            signal,
+            TerminalSignal::SigInt | TerminalSignal::SigTerm | TerminalSignal::SigTstp
+        );
+        if shell_survives && ShellKind::from_program(shell_program).is_none() {
+            return Err(format!("{} is not supported for this shell", signal.name()));
+        }
+
+        // shell启动时已成为进程组组长，进程组ID即shell的PID
+        if process_inspector::signal_group(shell_pid, signo) {
+            Ok(())
+        } else {
+            Err(format!("Failed to send signal: {}", std::io::Error::last_os_error()))
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn signal_foreground(_shell_pid: u32, _shell_program: &str, _signal: TerminalSignal) -> Result<(), String> {
+        Err("Signals are not supported on this platform".to_string())
+    }
+
     /// 执行单个命令并等待结果
-    pub async fn execute_command(&self, execution: CommandExecution) -> Result<TerminalOutput, String> {
+    pub async fn execute_command(&self, execution: CommandExecution) -> Result<CommandExecutionResult, String> {
         // 验证工作目录
         if !execution.working_directory.exists() || !execution.working_directory.is_dir() {
             return Err(format!("Invalid working directory: {:?}", execution.working_directory));
         }
 
-        let mut cmd = TokioCommand::new(&execution.command);
-        cmd.args(&execution.args)
-            .current_dir(&execution.working_directory)
+        let mut cmd = if execution.use_shell {
+            let shell = self.get_default_shell();
+            let mut command_line = execution.command.clone();
+            for arg in &execution.args {
+                command_line.push(' ');
+                command_line.push_str(&Self::shell_quote(arg));
+            }
+
+            let mut cmd = TokioCommand::new(&shell.program);
+            if cfg!(windows) {
+                cmd.arg("/C");
+            } else {
+                cmd.arg("-c");
+            }
+            cmd.arg(command_line);
+            cmd
+        } else {
+            let mut cmd = TokioCommand::new(&execution.command);
+            cmd.args(&execution.args);
+            cmd
+        };
+
+        cmd.current_dir(&execution.working_directory)
+            .stdin(if execution.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // shell启动的实际命令在同一进程组中，超时时一并结束
+        process_inspector::spawn_in_new_group(&mut cmd);
 
         // 设置环境变量
         for (key, value) in &execution.environment {
             cmd.env(key, value);
         }
 
-        let output = cmd.output()
-            .await
+        let started = std::time::Instant::now();
+        let mut child = cmd.spawn()
             .map_err(|e| format!("Failed to execute command: {}", e))?;
 
-        let result_content = if output.status.success() {
-            String::from_utf8_lossy(&output.stdout).to_string()
-        } else {
-            String::from_utf8_lossy(&output.stderr).to_string()
+        // 写入标准输入后关闭，避免进程等待输入
+        if let (Some(input), Some(mut stdin)) = (execution.stdin, child.stdin.take()) {
+            tokio::spawn(async move {
+                let _ = stdin.write_all(input.as_bytes()).await;
+            });
+        }
+
+        // 并行读取两个输出流，防止管道写满导致进程阻塞
+        let stdout_capture = child.stdout.take().map(StreamCapture::spawn);
+        let stderr_capture = child.stderr.take().map(StreamCapture::spawn);
+
+        let wait_result = match execution.timeout_ms {
+            Some(timeout_ms) => {
+                tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), child.wait()).await
+            }
+            None => Ok(child.wait().await),
         };
 
-        let terminal_output = TerminalOutput {
-            timestamp: Self::current_timestamp(),
-            content: result_content,
-            output_type: if output.status.success() {
-                OutputType::Stdout
-            } else {
-                OutputType::Stderr
-            },
+        let (status, timed_out) = match wait_result {
+            Ok(status) => (status.map_err(|e| format!("Failed to wait for command: {}", e))?, false),
+            Err(_) => {
+                #[cfg(unix)]
+                if let Some(pid) = child.id() {
+                    process_inspector::signal_group(pid, libc::SIGKILL);
+                }
+                let _ = child.kill().await;
+                let status = child.wait()
+                    .await
+                    .map_err(|e| format!("Failed to wait for command: {}", e))?;
+                (status, true)
+            }
         };
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let stdout = output_capture::collect(stdout_capture).await;
+        let stderr = output_capture::collect(stderr_capture).await;
+
+        if let Some(ref workspace_id) = execution.workspace_id {
+            let mut command_line = execution.command.clone();
+            for arg in &execution.args {
+                command_line.push(' ');
+                command_line.push_str(&Self::shell_quote(arg));
+            }
+            let exit_code = if timed_out { None } else { status.code() };
+            if let Err(e) = self.command_history.record(
+                workspace_id,
+                &command_line,
+                exit_code,
+                Some(execution.working_directory.clone()),
+            ) {
+                eprintln!("警告: 写入命令历史失败: {}", e);
+            }
+        }
+
+        Ok(CommandExecutionResult {
+            stdout,
+            stderr,
+            exit_code: status.code(),
+            signal: Self::exit_signal(&status),
+            duration_ms,
+            timed_out,
+            success: status.success() && !timed_out,
+        })
+    }
+
+    /// 为shell命令行转义参数
+    fn shell_quote(arg: &str) -> String {
+        if cfg!(windows) {
+            return format!("\"{}\"", arg.replace('"', "\\\""));
+        }
 
-        Ok(terminal_output)
+        let is_safe = !arg.is_empty()
+            && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+        if is_safe {
+            arg.to_string()
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
     }
 
     /// 获取终端输出
@@ -346,153 +1458,692 @@ impl TerminalService {
             .get_mut(terminal_id)
             .ok_or("Terminal not found")?;
 
-        let mut new_outputs = Vec::new();
+        // 按游标返回尚未读取的记录（输入回显除外）
+        let chunk = terminal.history.since(terminal.output_cursor, None);
+        terminal.output_cursor = chunk.next_seq - 1;
 
-        // 获取新的输出
-        if let Some(ref mut output_receiver) = terminal.output_receiver {
-            while let Ok(output) = output_receiver.try_recv() {
-                new_outputs.push(output.clone());
-                terminal.session.output_history.push(output);
-            }
-        }
+        let new_outputs = chunk
+            .entries
+            .into_iter()
+            .filter(|o| o.output_type != OutputType::Input)
+            .collect();
 
-        // 更新最后活动时间
-        if !new_outputs.is_empty() {
-            terminal.session.last_activity = Self::current_timestamp();
-        }
+        Ok(new_outputs)
+    }
+
+    /// 附加客户端到终端，返回从 `from_seq` 之后开始的历史和实时输出订阅
+    ///
+    /// `from_seq` 为空时只接收附加之后的新输出。
+    pub fn attach(&self, terminal_id: &str, from_seq: Option<u64>) -> Result<TerminalAttachment, String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        // 在同一把锁内获取历史和订阅，保证两者之间不丢失也不重复输出
+        Ok(terminal.attach(from_seq))
+    }
 
-        // 限制历史记录大小
-        if terminal.session.output_history.len() > self.max_history_size {
-            terminal.session.output_history.drain(..100);
+    /// 分离客户端
+    pub fn detach(&self, terminal_id: &str, subscriber_id: &str) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        if terminal.detach(subscriber_id) {
+            Ok(())
+        } else {
+            Err("Subscriber not found".to_string())
         }
+    }
 
-        Ok(new_outputs)
+    /// 按客户端各自的游标拉取新输出，供不使用推送的客户端轮询
+    pub fn poll_subscriber(&self, terminal_id: &str, subscriber_id: &str) -> Result<HistoryChunk, String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        let cursor = terminal
+            .subscribers
+            .get(subscriber_id)
+            .ok_or("Subscriber not found")?
+            .cursor;
+        let chunk = terminal.history.since(cursor, None);
+
+        if let Some(subscriber) = terminal.subscribers.get_mut(subscriber_id) {
+            subscriber.cursor = chunk.next_seq - 1;
+        }
+        Ok(chunk)
     }
 
     /// 获取终端历史记录
     pub fn get_terminal_history(&self, terminal_id: &str) -> Result<Vec<TerminalOutput>, String> {
-        let terminals = self.terminals.lock().unwrap();
+        let mut terminals = self.terminals.lock().unwrap();
         let terminal = terminals
-            .get(terminal_id)
+            .get_mut(terminal_id)
             .ok_or("Terminal not found")?;
 
-        Ok(terminal.session.output_history.clone())
+        Ok(terminal.history.entries())
     }
 
-    /// 关闭终端
-    pub async fn close_terminal(&self, terminal_id: &str) -> Result<(), String> {
-        let mut process_to_kill = None;
-        
-        // 获取需要关闭的进程
-        {
-            let mut terminals = self.terminals.lock().unwrap();
-            let terminal = terminals
-                .get_mut(terminal_id)
-                .ok_or("Terminal not found")?;
+    /// 增量获取序列号大于 `seq` 的历史记录
+    pub fn get_terminal_history_since(
+        &self,
+        terminal_id: &str,
+        seq: u64,
+        limit: Option<usize>,
+    ) -> Result<HistoryChunk, String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
 
-            // 取出进程以便在锁外关闭
-            process_to_kill = terminal.process.take();
-            
-            terminal.session.status = TerminalStatus::Closed;
-            terminal.session.last_activity = Self::current_timestamp();
+        Ok(terminal.history.since(seq, limit))
+    }
 
-            // 添加关闭消息
-            let close_msg = TerminalOutput {
-                timestamp: Self::current_timestamp(),
-                content: format!("Terminal {} closed", terminal_id),
-                output_type: OutputType::System,
-            };
-            terminal.session.output_history.push(close_msg);
-        }
-        
-        // 在锁外关闭进程
-        if let Some(mut process) = process_to_kill {
-            let _ = process.kill().await;
+    /// 在一个终端或一个工作区的所有终端中搜索历史记录
+    pub fn search_terminal_history(
+        &self,
+        scope: SearchScope,
+        query: HistorySearchQuery,
+    ) -> Result<Vec<TerminalSearchResult>, String> {
+        let matcher = HistoryMatcher::new(&query)?;
+        let mut remaining = query.max_results.unwrap_or(usize::MAX);
+
+        let mut terminals = self.terminals.lock().unwrap();
+        let mut targets: Vec<&mut TerminalInstance> = match &scope {
+            SearchScope::Terminal { terminal_id } => {
+                vec![terminals.get_mut(terminal_id).ok_or("Terminal not found")?]
+            }
+            SearchScope::Workspace { workspace_path } => terminals
+                .values_mut()
+                .filter(|t| t.session.working_directory.starts_with(workspace_path))
+                .collect(),
+        };
+        targets.sort_by_key(|t| t.session.created_at);
+
+        let mut results = Vec::new();
+        for terminal in targets {
+            if remaining == 0 {
+                break;
+            }
+
+            let matches = terminal.history.search(&matcher, remaining);
+            if matches.is_empty() {
+                continue;
+            }
+
+            remaining -= matches.len();
+            results.push(TerminalSearchResult {
+                terminal_id: terminal.session.id.clone(),
+                terminal_name: terminal.session.name.clone(),
+                matches,
+            });
         }
 
-        Ok(())
+        Ok(results)
     }
 
-    /// 获取终端会话信息
-    pub fn get_terminal_session(&self, terminal_id: &str) -> Option<TerminalSession> {
-        let terminals = self.terminals.lock().unwrap();
-        terminals.get(terminal_id).map(|t| t.session.clone())
-    }
+    /// 开始将终端会话录制为 asciicast v2 文件
+    pub fn start_recording(&self, terminal_id: &str, logs_dir: &std::path::Path) -> Result<PathBuf, String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
 
-    /// 获取所有终端会话
-    pub fn get_all_terminals(&self) -> Vec<TerminalSession> {
-        let terminals = self.terminals.lock().unwrap();
-        terminals.values().map(|t| t.session.clone()).collect()
+        if terminal.recorder.is_some() {
+            return Err("Terminal is already being recorded".to_string());
+        }
+
+        let recorder = AsciicastRecorder::create(logs_dir, &terminal.session, Self::current_timestamp())?;
+        let path = recorder.path().to_path_buf();
+        terminal.recorder = Some(recorder);
+        terminal.session.recording_path = Some(path.clone());
+
+        Ok(path)
     }
 
-    /// 清理已关闭的终端
-    pub fn cleanup_closed_terminals(&self) {
+    /// 停止录制，返回录制文件路径
+    pub fn stop_recording(&self, terminal_id: &str) -> Result<Option<PathBuf>, String> {
         let mut terminals = self.terminals.lock().unwrap();
-        terminals.retain(|_, terminal| {
-            !matches!(terminal.session.status, TerminalStatus::Closed)
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        let path = terminal.recorder.take().map(|mut recorder| {
+            let _ = recorder.flush();
+            recorder.path().to_path_buf()
         });
+        terminal.session.recording_path = None;
+
+        Ok(path)
     }
 
-    /// 设置终端名称
-    pub fn set_terminal_name(&self, terminal_id: &str, name: String) -> Result<(), String> {
+    /// 获取shell集成记录的命令块
+    pub fn get_terminal_commands(&self, terminal_id: &str) -> Result<Vec<TerminalCommand>, String> {
         let mut terminals = self.terminals.lock().unwrap();
         let terminal = terminals
             .get_mut(terminal_id)
             .ok_or("Terminal not found")?;
 
-        terminal.session.name = name;
-        terminal.session.last_activity = Self::current_timestamp();
+        Ok(terminal.commands.commands())
+    }
+
+    /// 设置终端历史记录的内存上限（字节）
+    pub fn set_history_limit(&self, terminal_id: &str, max_bytes: usize) -> Result<(), String> {
+        if max_bytes == 0 {
+            return Err("History limit must be greater than zero".to_string());
+        }
+
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
 
+        terminal.history.set_max_bytes(max_bytes);
+        terminal.session.history_limit_bytes = max_bytes;
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::env;
+    /// 关闭终端
+    pub async fn close_terminal(&self, terminal_id: &str) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
 
-    #[tokio::test]
-    async fn test_create_terminal() {
-        let service = TerminalService::new();
-        let working_dir = env::temp_dir();
+        terminal.session.status = TerminalStatus::Closed;
+        terminal.session.last_activity = Self::current_timestamp();
 
-        let terminal_id = service
-            .create_terminal(Some("Test Terminal".to_string()), working_dir, None)
-            .await
-            .unwrap();
+        // 通知监督任务结束进程
+        if let Some(kill_sender) = terminal.kill_sender.take() {
+            let _ = kill_sender.send(());
+        }
+        terminal.pid = None;
+        terminal.input_sender = None;
 
-        let session = service.get_terminal_session(&terminal_id).unwrap();
-        assert_eq!(session.name, "Test Terminal");
-        assert_eq!(session.status, TerminalStatus::Inactive);
-    }
+        // 未结束的命令标记为中断
+        let last_seq = terminal.history.last_seq();
+        terminal.commands.interrupt_all(last_seq, Self::current_timestamp());
 
-    #[tokio::test]
-    async fn test_execute_command() {
-        let service = TerminalService::new();
-        let working_dir = env::temp_dir();
+        // 添加关闭消息
+        let close_msg = TerminalOutput::new(
+            format!("Terminal {} closed", terminal_id),
+            OutputType::System,
+        );
+        terminal.record(close_msg);
+        terminal.end_journal();
 
-        let execution = CommandExecution {
-            command: "echo".to_string(),
-            args: vec!["Hello World".to_string()],
-            working_directory: working_dir,
-            environment: HashMap::new(),
-        };
+        // 结束录制
+        terminal.recorder = None;
+        terminal.session.recording_path = None;
 
-        let output = service.execute_command(execution).await.unwrap();
-        assert!(output.content.contains("Hello World"));
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn test_terminal_lifecycle() {
-        let service = TerminalService::new();
-        let working_dir = env::temp_dir();
+    /// 获取工作区的所有终端，按创建时间排序
+    pub fn get_workspace_terminals(&self, workspace_id: &str) -> Vec<TerminalSession> {
+        let terminals = self.terminals.lock().unwrap();
+        let mut sessions: Vec<TerminalSession> = terminals
+            .values()
+            .filter(|t| {
+                t.session
+                    .workspace
+                    .as_ref()
+                    .map(|w| w.workspace_id == workspace_id)
+                    .unwrap_or(false)
+            })
+            .map(|t| t.session.clone())
+            .collect();
+
+        sessions.sort_by_key(|s| s.created_at);
+        sessions
+    }
 
-        // 创建终端
-        let terminal_id = service
-            .create_terminal(None, working_dir, None)
-            .await
-            .unwrap();
+    /// 获取工作区中仍在运行的终端
+    pub fn get_active_workspace_terminals(&self, workspace_id: &str) -> Vec<TerminalSession> {
+        self.get_workspace_terminals(workspace_id)
+            .into_iter()
+            .filter(|s| matches!(s.status, TerminalStatus::Active))
+            .collect()
+    }
+
+    /// 关闭工作区中所有运行中的终端，返回被关闭的终端ID
+    ///
+    /// 已出错退出或中断的终端保留原有状态，不改写为 Closed。
+    pub async fn close_workspace_terminals(&self, workspace_id: &str) -> Result<Vec<String>, String> {
+        let mut closed = Vec::new();
+        for session in self.get_active_workspace_terminals(workspace_id) {
+            self.close_terminal(&session.id).await?;
+            closed.push(session.id);
+        }
+        Ok(closed)
+    }
+
+    /// 获取终端shell及其所有子进程的进程树
+    pub fn get_process_tree(&self, terminal_id: &str) -> Result<Option<ProcessTree>, String> {
+        let pid = {
+            let terminals = self.terminals.lock().unwrap();
+            let terminal = terminals
+                .get(terminal_id)
+                .ok_or("Terminal not found")?;
+            terminal.pid
+        };
+
+        match pid {
+            Some(pid) => process_inspector::process_tree(pid).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 获取工作区所有终端的进程监听的端口
+    pub fn get_workspace_ports(&self, workspace_id: &str) -> Vec<WorkspacePort> {
+        let mut ports = Vec::new();
+
+        for session in self.get_active_workspace_terminals(workspace_id) {
+            // 进程可能已退出，跳过无法读取的终端
+            let tree = match self.get_process_tree(&session.id) {
+                Ok(Some(tree)) => tree,
+                _ => continue,
+            };
+            for port in tree.ports {
+                ports.push(WorkspacePort {
+                    terminal_id: session.id.clone(),
+                    terminal_name: session.name.clone(),
+                    port,
+                });
+            }
+        }
+
+        ports
+    }
+
+    /// 仍有命令或子进程在运行的活动终端
+    pub fn busy_terminals(&self) -> Vec<TerminalSession> {
+        let active: Vec<(TerminalSession, bool, Option<u32>)> = {
+            let terminals = self.terminals.lock().unwrap();
+            terminals
+                .values()
+                .filter(|t| matches!(t.session.status, TerminalStatus::Active))
+                .map(|t| (t.session.clone(), t.commands.has_running(), t.pid))
+                .collect()
+        };
+
+        active
+            .into_iter()
+            .filter(|(_, running, pid)| *running || Self::has_child_processes(*pid))
+            .map(|(session, _, _)| session)
+            .collect()
+    }
+
+    /// shell的进程组中是否还有其他进程（没有shell集成时只能通过子进程判断是否忙碌）。
+    /// 需要遍历进程表，不应在持有终端锁时调用
+    fn has_child_processes(pid: Option<u32>) -> bool {
+        pid.map(process_inspector::group_has_other_members).unwrap_or(false)
+    }
+
+    /// 关闭所有终端：向shell的进程组发送 SIGHUP，等待退出，超时后强制结束并写出录制文件
+    pub async fn shutdown(&self, timeout: std::time::Duration) -> TerminalShutdownReport {
+        let active: Vec<(String, Option<u32>)> = {
+            let mut terminals = self.terminals.lock().unwrap();
+            terminals
+                .iter_mut()
+                .filter(|(_, t)| matches!(t.session.status, TerminalStatus::Active))
+                .map(|(id, t)| {
+                    // 关闭过程中不再自动重启
+                    t.session.auto_restart = false;
+                    (id.clone(), t.pid)
+                })
+                .collect()
+        };
+
+        #[cfg(unix)]
+        for (_, pid) in &active {
+            if let Some(pid) = pid {
+                process_inspector::signal_group(*pid, libc::SIGHUP);
+            }
+        }
+
+        let still_active = || -> Vec<(String, Option<u32>)> {
+            let terminals = self.terminals.lock().unwrap();
+            active
+                .iter()
+                .filter(|(id, _)| {
+                    terminals
+                        .get(id)
+                        .map(|t| matches!(t.session.status, TerminalStatus::Active))
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect()
+        };
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !still_active().is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        // 超时仍未退出的终端强制结束
+        let remaining = still_active();
+        for (terminal_id, pid) in &remaining {
+            #[cfg(unix)]
+            if let Some(pid) = pid {
+                process_inspector::signal_group(*pid, libc::SIGKILL);
+            }
+            let _ = self.close_terminal(terminal_id).await;
+        }
+
+        // 写出所有录制文件
+        {
+            let mut terminals = self.terminals.lock().unwrap();
+            for terminal in terminals.values_mut() {
+                terminal.flush_logs();
+                terminal.recorder = None;
+                terminal.session.recording_path = None;
+            }
+        }
+
+        // 写出尚未保存的命令历史
+        let command_history = Arc::clone(&self.command_history);
+        let _ = tokio::task::spawn_blocking(move || command_history.flush()).await;
+
+        TerminalShutdownReport {
+            closed: active.len() - remaining.len(),
+            killed: remaining.len(),
+        }
+    }
+
+    /// 工作区归档或删除前检查其终端
+    ///
+    /// `close_terminals` 为 false 且仍有运行中的终端时返回错误，由前端提示用户确认后重试。
+    /// 终端在归档或删除成功后再通过 close_workspace_terminals 关闭，操作失败时保留用户的shell。
+    pub fn check_workspace_terminals(&self, workspace_id: &str, close_terminals: bool) -> Result<(), String> {
+        if !close_terminals {
+            let active = self.get_active_workspace_terminals(workspace_id);
+            if !active.is_empty() {
+                return Err(format!("Workspace has {} running terminal(s)", active.len()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 获取终端限制与回收策略
+    pub fn get_limits(&self) -> TerminalLimits {
+        self.limits.lock().unwrap().clone()
+    }
+
+    /// 更新终端限制与回收策略
+    pub fn set_limits(&self, limits: TerminalLimits) -> Result<(), String> {
+        if limits.max_terminals == 0 {
+            return Err("Maximum number of terminals must be greater than zero".to_string());
+        }
+        if limits.max_terminals_per_workspace == Some(0) {
+            return Err("Per-workspace terminal limit must be greater than zero".to_string());
+        }
+        if limits.reap_interval_seconds == 0 {
+            return Err("Reap interval must be greater than zero".to_string());
+        }
+
+        *self.limits.lock().unwrap() = limits;
+        Ok(())
+    }
+
+    /// 执行一次回收检查：警告并关闭空闲终端，清除超过保留时间的已关闭终端
+    pub async fn reap_terminals(&self) -> ReapReport {
+        let limits = self.get_limits();
+        let now = Self::current_timestamp();
+        let mut report = ReapReport::default();
+        let mut to_close = Vec::new();
+        let timeout_ms = limits.idle_timeout_minutes.map(|minutes| minutes.saturating_mul(60_000));
+        let warning_ms = limits.idle_warning_seconds.saturating_mul(1000);
+
+        // 找出进入警告或关闭窗口、且没有正在执行命令的终端
+        let candidates: Vec<(String, Option<u32>)> = match timeout_ms {
+            Some(timeout_ms) => {
+                let mut terminals = self.terminals.lock().unwrap();
+                terminals
+                    .iter_mut()
+                    .filter(|(_, t)| matches!(t.session.status, TerminalStatus::Active))
+                    .filter_map(|(terminal_id, terminal)| {
+                        let last_activity = terminal.session.last_activity;
+                        if terminal.idle_warned_at.map(|at| at < last_activity).unwrap_or(false) {
+                            terminal.idle_warned_at = None;
+                        }
+                        let idle_ms = now.saturating_sub(last_activity);
+                        if idle_ms + warning_ms < timeout_ms || terminal.commands.has_running() {
+                            return None;
+                        }
+                        Some((terminal_id.clone(), terminal.pid))
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        // 仍在运行的子进程（例如没有输出的开发服务器）不算空闲，检查进程表时不持有终端锁
+        let idle: Vec<String> = candidates
+            .into_iter()
+            .filter(|(_, pid)| !Self::has_child_processes(*pid))
+            .map(|(terminal_id, _)| terminal_id)
+            .collect();
+
+        {
+            let mut terminals = self.terminals.lock().unwrap();
+
+            if let (Some(idle_minutes), Some(timeout_ms)) = (limits.idle_timeout_minutes, timeout_ms) {
+                for terminal_id in idle {
+                    let terminal = match terminals.get_mut(&terminal_id) {
+                        Some(terminal) => terminal,
+                        None => continue,
+                    };
+                    // 检查子进程期间终端可能已被关闭或有了新的活动
+                    if !matches!(terminal.session.status, TerminalStatus::Active) || terminal.commands.has_running() {
+                        continue;
+                    }
+                    let idle_ms = now.saturating_sub(terminal.session.last_activity);
+                    if idle_ms + warning_ms < timeout_ms {
+                        continue;
+                    }
+
+                    if idle_ms >= timeout_ms {
+                        terminal.record(TerminalOutput::new(
+                            format!("Terminal closed after {} minutes of inactivity", idle_minutes),
+                            OutputType::System,
+                        ));
+                        to_close.push(terminal_id);
+                    } else if terminal.idle_warned_at.is_none() {
+                        let remaining = (timeout_ms - idle_ms).div_ceil(1000);
+                        terminal.record(TerminalOutput::new(
+                            format!("Terminal is idle and will be closed in {} seconds", remaining),
+                            OutputType::System,
+                        ));
+                        terminal.idle_warned_at = Some(now);
+                        report.warned.push(terminal_id);
+                    }
+                }
+            }
+
+            if let Some(retention_minutes) = limits.closed_retention_minutes {
+                let retention_ms = retention_minutes.saturating_mul(60_000);
+                terminals.retain(|terminal_id, terminal| {
+                    let expired = matches!(terminal.session.status, TerminalStatus::Closed)
+                        && now.saturating_sub(terminal.session.last_activity) >= retention_ms;
+                    if expired {
+                        report.purged.push(terminal_id.clone());
+                    }
+                    !expired
+                });
+            }
+        }
+
+        for terminal_id in to_close {
+            if self.close_terminal(&terminal_id).await.is_ok() {
+                report.closed.push(terminal_id);
+            }
+        }
+
+        report
+    }
+
+    /// 后台回收任务，按策略中的间隔周期性执行 `reap_terminals`
+    pub async fn run_reaper(self: Arc<Self>) {
+        loop {
+            let interval = self.get_limits().reap_interval_seconds.max(1);
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+
+            self.reap_terminals().await;
+        }
+    }
+
+    /// 获取终端会话信息
+    pub fn get_terminal_session(&self, terminal_id: &str) -> Option<TerminalSession> {
+        let terminals = self.terminals.lock().unwrap();
+        terminals.get(terminal_id).map(|t| t.session.clone())
+    }
+
+    /// 获取所有终端会话
+    pub fn get_all_terminals(&self) -> Vec<TerminalSession> {
+        let terminals = self.terminals.lock().unwrap();
+        terminals.values().map(|t| t.session.clone()).collect()
+    }
+
+    /// 清理已关闭的终端
+    pub fn cleanup_closed_terminals(&self) {
+        let mut terminals = self.terminals.lock().unwrap();
+        terminals.retain(|_, terminal| {
+            !matches!(terminal.session.status, TerminalStatus::Closed)
+        });
+    }
+
+    /// 设置终端名称
+    pub fn set_terminal_name(&self, terminal_id: &str, name: String) -> Result<(), String> {
+        let mut terminals = self.terminals.lock().unwrap();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or("Terminal not found")?;
+
+        terminal.session.name = name;
+        terminal.session.last_activity = Self::current_timestamp();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::shell_integration::CommandStatus;
+    use crate::services::terminal_recording::{list_recordings, replay_recording, resolve_recording, ReplayOptions};
+    use std::env;
+
+    #[tokio::test]
+    async fn test_create_terminal() {
+        let service = TerminalService::new();
+        let working_dir = env::temp_dir();
+
+        let terminal_id = service
+            .create_terminal(Some("Test Terminal".to_string()), working_dir, None, None)
+            .await
+            .unwrap();
+
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.name, "Test Terminal");
+        assert_eq!(session.status, TerminalStatus::Inactive);
+    }
+
+    #[tokio::test]
+    async fn test_execute_command() {
+        let service = TerminalService::new();
+        let working_dir = env::temp_dir();
+
+        let execution = CommandExecution {
+            command: "echo".to_string(),
+            args: vec!["Hello World".to_string()],
+            working_directory: working_dir,
+            environment: HashMap::new(),
+            timeout_ms: None,
+            stdin: None,
+            use_shell: false,
+            workspace_id: None,
+        };
+
+        let result = service.execute_command(execution).await.unwrap();
+        assert!(result.stdout.contains("Hello World"));
+        assert_eq!(result.exit_code, Some(0));
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_options() {
+        let service = TerminalService::new();
+        service.set_default_shell(Some("sh".to_string()), Vec::new()).unwrap();
+
+        let execution = |command: &str, args: Vec<&str>| CommandExecution {
+            command: command.to_string(),
+            args: args.into_iter().map(String::from).collect(),
+            working_directory: env::temp_dir(),
+            environment: HashMap::new(),
+            timeout_ms: None,
+            stdin: None,
+            use_shell: true,
+            workspace_id: None,
+        };
+
+        // 同时保留两个输出流和退出码
+        let result = service
+            .execute_command(execution("echo out; echo err >&2; exit 3", Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(result.stdout.trim(), "out");
+        assert_eq!(result.stderr.trim(), "err");
+        assert_eq!(result.exit_code, Some(3));
+        assert!(!result.success);
+
+        // 参数按原样传递
+        let result = service
+            .execute_command(execution("printf '%s|'", vec!["a b", "it's"]))
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, "a b|it's|");
+
+        let mut with_stdin = execution("cat", Vec::new());
+        with_stdin.use_shell = false;
+        with_stdin.stdin = Some("from stdin".to_string());
+        let result = service.execute_command(with_stdin).await.unwrap();
+        assert_eq!(result.stdout, "from stdin");
+
+        let mut slow = execution("sleep 5", Vec::new());
+        slow.timeout_ms = Some(200);
+        let result = service.execute_command(slow).await.unwrap();
+        assert!(result.timed_out);
+        assert!(!result.success);
+        assert!(result.duration_ms < 5000);
+        #[cfg(unix)]
+        assert_eq!(result.signal, Some(libc::SIGKILL));
+
+        // 超时后保留已有输出，shell启动的子进程一并结束
+        let mut partial = execution("echo started; sleep 30; echo done", Vec::new());
+        partial.timeout_ms = Some(300);
+        partial.environment.insert("WORKHORSE_TEST_MARKER".to_string(), "partial-output".to_string());
+        let result = service.execute_command(partial).await.unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.stdout, "started\n");
+        #[cfg(target_os = "linux")]
+        assert!(process_inspector::processes_with_env("WORKHORSE_TEST_MARKER", "partial-output").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_terminal_lifecycle() {
+        let service = TerminalService::new();
+        let working_dir = env::temp_dir();
+
+        // 创建终端
+        let terminal_id = service
+            .create_terminal(None, working_dir, None, None)
+            .await
+            .unwrap();
 
         // 启动终端
         service.start_terminal(&terminal_id).await.unwrap();
@@ -508,4 +2159,736 @@ mod tests {
         let session = service.get_terminal_session(&terminal_id).unwrap();
         assert_eq!(session.status, TerminalStatus::Closed);
     }
+
+    #[tokio::test]
+    async fn test_custom_login_shell() {
+        let service = TerminalService::new();
+        let working_dir = env::temp_dir();
+
+        let options = ShellOptions {
+            selection: ShellSelection::Custom {
+                program: "sh".to_string(),
+                args: Vec::new(),
+            },
+            login: true,
+            interactive: false,
+            shell_integration: false,
+        };
+
+        let terminal_id = service
+            .create_terminal(None, working_dir, None, Some(options))
+            .await
+            .unwrap();
+
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.shell.program, "sh");
+        assert!(session.shell.login);
+        assert_eq!(TerminalService::shell_arguments(&session.shell), vec!["-l".to_string()]);
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_default_shell() {
+        let service = TerminalService::new();
+        assert!(!service.get_default_shell().program.is_empty());
+
+        service
+            .set_default_shell(Some("bash".to_string()), vec!["--norc".to_string()])
+            .unwrap();
+
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.shell.program, "bash");
+        assert_eq!(session.shell.args, vec!["--norc".to_string()]);
+
+        assert!(service.set_default_shell(Some(" ".to_string()), Vec::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_terminal_exit_detection() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "exit 3").await.unwrap();
+
+        let mut status = TerminalStatus::Active;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            status = service.get_terminal_session(&terminal_id).unwrap().status;
+            if status != TerminalStatus::Active {
+                break;
+            }
+        }
+
+        assert_eq!(status, TerminalStatus::Error);
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.exit_code, Some(3));
+
+        let output = service.get_terminal_output(&terminal_id).await.unwrap();
+        assert!(output.iter().any(|o| o.content.contains("exited with code 3")));
+        assert!(service.send_command(&terminal_id, "echo hi").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_terminal_auto_restart() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.set_auto_restart(&terminal_id, true).unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "exit 0").await.unwrap();
+
+        let mut restart_count = 0;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            restart_count = service.get_terminal_session(&terminal_id).unwrap().restart_count;
+            if restart_count > 0 {
+                break;
+            }
+        }
+
+        assert_eq!(restart_count, 1);
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Active);
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_signal_interrupts_command() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "sleep 31 &").await.unwrap();
+        service.send_command(&terminal_id, "sleep 30; echo after-sleep").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        service.send_signal(&terminal_id, TerminalSignal::SigInt).await.unwrap();
+        service.send_command(&terminal_id, "echo still-alive").await.unwrap();
+
+        let mut outputs = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            outputs.extend(service.get_terminal_output(&terminal_id).await.unwrap());
+            if outputs.iter().any(|o| o.content == "still-alive") {
+                break;
+            }
+        }
+
+        assert!(outputs.iter().any(|o| o.content == "still-alive"));
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Active);
+
+        // 后台命令不受 Ctrl-C 影响
+        #[cfg(target_os = "linux")]
+        {
+            let tree = service.get_process_tree(&terminal_id).unwrap().unwrap();
+            let commands: Vec<&str> = tree.root.children.iter().map(|p| p.command_line.as_str()).collect();
+            assert!(commands.contains(&"sleep 31"));
+            assert!(!commands.contains(&"sleep 30"));
+        }
+
+        // 没有前台命令时 SIGINT 不会结束shell
+        service.send_signal(&terminal_id, TerminalSignal::SigInt).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Active);
+
+        service.close_terminal(&terminal_id).await.unwrap();
+
+        // 无法设置信号捕获的shell明确返回不支持
+        let options = ShellOptions {
+            selection: ShellSelection::Custom {
+                program: "cat".to_string(),
+                args: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, Some(options))
+            .await
+            .unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+        let error = service.send_signal(&terminal_id, TerminalSignal::SigInt).await.unwrap_err();
+        assert!(error.contains("not supported"));
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_control_keys() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_input(&terminal_id, "echo raw").await.unwrap();
+        service.send_control_key(&terminal_id, ControlKey::Enter).await.unwrap();
+        service.send_control_key(&terminal_id, ControlKey::CtrlD).await.unwrap();
+        service.send_control_key(&terminal_id, ControlKey::Enter).await.unwrap();
+
+        // CtrlD 之后终端仍然可以接收命令
+        service.send_command(&terminal_id, "echo after-eof").await.unwrap();
+        let mut history = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            history = service.get_terminal_history(&terminal_id).unwrap();
+            if history.iter().any(|o| o.content == "after-eof") {
+                break;
+            }
+        }
+
+        assert!(history.iter().any(|o| o.content == "raw"));
+        assert!(history.iter().any(|o| o.content == "after-eof"));
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Active);
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_terminal_history_since() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "echo first").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let chunk = service.get_terminal_history_since(&terminal_id, 0, None).unwrap();
+        assert!(chunk.entries.iter().any(|o| o.content == "first"));
+        let cursor = chunk.next_seq - 1;
+
+        service.send_command(&terminal_id, "echo second").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let chunk = service.get_terminal_history_since(&terminal_id, cursor, None).unwrap();
+        assert!(chunk.entries.iter().all(|o| o.seq > cursor));
+        assert!(chunk.entries.iter().any(|o| o.content == "second"));
+        assert!(!chunk.entries.iter().any(|o| o.content == "first"));
+
+        assert!(service.set_history_limit(&terminal_id, 0).is_err());
+        service.set_history_limit(&terminal_id, 1024).unwrap();
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.history_limit_bytes, 1024);
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_terminal_history() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(Some("dev".to_string()), env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "echo needle-one").await.unwrap();
+        service.send_command(&terminal_id, "echo haystack").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let query = HistorySearchQuery {
+            pattern: "NEEDLE".to_string(),
+            is_regex: false,
+            case_sensitive: false,
+            output_types: Some(vec![OutputType::Stdout]),
+            context_lines: 1,
+            max_results: None,
+        };
+
+        let results = service
+            .search_terminal_history(SearchScope::Terminal { terminal_id: terminal_id.clone() }, query.clone())
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].terminal_name, "dev");
+        assert_eq!(results[0].matches.len(), 1);
+        assert_eq!(results[0].matches[0].entry.content, "needle-one");
+
+        let results = service
+            .search_terminal_history(SearchScope::Workspace { workspace_path: env::temp_dir() }, query)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_terminal_recording_and_replay() {
+        let logs_dir = tempfile::TempDir::new().unwrap();
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        let path = service.start_recording(&terminal_id, logs_dir.path()).unwrap();
+        service.send_command(&terminal_id, "echo recorded").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        service.get_terminal_output(&terminal_id).await.unwrap();
+        assert_eq!(service.stop_recording(&terminal_id).unwrap(), Some(path.clone()));
+        service.close_terminal(&terminal_id).await.unwrap();
+
+        let recordings = list_recordings(logs_dir.path(), Some(&env::temp_dir())).unwrap();
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].terminal_id.as_deref(), Some(terminal_id.as_str()));
+        assert!(list_recordings(logs_dir.path(), Some(std::path::Path::new("/nonexistent")))
+            .unwrap()
+            .is_empty());
+
+        // 只能按文件名回放日志目录中的录制文件
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let resolved = resolve_recording(logs_dir.path(), file_name).unwrap();
+        assert_eq!(resolved, path.canonicalize().unwrap());
+        assert!(resolve_recording(logs_dir.path(), "missing.cast").is_err());
+        let outside = tempfile::NamedTempFile::with_suffix(".cast").unwrap();
+        assert!(resolve_recording(logs_dir.path(), outside.path().to_str().unwrap()).is_err());
+        let relative = format!("../{}", outside.path().file_name().unwrap().to_str().unwrap());
+        assert!(resolve_recording(logs_dir.path(), &relative).is_err());
+
+        let options = ReplayOptions { speed: 100.0, max_idle: Some(0.1) };
+        let mut events = replay_recording(&resolved, options).unwrap();
+        let mut replayed = Vec::new();
+        while let Some(event) = events.recv().await {
+            replayed.push(event);
+        }
+
+        assert!(replayed.iter().any(|e| e.event_type == "i" && e.data == "echo recorded\n"));
+        assert!(replayed.iter().any(|e| e.event_type == "o" && e.data == "recorded\r\n"));
+        assert!(replayed.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[tokio::test]
+    async fn test_shell_integration_commands() {
+        let service = TerminalService::new();
+        let options = ShellOptions {
+            selection: ShellSelection::Custom {
+                program: "sh".to_string(),
+                args: Vec::new(),
+            },
+            ..ShellOptions::default()
+        };
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, Some(options))
+            .await
+            .unwrap();
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "echo block-output").await.unwrap();
+        service.send_command(&terminal_id, "false").await.unwrap();
+
+        let mut commands = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            commands = service.get_terminal_commands(&terminal_id).unwrap();
+            if commands.iter().all(|c| c.status == CommandStatus::Completed) {
+                break;
+            }
+        }
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].command, "echo block-output");
+        assert_eq!(commands[0].exit_code, Some(0));
+        assert_eq!(commands[1].exit_code, Some(1));
+        assert_eq!(commands[1].status, CommandStatus::Completed);
+
+        // 标记不会出现在历史记录中，命令输出位于命令块的范围内
+        let history = service.get_terminal_history(&terminal_id).unwrap();
+        assert!(history.iter().all(|o| !o.content.contains("\u{1b}]133")));
+        let output = history.iter().find(|o| o.content == "block-output").unwrap();
+        assert!(output.seq > commands[0].start_seq);
+        assert!(output.seq <= commands[0].end_seq.unwrap());
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_workspace_terminals() {
+        let service = TerminalService::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        let workspace = TerminalWorkspace {
+            workspace_id: "ws-1".to_string(),
+            workspace_path: temp_dir.path().to_path_buf(),
+            repository_id: Some("repo-1".to_string()),
+            repository_path: Some(PathBuf::from("/repos/app")),
+            branch: Some("feature/login".to_string()),
+        };
+
+        let terminal_id = service
+            .create_workspace_terminal(None, workspace.clone(), None, None)
+            .await
+            .unwrap();
+        let other_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.working_directory, temp_dir.path());
+        assert_eq!(session.workspace, Some(workspace.clone()));
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service
+            .send_command(&terminal_id, "echo \"$WORKHORSE_WORKSPACE_ID:$WORKHORSE_BRANCH:$WORKHORSE_REPO_PATH\"")
+            .await
+            .unwrap();
+
+        let mut found = false;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let history = service.get_terminal_history(&terminal_id).unwrap();
+            if history.iter().any(|o| o.content == "ws-1:feature/login:/repos/app") {
+                found = true;
+                break;
+            }
+        }
+        assert!(found);
+
+        // 出错退出的终端
+        let failed_id = service
+            .create_workspace_terminal(None, workspace.clone(), None, None)
+            .await
+            .unwrap();
+        service.start_terminal(&failed_id).await.unwrap();
+        service.send_command(&failed_id, "exit 3").await.unwrap();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            if service.get_terminal_session(&failed_id).unwrap().status != TerminalStatus::Active {
+                break;
+            }
+        }
+
+        let sessions = service.get_workspace_terminals("ws-1");
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(service.get_active_workspace_terminals("ws-1").len(), 1);
+
+        assert!(service.check_workspace_terminals("ws-1", false).is_err());
+        assert!(service.check_workspace_terminals("ws-1", true).is_ok());
+        let closed = service.close_workspace_terminals("ws-1").await.unwrap();
+        assert_eq!(closed, vec![terminal_id.clone()]);
+        assert!(service.get_active_workspace_terminals("ws-1").is_empty());
+        let failed = service.get_terminal_session(&failed_id).unwrap();
+        assert_eq!(failed.status, TerminalStatus::Error);
+
+        // 不属于工作区的终端不受影响
+        let other = service.get_terminal_session(&other_id).unwrap();
+        assert_eq!(other.status, TerminalStatus::Inactive);
+    }
+
+    #[tokio::test]
+    async fn test_idle_reaper() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+
+        // 警告窗口覆盖整个超时时间，第一次检查即发出警告
+        service
+            .set_limits(TerminalLimits {
+                idle_timeout_minutes: Some(1),
+                idle_warning_seconds: 120,
+                closed_retention_minutes: None,
+                ..TerminalLimits::default()
+            })
+            .unwrap();
+
+        let report = service.reap_terminals().await;
+        assert_eq!(report.warned, vec![terminal_id.clone()]);
+        assert!(report.closed.is_empty());
+        let history = service.get_terminal_history(&terminal_id).unwrap();
+        assert!(history.iter().any(|o| o.content.contains("will be closed in")));
+
+        // 同一次空闲只警告一次
+        let report = service.reap_terminals().await;
+        assert!(report.warned.is_empty());
+
+        service
+            .set_limits(TerminalLimits {
+                idle_timeout_minutes: Some(0),
+                closed_retention_minutes: Some(0),
+                ..TerminalLimits::default()
+            })
+            .unwrap();
+
+        // 没有shell集成时，仍在运行的子进程也使终端保持忙碌
+        #[cfg(target_os = "linux")]
+        {
+            service.send_input(&terminal_id, "sleep 30\n").await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            let report = service.reap_terminals().await;
+            assert!(report.closed.is_empty());
+            service.send_signal(&terminal_id, TerminalSignal::SigInt).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+
+        let report = service.reap_terminals().await;
+        assert_eq!(report.closed, vec![terminal_id.clone()]);
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Closed);
+
+        let report = service.reap_terminals().await;
+        assert_eq!(report.purged, vec![terminal_id.clone()]);
+        assert!(service.get_terminal_session(&terminal_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_workspace_terminal_limit() {
+        let service = TerminalService::new();
+        service
+            .set_limits(TerminalLimits {
+                max_terminals_per_workspace: Some(1),
+                ..TerminalLimits::default()
+            })
+            .unwrap();
+
+        let workspace = TerminalWorkspace {
+            workspace_id: "ws-limit".to_string(),
+            workspace_path: env::temp_dir(),
+            repository_id: None,
+            repository_path: None,
+            branch: None,
+        };
+
+        let first = service
+            .create_workspace_terminal(None, workspace.clone(), None, None)
+            .await
+            .unwrap();
+        assert!(service
+            .create_workspace_terminal(None, workspace.clone(), None, None)
+            .await
+            .is_err());
+
+        // 关闭后不再占用名额
+        service.close_terminal(&first).await.unwrap();
+        assert!(service
+            .create_workspace_terminal(None, workspace, None, None)
+            .await
+            .is_ok());
+
+        assert!(service
+            .set_limits(TerminalLimits {
+                max_terminals: 0,
+                ..TerminalLimits::default()
+            })
+            .is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_terminal_process_tree() {
+        let service = TerminalService::new();
+        let workspace = TerminalWorkspace {
+            workspace_id: "ws-ports".to_string(),
+            workspace_path: env::temp_dir(),
+            repository_id: None,
+            repository_path: None,
+            branch: None,
+        };
+        let terminal_id = service
+            .create_workspace_terminal(None, workspace, None, None)
+            .await
+            .unwrap();
+
+        assert!(service.get_process_tree(&terminal_id).unwrap().is_none());
+
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "sleep 30 &").await.unwrap();
+
+        let mut tree = None;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let current = service.get_process_tree(&terminal_id).unwrap().unwrap();
+            if current.root.children.iter().any(|p| p.command_line == "sleep 30") {
+                tree = Some(current);
+                break;
+            }
+        }
+        let tree = tree.expect("sleep process not found in terminal process tree");
+        let session = service.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(tree.root.command_line.split_whitespace().next(), Some(session.shell.program.as_str()));
+        assert!(service.get_workspace_ports("ws-ports").is_empty());
+
+        service.send_signal(&terminal_id, TerminalSignal::SigKill).await.unwrap();
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multiple_attached_clients() {
+        let service = TerminalService::new();
+        let terminal_id = service
+            .create_terminal(None, env::temp_dir(), None, None)
+            .await
+            .unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+
+        let mut first = service.attach(&terminal_id, Some(0)).unwrap();
+        let second = service.attach(&terminal_id, None).unwrap();
+        assert_eq!(service.get_terminal_session(&terminal_id).unwrap().attached_clients, 2);
+
+        // 从序列号0附加时收到启动消息
+        assert!(first.backlog.entries.iter().any(|o| o.content.contains("started")));
+        assert!(second.backlog.entries.is_empty());
+
+        // 任意客户端都可以输入，所有客户端都收到输出
+        service.send_command(&terminal_id, "echo shared-output").await.unwrap();
+
+        let received = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let output = first.receiver.recv().await.unwrap();
+                if output.content == "shared-output" {
+                    return output;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let mut polled = Vec::new();
+        for _ in 0..50 {
+            polled.extend(service.poll_subscriber(&terminal_id, &second.subscriber_id).unwrap().entries);
+            if polled.iter().any(|o| o.seq == received.seq) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(polled.iter().any(|o| o.content == "shared-output"));
+
+        // 轮询游标按客户端独立维护
+        assert!(service.poll_subscriber(&terminal_id, &second.subscriber_id).unwrap().entries.is_empty());
+
+        service.detach(&terminal_id, &first.subscriber_id).unwrap();
+        assert!(first.detached.await.is_ok());
+        assert!(service.detach(&terminal_id, &first.subscriber_id).is_err());
+        assert_eq!(service.get_terminal_session(&terminal_id).unwrap().attached_clients, 1);
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_workspace_command_history() {
+        use crate::services::command_history::HistoryMatchMode;
+
+        let service = TerminalService::new();
+        service.set_default_shell(Some("sh".to_string()), Vec::new()).unwrap();
+        let workspace = TerminalWorkspace {
+            workspace_id: "ws-history".to_string(),
+            workspace_path: env::temp_dir(),
+            repository_id: None,
+            repository_path: None,
+            branch: None,
+        };
+        let terminal_id = service
+            .create_workspace_terminal(None, workspace, None, None)
+            .await
+            .unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "echo one").await.unwrap();
+        service.send_command(&terminal_id, "false").await.unwrap();
+
+        let history = service.command_history();
+        let mut entries = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            entries = history.search("ws-history", "", HistoryMatchMode::Prefix, None);
+            if entries.iter().all(|e| e.exit_code.is_some()) {
+                break;
+            }
+        }
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "false");
+        assert_eq!(entries[0].exit_code, Some(1));
+        assert_eq!(entries[1].exit_code, Some(0));
+
+        let execution = CommandExecution {
+            command: "ls".to_string(),
+            args: vec!["my dir".to_string()],
+            working_directory: env::temp_dir(),
+            environment: HashMap::new(),
+            timeout_ms: None,
+            stdin: None,
+            use_shell: false,
+            workspace_id: Some("ws-history".to_string()),
+        };
+        service.execute_command(execution).await.unwrap();
+
+        let entries = history.search("ws-history", "ls", HistoryMatchMode::Prefix, None);
+        assert_eq!(entries[0].command, "ls 'my dir'");
+        assert_ne!(entries[0].exit_code, Some(0));
+
+        service.close_terminal(&terminal_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_interrupted_terminal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let journal = Arc::new(ExecutionJournal::new(temp_dir.path().to_path_buf()));
+        let service = TerminalService::new().with_journal(journal);
+        service.set_default_shell(Some("sh".to_string()), Vec::new()).unwrap();
+
+        let terminal_id = service
+            .create_terminal(Some("dev".to_string()), env::temp_dir(), None, None)
+            .await
+            .unwrap();
+        service.start_terminal(&terminal_id).await.unwrap();
+        service.send_command(&terminal_id, "echo before-crash").await.unwrap();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let history = service.get_terminal_history(&terminal_id).unwrap();
+            if history.iter().any(|o| o.content == "before-crash") {
+                break;
+            }
+        }
+        // 输出日志由后台任务写入
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        // 不关闭终端，模拟应用崩溃后重新启动
+        let journal = Arc::new(ExecutionJournal::new(temp_dir.path().to_path_buf()));
+        let interrupted = journal.interrupted();
+        assert_eq!(interrupted.len(), 1);
+        // 上一个“进程”中的shell仍在运行，被识别为遗留进程；其他平台无法检测
+        #[cfg(target_os = "linux")]
+        assert!(!interrupted[0].orphans.is_empty());
+        #[cfg(not(target_os = "linux"))]
+        assert!(interrupted[0].orphans_error.is_some());
+
+        let recovered = TerminalService::new().with_journal(journal.clone());
+        recovered.restore_interrupted(&interrupted);
+        let session = recovered.get_terminal_session(&terminal_id).unwrap();
+        assert_eq!(session.status, TerminalStatus::Interrupted);
+        assert_eq!(session.name, "dev");
+        let history = recovered.get_terminal_history(&terminal_id).unwrap();
+        assert!(history.iter().any(|o| o.content == "before-crash"));
+
+        service.close_terminal(&terminal_id).await.unwrap();
+
+        // 重新启动后中断记录失效
+        recovered.restart_interrupted(&interrupted[0]).await.unwrap();
+        assert_eq!(recovered.get_terminal_session(&terminal_id).unwrap().status, TerminalStatus::Active);
+        assert!(journal.interrupted().is_empty());
+        recovered.close_terminal(&terminal_id).await.unwrap();
+    }
 }
\ No newline at end of file
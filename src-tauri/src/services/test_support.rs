@@ -0,0 +1,42 @@
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+
+use crate::services::git_service::{CommitOptions, GitService};
+
+/// 初始化仓库并配置提交者
+pub fn init_repo(repo_path: &Path) -> Repository {
+    let repo = GitService::init_repository(repo_path, false).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    repo
+}
+
+/// 初始化仓库并配置提交者，提交一个包含 `file.txt` 的初始版本
+pub fn init_repo_with_commit(repo_path: &Path) -> Repository {
+    let repo = init_repo(repo_path);
+    commit_file(repo_path, "file.txt", "line 1\nline 2\nline 3\n", "Initial commit");
+    repo
+}
+
+/// 写入文件并提交，返回提交ID
+pub fn commit_file(repo_path: &Path, file: &str, content: &str, message: &str) -> String {
+    fs::write(repo_path.join(file), content).unwrap();
+    GitService::stage_paths(repo_path, &[file.to_string()]).unwrap();
+    commit_staged(repo_path, message)
+}
+
+/// 暂存工作目录的所有改动并提交，返回提交ID
+pub fn commit_all(repo_path: &Path, message: &str) -> String {
+    GitService::stage_paths(repo_path, &[".".to_string()]).unwrap();
+    commit_staged(repo_path, message)
+}
+
+fn commit_staged(repo_path: &Path, message: &str) -> String {
+    GitService::commit(repo_path, &CommitOptions {
+        message: message.to_string(),
+        ..Default::default()
+    })
+    .unwrap()
+}
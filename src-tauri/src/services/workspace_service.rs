@@ -4,6 +4,8 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
 use crate::services::{GitService, RepositoryManagerService};
+use crate::services::git_remote::{PullResult, PullStrategy};
+use crate::services::git_service::WorktreeOptions;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum WorkspaceStatus {
@@ -38,6 +40,8 @@ pub struct CreateWorkspaceRequest {
     pub description: Option<String>,
     pub tags: Vec<String>,
     pub base_path: Option<PathBuf>,  // 工作区的基础路径，如果不指定则使用默认位置
+    #[serde(default)]
+    pub start_point: Option<String>,  // 新分支的起点，可以是远程分支、标签或提交，默认为HEAD
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,11 +94,15 @@ impl WorkspaceManagerService {
 
         // 创建Git worktree
         let worktree_name = format!("ws-{}", &workspace_id[..8]);
-        GitService::create_worktree(
+        let worktree = GitService::create_worktree_with_options(
             repo_path,
             &worktree_name,
             &workspace_path,
-            request.branch.as_deref(),
+            &WorktreeOptions {
+                branch: request.branch.clone(),
+                start_point: request.start_point.clone(),
+                detach: false,
+            },
         )?;
 
         let now = chrono::Utc::now();
@@ -103,7 +111,8 @@ impl WorkspaceManagerService {
             name: request.name,
             repository_path: repo_path.to_path_buf(),
             workspace_path,
-            branch: request.branch,
+            // 未指定分支时记录实际检出的分支
+            branch: worktree.branch.or(request.branch),
             status: WorkspaceStatus::Active,
             created_at: now,
             updated_at: now,
@@ -379,6 +388,46 @@ impl WorkspaceManagerService {
         Ok(metadata)
     }
 
+    /// 将源分支的更新合并或变基到工作区分支，未指定源分支时使用仓库的默认分支。
+    /// 产生冲突时工作区停留在合并或变基中，由 GitService 的 continue/abort 操作完成
+    pub fn update_from_source(
+        repo_path: &Path,
+        workspace_id: &str,
+        source_branch: Option<&str>,
+        strategy: PullStrategy,
+    ) -> Result<PullResult> {
+        let metadata = Self::load_workspace_metadata(repo_path, workspace_id)?;
+        if metadata.status == WorkspaceStatus::Archived {
+            return Err(anyhow!("工作区已归档，无法更新"));
+        }
+
+        let source = match source_branch {
+            Some(branch) => branch.to_string(),
+            None => RepositoryManagerService::load_repository_config(repo_path)?
+                .default_branch
+                .ok_or_else(|| anyhow!("仓库未配置默认分支"))?,
+        };
+
+        GitService::update_from_branch(&metadata.workspace_path, &source, strategy)
+    }
+
+    /// 重命名分支，并更新引用该分支的工作区元数据
+    pub fn rename_branch(repo_path: &Path, old_name: &str, new_name: &str) -> Result<Vec<WorkspaceMetadata>> {
+        GitService::rename_branch(repo_path, old_name, new_name, false)?;
+
+        let mut updated = Vec::new();
+        for workspace in Self::list_workspaces(repo_path)? {
+            if workspace.branch.as_deref() == Some(old_name) {
+                updated.push(Self::update_workspace_metadata(repo_path, &workspace.id, |metadata| {
+                    metadata.branch = Some(new_name.to_string());
+                    Ok(())
+                })?);
+            }
+        }
+
+        Ok(updated)
+    }
+
     /// 更新工作区元数据
     pub fn update_workspace_metadata(
         repo_path: &Path,
@@ -542,13 +591,16 @@ mod tests {
     use tempfile::TempDir;
     use crate::services::{GitService, RepositoryManagerService};
     use crate::services::repository_service::AddRepositoryRequest;
+    use crate::services::test_support::{commit_file, init_repo};
 
     fn setup_test_repo() -> (TempDir, PathBuf) {
         let temp_dir = TempDir::new().unwrap();
         let repo_path = temp_dir.path().to_path_buf();
 
-        // 初始化Git仓库
-        GitService::init_repository(&repo_path, false).unwrap();
+        // 初始化Git仓库，在 main 分支上创建初始提交
+        let repo = init_repo(&repo_path);
+        repo.set_head("refs/heads/main").unwrap();
+        commit_file(&repo_path, "README.md", "test\n", "Initial commit");
 
         // 添加到Workhorse管理
         let request = AddRepositoryRequest {
@@ -570,10 +622,11 @@ mod tests {
         let request = CreateWorkspaceRequest {
             name: "test-workspace".to_string(),
             repository_path: repo_path.clone(),
-            branch: Some("main".to_string()),
+            branch: Some("feature".to_string()),
             description: Some("Test workspace".to_string()),
             tags: vec!["test".to_string(), "feature".to_string()],
             base_path: None,
+            start_point: None,
         };
 
         let metadata = WorkspaceManagerService::create_workspace(&repo_path, request).unwrap();
@@ -582,6 +635,22 @@ mod tests {
         assert_eq!(metadata.status, WorkspaceStatus::Active);
         assert!(metadata.workspace_path.exists());
         assert_eq!(metadata.tags.len(), 2);
+        assert_eq!(metadata.branch, Some("feature".to_string()));
+        let worktree = GitService::open_repository(&metadata.workspace_path).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("feature"));
+
+        // main 已在主仓库检出
+        let request = CreateWorkspaceRequest {
+            name: "on-main".to_string(),
+            repository_path: repo_path.clone(),
+            branch: Some("main".to_string()),
+            description: None,
+            tags: Vec::new(),
+            base_path: None,
+            start_point: None,
+        };
+        let error = WorkspaceManagerService::create_workspace(&repo_path, request).unwrap_err();
+        assert!(error.to_string().contains("already checked out"));
     }
 
     #[test]
@@ -596,6 +665,7 @@ mod tests {
             description: None,
             tags: Vec::new(),
             base_path: None,
+            start_point: None,
         };
 
         let metadata = WorkspaceManagerService::create_workspace(&repo_path, request).unwrap();
@@ -630,6 +700,7 @@ mod tests {
             description: None,
             tags: vec!["initial".to_string()],
             base_path: None,
+            start_point: None,
         };
 
         let metadata = WorkspaceManagerService::create_workspace(&repo_path, request).unwrap();
@@ -643,4 +714,27 @@ mod tests {
         let updated = WorkspaceManagerService::remove_workspace_tag(&repo_path, &workspace_id, "initial").unwrap();
         assert!(!updated.tags.contains(&"initial".to_string()));
     }
+
+    #[test]
+    fn test_rename_workspace_branch() {
+        let (_temp_dir, repo_path) = setup_test_repo();
+
+        let request = CreateWorkspaceRequest {
+            name: "rename".to_string(),
+            repository_path: repo_path.clone(),
+            branch: Some("old-branch".to_string()),
+            description: None,
+            tags: Vec::new(),
+            base_path: None,
+            start_point: None,
+        };
+        let metadata = WorkspaceManagerService::create_workspace(&repo_path, request).unwrap();
+
+        let updated = WorkspaceManagerService::rename_branch(&repo_path, "old-branch", "new-branch").unwrap();
+        assert_eq!(updated.len(), 1);
+        let reloaded = WorkspaceManagerService::load_workspace_metadata(&repo_path, &metadata.id).unwrap();
+        assert_eq!(reloaded.branch, Some("new-branch".to_string()));
+        let worktree = GitService::open_repository(&metadata.workspace_path).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("new-branch"));
+    }
 }
\ No newline at end of file